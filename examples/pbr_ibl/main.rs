@@ -1,10 +1,13 @@
 use bevy_math::{Quat, Vec3};
 use mo_core::App;
-use mo_ecs::resource::{GlobalSamplers, IBLResource};
+use bevy_ecs::prelude::IntoSystemConfigs;
+use mo_ecs::resource::{Console, DebugUi, GlobalSamplers, IBLResource, InputRecorder};
 use mo_ecs::{
+    color::{EColorSpace, FColor},
     component::{DirectionalLight, Transform},
     model::Model,
     resource::{Camera, DefaultTextures, Input, Timer},
+    system::load_dropped_files,
 };
 use std::f32::consts::PI;
 use winit::event_loop::EventLoop;
@@ -13,16 +16,30 @@ fn main() {
     let event_loop = EventLoop::new().unwrap();
     let mut app = App::new(&event_loop, Default::default());
 
-    // app.init_resource::<AssetManager>();
     app.init_resource::<Timer>();
     app.init_resource::<Input>();
+    app.init_resource::<InputRecorder>();
+    app.init_resource::<Console>();
+    app.init_resource::<DebugUi>();
 
     app.init_resource::<DefaultTextures>();
     app.init_resource::<IBLResource>();
     app.init_resource::<GlobalSamplers>();
 
-    app.add_runtime_system(Timer::update_timer);
-    app.add_runtime_system(Camera::update_camera);
+    app.add_startup_system(Console::run_startup_script);
+    app.add_runtime_system(
+        (
+            InputRecorder::replay_input,
+            Timer::update_timer,
+            InputRecorder::record_input,
+            Console::update_console,
+            DebugUi::toggle_visibility,
+            DebugUi::inspector,
+            load_dropped_files,
+            Camera::update_camera,
+        )
+            .chain(),
+    );
 
     let camera = Camera::new(
         Vec3::new(-3.0, 0.0, 3.0),
@@ -30,21 +47,21 @@ fn main() {
     );
     app.insert_resource::<Camera>(camera);
 
-    app.add_entity((
-        Transform::from_xyz(0.0, 0.0, 0.0),
-        Model::load_gltf("DamagedHelmet.glb"),
-    ));
+    let monkey = app.load_asset::<Model>("monkey.glb");
+    app.add_entity((Transform::from_xyz(0.0, 0.0, 0.0), monkey));
 
     app.add_entity((DirectionalLight::new(
         Transform::from_xyz(10.0, 10.0, 10.0).looking_at(Vec3::ZERO, Vec3::Y),
-        Vec3::new(1.0, 1.0, 1.0),
+        FColor::white(),
+        EColorSpace::Linear,
         4.0,
         true,
     ),));
 
     app.add_entity((DirectionalLight::new(
         Transform::from_xyz(-10.0, 10.0, 10.0).looking_at(Vec3::ZERO, Vec3::Y),
-        Vec3::new(1.0, 1.0, 1.0),
+        FColor::white(),
+        EColorSpace::Linear,
         2.0,
         false,
     ),));
@@ -1,10 +1,27 @@
-use crate::application::plugin::{PluginState, PluginTrait};
+use crate::{
+    application::plugin::{PluginState, PluginTrait},
+    logging::{LogError, LogSettings},
+};
 use anyhow::Result;
 use bevy_ecs::prelude::*;
-use mo_ecs::resource::{Camera, Input};
-use mo_renderpass::{RenderPassTrait, GBufferPass, PresentPass, SSAOPass, ShadingPass, ShadowPass};
-use mo_vk::{VulkanoWindows, WindowDescriptor, VULKAN};
-use std::{cell::RefCell, sync::Arc, time::Duration};
+use egui_winit::egui::ViewportId;
+use mo_ecs::{
+    asset::{Asset, AssetServer, Handle},
+    resource::{
+        Camera, DebugUi, DefaultTextures, FramePacing, GlobalSamplers, IBLResource, Input,
+    },
+    system::{propagate_transforms, spawn_scene_nodes, sync_material_variants, sync_scene_lights},
+};
+use mo_renderpass::{
+    GBufferPass, PresentPass, RenderError, RenderPassTrait, SSAOPass, ShadingPass, ShadowPass,
+    SkinningPass, TransmissionPass, UiPass,
+};
+use mo_vk::{VulkanoConfig, VulkanoWindows, WindowDescriptor, VULKAN};
+use std::{
+    cell::RefCell,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use thiserror::Error;
 use vulkano::{
     command_buffer::{AutoCommandBufferBuilder, CommandBufferUsage},
@@ -13,8 +30,8 @@ use vulkano::{
 };
 use winit::{
     application::ApplicationHandler,
-    event::{DeviceEvent, DeviceId, WindowEvent},
-    event_loop::{ActiveEventLoop, EventLoop},
+    event::{DeviceEvent, DeviceId, ElementState, KeyEvent, WindowEvent},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
     window::WindowId,
 };
 
@@ -24,6 +41,7 @@ pub struct App {
     pub world: RefCell<World>,
     startup_schedule: Schedule,
     runtime_schedule: Schedule,
+    frame_end_schedule: Schedule,
     plugin_state: PluginState,
     plugins: Vec<Box<dyn PluginTrait>>,
 
@@ -34,6 +52,9 @@ pub struct App {
 
     // Render pass
     render_passes: RefCell<Vec<Box<dyn RenderPassTrait>>>,
+
+    // Translates window events for the debug UI, exists when the world has a [`DebugUi`]
+    debug_ui_state: Option<egui_winit::State>,
 }
 
 #[derive(Debug, Error)]
@@ -43,17 +64,32 @@ pub enum AppError {
 }
 
 impl App {
-    pub fn new(_event_loop: &EventLoop<()>, window_descriptor: WindowDescriptor) -> Self {
-        tracing_subscriber::fmt()
-            .with_max_level(tracing::Level::INFO)
-            .init();
+    /// Constructs the application with the default [`LogSettings`]
+    pub fn new(event_loop: &EventLoop<()>, window_descriptor: WindowDescriptor) -> Self {
+        Self::with_log_settings(event_loop, window_descriptor, Some(LogSettings::default()))
+    }
+
+    /// Constructs the application with custom logging. With `None` no subscriber is installed.
+    pub fn with_log_settings(
+        _event_loop: &EventLoop<()>,
+        window_descriptor: WindowDescriptor,
+        log_settings: Option<LogSettings>,
+    ) -> Self {
+        match log_settings.map(|settings| settings.init()) {
+            // Another app or the embedding application installed a subscriber, keep using it.
+            Some(Err(LogError::AlreadyInitialized(_))) => {
+                tracing::debug!("Context - Tracing subscriber already installed.")
+            }
+            Some(Err(err)) => eprintln!("Failed to initialize logging: {err}"),
+            _ => {}
+        }
 
         // Manages any windows and their rendering.
         let windows = VulkanoWindows::default();
 
         // Some little debug infos.
-        println!(
-            "Using device: {} (type: {:?})",
+        tracing::info!(
+            "Context - Using device: {} (type: {:?})",
             VULKAN.device().physical_device().properties().device_name,
             VULKAN.device().physical_device().properties().device_type,
         );
@@ -65,16 +101,37 @@ impl App {
             Default::default(),
         ));
 
+        let mut world = World::new();
+        world.init_resource::<FramePacing>();
+        world.init_resource::<AssetServer>();
+
+        // Engine systems which must run after all user runtime systems.
+        let mut frame_end_schedule = Schedule::default();
+        frame_end_schedule.add_systems(
+            (
+                AssetServer::update_loading_assets,
+                spawn_scene_nodes,
+                sync_material_variants,
+                propagate_transforms,
+                sync_scene_lights,
+                FramePacing::detect_changes,
+                AssetServer::free_unused_assets,
+            )
+                .chain(),
+        );
+
         Self {
-            world: RefCell::new(World::new()),
+            world: RefCell::new(world),
             startup_schedule: Schedule::default(),
             runtime_schedule: Schedule::default(),
+            frame_end_schedule,
             plugin_state: PluginState::Adding,
             plugins: Vec::new(),
             windows,
             window_descriptor,
             descriptor_set_allocator,
             render_passes: RefCell::new(Vec::new()),
+            debug_ui_state: None,
         }
     }
 
@@ -91,7 +148,7 @@ impl App {
         self
     }
 
-    pub fn insert_resource<R: Resource + FromWorld>(&mut self, resource: R) -> &mut Self {
+    pub fn insert_resource<R: Resource>(&mut self, resource: R) -> &mut Self {
         self.world.borrow_mut().insert_resource(resource);
         self
     }
@@ -115,25 +172,46 @@ impl App {
 
     pub fn run_runtime_systems(&mut self) {
         self.runtime_schedule.run(&mut self.world.borrow_mut());
+        self.frame_end_schedule.run(&mut self.world.borrow_mut());
     }
 
     pub fn add_entity<B: Bundle>(&mut self, entity: B) -> Entity {
         self.world.borrow_mut().spawn(entity).id()
     }
 
+    /// Loads an asset in the background through the [`AssetServer`], see [`AssetServer::load`]
+    pub fn load_asset<T: Asset>(&mut self, path: &str) -> Handle<T> {
+        self.world.borrow_mut().resource_mut::<AssetServer>().load(path)
+    }
+
+    /// Returns the loaded asset of `handle`, see [`AssetServer::get_shared`]
+    pub fn get_asset<T: Asset>(&self, handle: &Handle<T>) -> Option<Arc<T>> {
+        self.world
+            .borrow()
+            .resource::<AssetServer>()
+            .get_shared(handle)
+            .cloned()
+    }
+
     pub fn window_descriptor(&self) -> &WindowDescriptor {
         &self.window_descriptor
     }
-}
 
-impl ApplicationHandler for App {
-    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+    /// Creates the primary window and the render passes drawing into it.
+    fn create_window_and_passes(&mut self, event_loop: &ActiveEventLoop) {
         if let Some(primary_window_id) = self.windows.primary_window_id() {
             self.windows.remove_renderer(primary_window_id);
         }
+        self.render_passes.borrow_mut().clear();
 
-        self.windows
-            .create_window(event_loop, &VULKAN, &Default::default(), |_| {});
+        self.windows.create_window(
+            event_loop,
+            &VULKAN.get(),
+            &self.window_descriptor,
+            |_| {},
+        );
+
+        let skinning_pass = SkinningPass::new();
 
         let shadow_pass = ShadowPass::new();
 
@@ -157,6 +235,26 @@ impl ApplicationHandler for App {
             self.descriptor_set_allocator.clone(),
         );
 
+        let transmission_pass = TransmissionPass::new(
+            &self.world,
+            &gbuffer_pass.gbuffer_textures,
+            shading_pass.output_image.clone(),
+            self.windows.get_primary_renderer().unwrap(),
+            self.descriptor_set_allocator.clone(),
+        );
+
+        let ui_pass = self
+            .world
+            .borrow()
+            .contains_resource::<DebugUi>()
+            .then(|| {
+                UiPass::new(
+                    &self.world,
+                    self.windows.get_primary_renderer().unwrap(),
+                    shading_pass.output_image.clone(),
+                )
+            });
+
         let present_pass = PresentPass::new(
             &self.world,
             self.windows.get_primary_renderer().unwrap(),
@@ -164,12 +262,238 @@ impl ApplicationHandler for App {
             shading_pass.output_image.clone(),
         );
 
+        self.add_render_pass(Box::new(skinning_pass));
         self.add_render_pass(Box::new(shadow_pass));
         self.add_render_pass(Box::new(gbuffer_pass));
         self.add_render_pass(Box::new(ssao_pass));
         self.add_render_pass(Box::new(shading_pass));
+        self.add_render_pass(Box::new(transmission_pass));
+        if let Some(ui_pass) = ui_pass {
+            self.add_render_pass(Box::new(ui_pass));
+        }
         self.add_render_pass(Box::new(present_pass));
 
+        self.debug_ui_state = self.world.borrow().get_resource::<DebugUi>().map(|debug_ui| {
+            // The new UI pass has no textures yet.
+            debug_ui.reset_textures();
+
+            let window = self.windows.get_primary_renderer().unwrap().window();
+            egui_winit::State::new(
+                debug_ui.context().clone(),
+                ViewportId::ROOT,
+                event_loop,
+                Some(window.scale_factor() as f32),
+                window.theme(),
+                Some(
+                    VULKAN
+                        .device()
+                        .physical_device()
+                        .properties()
+                        .max_image_dimension2_d as usize,
+                ),
+            )
+        });
+    }
+
+    /// Starts a debug UI frame with the window events received since the last frame.
+    fn begin_debug_ui_frame(&mut self) {
+        let (Some(state), Some(window_renderer)) = (
+            self.debug_ui_state.as_mut(),
+            self.windows.get_primary_renderer(),
+        ) else {
+            return;
+        };
+
+        let raw_input = state.take_egui_input(window_renderer.window());
+        if let Some(mut debug_ui) = self.world.borrow_mut().get_resource_mut::<DebugUi>() {
+            debug_ui.begin_frame(raw_input);
+        }
+    }
+
+    /// Finishes the debug UI frame after the runtime systems drew their panels.
+    fn end_debug_ui_frame(&mut self) {
+        let (Some(state), Some(window_renderer)) = (
+            self.debug_ui_state.as_mut(),
+            self.windows.get_primary_renderer(),
+        ) else {
+            return;
+        };
+
+        let mut world = self.world.borrow_mut();
+        let Some(mut debug_ui) = world.get_resource_mut::<DebugUi>() else {
+            return;
+        };
+
+        let platform_output = debug_ui.end_frame();
+        let repaint = debug_ui.context().has_requested_repaint();
+        state.handle_platform_output(window_renderer.window(), platform_output);
+
+        // Animations such as collapsing headers need the following frames too.
+        if repaint {
+            world.resource_mut::<FramePacing>().request_redraw();
+        }
+    }
+
+    /// Records and submits all render passes for the primary window.
+    fn draw_frame(&mut self) -> Result<(), RenderError> {
+        let window_renderer = self.windows.get_primary_renderer_mut().unwrap();
+
+        let window_size = window_renderer.window().inner_size();
+
+        // Do not draw the frame when the screen size is zero. On Windows, this can
+        // occur when minimizing the application.
+        if window_size.width == 0 || window_size.height == 0 {
+            return Ok(());
+        }
+
+        for render_pass in self.render_passes.borrow_mut().iter_mut() {
+            render_pass.prepare(&mut self.world.borrow_mut())?;
+        }
+
+        // Begin rendering by acquiring the gpu future from the window renderer.
+        let previous_frame_end =
+            window_renderer.acquire(Some(Duration::from_millis(1000)), |swapchain_images| {
+                // When window size changes, we need to resize the camera.
+                self.world
+                    .borrow_mut()
+                    .resource_mut::<Camera>()
+                    .resize(window_size.into());
+
+                // Whenever the window resizes we need to recreate everything dependent on the window size.
+                // In this example that includes the swapchain, the framebuffers and the dynamic state viewport.
+                for render_pass in self.render_passes.borrow_mut().iter_mut() {
+                    render_pass.on_swapchain_recreate(swapchain_images, window_size);
+                }
+            })?;
+
+        let image_index = window_renderer.image_index();
+        let future = match self.record_frame(image_index, previous_frame_end) {
+            Ok(future) => future,
+            Err(err) => {
+                // The acquired image is never presented, only recreating the swapchain gets it back.
+                self.windows.get_primary_renderer_mut().unwrap().resize();
+                return Err(err);
+            }
+        };
+
+        // The color output is now expected to contain our triangle. But in order to show
+        // it on the screen, we have to *present* the image by calling `present` on the
+        // window renderer.
+        //
+        // This function does not actually present the image immediately. Instead, it
+        // submits a present command at the end of the queue. This means that it will only
+        // be presented once the GPU has finished executing the command buffer that draws
+        // the triangle.
+        self.windows
+            .get_primary_renderer_mut()
+            .unwrap()
+            .present(future, false)?;
+
+        Ok(())
+    }
+
+    /// Records all render passes into a command buffer executed after `previous_frame_end`.
+    fn record_frame(
+        &self,
+        image_index: u32,
+        previous_frame_end: Box<dyn GpuFuture>,
+    ) -> Result<Box<dyn GpuFuture>, RenderError> {
+        // In order to draw, we have to record a *command buffer*. The command buffer
+        // object holds the list of commands that are going to be executed.
+        //
+        // Recording a command buffer is an expensive operation (usually a few hundred
+        // microseconds), but it is known to be a hot path in the driver and is expected to
+        // be optimized.
+        //
+        // Note that we have to pass a queue family when we create the command buffer. The
+        // command buffer will only be executable on that given queue family.
+        let mut builder = AutoCommandBufferBuilder::primary(
+            VULKAN.command_buffer_allocator().clone(),
+            VULKAN.graphics_queue().queue_family_index(),
+            CommandBufferUsage::OneTimeSubmit,
+        )?;
+
+        for render_pass in self.render_passes.borrow_mut().iter_mut() {
+            render_pass.render(
+                image_index,
+                &self.world.borrow(),
+                self.descriptor_set_allocator.clone(),
+                &mut builder,
+            )?;
+        }
+
+        // Finish recording the command buffer by calling `end`.
+        let command_buffer = builder.build()?;
+
+        Ok(previous_frame_end
+            .then_execute(VULKAN.graphics_queue().clone(), command_buffer)?
+            .boxed())
+    }
+
+    /// Replaces the lost device with a new Vulkan context and recreates every GPU resource.
+    fn recover_from_device_lost(&mut self, event_loop: &ActiveEventLoop) {
+        tracing::error!("Render - Device lost, recreating the Vulkan context and GPU resources.");
+
+        // Drop everything holding on to the old device first.
+        self.render_passes.borrow_mut().clear();
+        if let Some(primary_window_id) = self.windows.primary_window_id() {
+            self.windows.remove_renderer(primary_window_id);
+        }
+
+        VULKAN.recreate(VulkanoConfig::default());
+        self.descriptor_set_allocator = Arc::new(StandardDescriptorSetAllocator::new(
+            VULKAN.device().clone(),
+            Default::default(),
+        ));
+
+        recreate_gpu_resources(&mut self.world.borrow_mut());
+        self.create_window_and_passes(event_loop);
+        self.world
+            .borrow_mut()
+            .resource_mut::<FramePacing>()
+            .request_redraw();
+    }
+}
+
+/// Returns true for key and mouse button releases
+fn is_release_event(event: &WindowEvent) -> bool {
+    matches!(
+        event,
+        WindowEvent::KeyboardInput {
+            event: KeyEvent {
+                state: ElementState::Released,
+                ..
+            },
+            ..
+        } | WindowEvent::MouseInput {
+            state: ElementState::Released,
+            ..
+        }
+    )
+}
+
+/// Recreates the GPU data stored in the world after the Vulkan context was replaced.
+fn recreate_gpu_resources(world: &mut World) {
+    if world.contains_resource::<DefaultTextures>() {
+        world.insert_resource(DefaultTextures::new());
+    }
+    if world.contains_resource::<GlobalSamplers>() {
+        world.insert_resource(GlobalSamplers::default());
+    }
+    if let Some(name) = world
+        .get_resource::<IBLResource>()
+        .map(|ibl| ibl.name.clone())
+    {
+        world.insert_resource(IBLResource::new(&name));
+    }
+
+    world.resource_mut::<AssetServer>().reload_all();
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        self.create_window_and_passes(event_loop);
+
         self.run_startup_systems();
 
         tracing::info!("Runtime - Starting render loop.")
@@ -181,11 +505,36 @@ impl ApplicationHandler for App {
         _window_id: WindowId,
         event: WindowEvent,
     ) {
-        // We first handle the window event.
-        self.world
-            .borrow_mut()
-            .resource_mut::<Input>()
-            .on_window_event(&event);
+        // The debug UI sees the event first and keeps the ones it uses from the other systems,
+        // so the camera does not move while typing in a panel.
+        let consumed_by_ui = match (
+            self.debug_ui_state.as_mut(),
+            self.windows.get_primary_renderer(),
+        ) {
+            (Some(state), Some(window_renderer)) => {
+                state
+                    .on_window_event(window_renderer.window(), &event)
+                    .consumed
+            }
+            _ => false,
+        };
+
+        // Then we handle the window event. Releases always pass, otherwise a button pressed
+        // before the UI took the input would stay held.
+        if !consumed_by_ui || is_release_event(&event) {
+            self.world
+                .borrow_mut()
+                .resource_mut::<Input>()
+                .on_window_event(&event);
+        }
+
+        // Any window event other than the redraw itself may change what is on screen.
+        if !matches!(event, WindowEvent::RedrawRequested) {
+            self.world
+                .borrow_mut()
+                .resource_mut::<FramePacing>()
+                .request_redraw();
+        }
 
         match event {
             WindowEvent::CloseRequested => {
@@ -194,77 +543,34 @@ impl ApplicationHandler for App {
             WindowEvent::Resized(_) => {
                 self.windows.get_primary_renderer_mut().unwrap().resize();
             }
+            WindowEvent::Focused(focused) => {
+                self.world
+                    .borrow_mut()
+                    .resource_mut::<FramePacing>()
+                    .set_focused(focused);
+            }
             WindowEvent::RedrawRequested => {
+                self.world
+                    .borrow_mut()
+                    .resource_mut::<FramePacing>()
+                    .begin_frame();
+                self.begin_debug_ui_frame();
                 self.run_runtime_systems();
-                let window_renderer = self.windows.get_primary_renderer_mut().unwrap();
-
-                let window_size = window_renderer.window().inner_size();
-
-                // Do not draw the frame when the screen size is zero. On Windows, this can
-                // occur when minimizing the application.
-                if window_size.width == 0 || window_size.height == 0 {
-                    return;
-                }
-
-                // Begin rendering by acquiring the gpu future from the window renderer.
-                let previous_frame_end = window_renderer
-                    .acquire(Some(Duration::from_millis(1000)), |swapchain_images| {
-                        // When window size changes, we need to resize the camera.
-                        self.world
-                            .borrow_mut()
-                            .resource_mut::<Camera>()
-                            .resize(window_size.into());
-
-                        // Whenever the window resizes we need to recreate everything dependent on the window size.
-                        // In this example that includes the swapchain, the framebuffers and the dynamic state viewport.
-                        for render_pass in self.render_passes.borrow_mut().iter_mut() {
-                            render_pass.on_swapchain_recreate(swapchain_images, window_size);
-                        }
-                    })
-                    .unwrap();
-
-                // In order to draw, we have to record a *command buffer*. The command buffer
-                // object holds the list of commands that are going to be executed.
-                //
-                // Recording a command buffer is an expensive operation (usually a few hundred
-                // microseconds), but it is known to be a hot path in the driver and is expected to
-                // be optimized.
-                //
-                // Note that we have to pass a queue family when we create the command buffer. The
-                // command buffer will only be executable on that given queue family.
-                let mut builder = AutoCommandBufferBuilder::primary(
-                    VULKAN.command_buffer_allocator().clone(),
-                    VULKAN.graphics_queue().queue_family_index(),
-                    CommandBufferUsage::OneTimeSubmit,
-                )
-                .unwrap();
-
-                for render_pass in self.render_passes.borrow_mut().iter_mut() {
-                    render_pass.render(
-                        window_renderer.image_index(),
-                        &self.world.borrow(),
-                        self.descriptor_set_allocator.clone(),
-                        &mut builder,
-                    );
+                self.end_debug_ui_frame();
+                // Events are consumed by the runtime systems of this frame only.
+                self.world
+                    .borrow_mut()
+                    .resource_mut::<Input>()
+                    .clear_events();
+
+                match self.draw_frame() {
+                    Ok(()) => {}
+                    // The swapchain is recreated when the next frame is acquired.
+                    Err(err) if err.is_out_of_date() => {}
+                    Err(err) if err.is_device_lost() => self.recover_from_device_lost(event_loop),
+                    // Errors after acquiring an image recreate the swapchain in `draw_frame`.
+                    Err(err) => tracing::warn!("Render - Skipping frame: {err}"),
                 }
-
-                // Finish recording the command buffer by calling `end`.
-                let command_buffer = builder.build().unwrap();
-
-                let future = previous_frame_end
-                    .then_execute(VULKAN.graphics_queue().clone(), command_buffer)
-                    .unwrap()
-                    .boxed();
-
-                // The color output is now expected to contain our triangle. But in order to show
-                // it on the screen, we have to *present* the image by calling `present` on the
-                // window renderer.
-                //
-                // This function does not actually present the image immediately. Instead, it
-                // submits a present command at the end of the queue. This means that it will only
-                // be presented once the GPU has finished executing the command buffer that draws
-                // the triangle.
-                window_renderer.present(future, false);
             }
             _ => {}
         }
@@ -282,7 +588,28 @@ impl ApplicationHandler for App {
             .on_device_event(&event);
     }
 
-    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let (needs_redraw, next_frame_at) = {
+            let world = self.world.borrow();
+            let pacing = world.resource::<FramePacing>();
+            (pacing.needs_redraw(), pacing.next_frame_at())
+        };
+
+        // Sleep until an event arrives, nothing has changed on screen.
+        if !needs_redraw {
+            event_loop.set_control_flow(ControlFlow::Wait);
+            return;
+        }
+
+        // Sleep until the frame rate limit allows the next frame.
+        if let Some(next_frame_at) = next_frame_at {
+            if Instant::now() < next_frame_at {
+                event_loop.set_control_flow(ControlFlow::WaitUntil(next_frame_at));
+                return;
+            }
+        }
+
+        event_loop.set_control_flow(ControlFlow::Wait);
         let window_renderer = self.windows.get_primary_renderer_mut().unwrap();
         window_renderer.window().request_redraw();
     }
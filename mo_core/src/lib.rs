@@ -2,6 +2,8 @@
 
 pub mod application;
 pub mod color;
+pub mod logging;
 pub mod utils;
 
 pub use application::{App, AppError};
+pub use logging::{LogFormat, LogSettings};
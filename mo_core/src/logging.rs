@@ -0,0 +1,145 @@
+//! Logging initialization
+use std::{fs::File, path::PathBuf, sync::Mutex};
+use thiserror::Error;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::{
+    filter::Targets,
+    fmt::MakeWriter,
+    layer::SubscriberExt,
+    registry::Registry,
+    util::{SubscriberInitExt, TryInitError},
+    Layer,
+};
+
+/// Environment variable with extra per crate filters, e.g. `mo_renderpass=debug,vulkano=warn`.
+pub const LOG_FILTER_ENV: &str = "MO_LOG";
+
+/// Output format of log records
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human readable, one line per record
+    #[default]
+    Full,
+    /// Shorter human readable output
+    Compact,
+    /// Newline delimited JSON objects
+    Json,
+}
+
+#[derive(Debug, Error)]
+pub enum LogError {
+    #[error("failed to create log file {path:?}: {source}")]
+    File {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("a global tracing subscriber is already installed")]
+    AlreadyInitialized(#[from] TryInitError),
+}
+
+/// Logging configuration used by [`crate::App`]
+///
+/// Passing `None` as log settings to [`crate::App::with_log_settings`] skips the setup entirely,
+/// so embedding applications can install their own subscriber.
+#[derive(Debug, Clone)]
+pub struct LogSettings {
+    /// Level for every target without a more specific filter
+    pub level: LevelFilter,
+    /// Per crate (or module path) level filters
+    pub filters: Vec<(String, LevelFilter)>,
+    /// Output format, used for stdout and the log file
+    pub format: LogFormat,
+    /// Writes the log to this file in addition to stdout
+    pub file: Option<PathBuf>,
+    /// Writes the log to stdout
+    pub stdout: bool,
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        Self {
+            level: LevelFilter::INFO,
+            filters: Vec::new(),
+            format: LogFormat::Full,
+            file: None,
+            stdout: true,
+        }
+    }
+}
+
+impl LogSettings {
+    pub fn with_level(mut self, level: impl Into<LevelFilter>) -> Self {
+        self.level = level.into();
+        self
+    }
+
+    pub fn with_filter(mut self, target: impl Into<String>, level: impl Into<LevelFilter>) -> Self {
+        self.filters.push((target.into(), level.into()));
+        self
+    }
+
+    pub fn with_format(mut self, format: LogFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.file = Some(path.into());
+        self
+    }
+
+    pub fn with_stdout(mut self, stdout: bool) -> Self {
+        self.stdout = stdout;
+        self
+    }
+
+    /// Installs the global tracing subscriber.
+    ///
+    /// Filters from the [`LOG_FILTER_ENV`] environment variable take precedence over
+    /// [`LogSettings::filters`]. Fails instead of panicking when a subscriber is already installed.
+    pub fn init(&self) -> Result<(), LogError> {
+        let mut targets = Targets::new()
+            .with_default(self.level)
+            .with_targets(self.filters.iter().cloned());
+        if let Ok(env_filters) = std::env::var(LOG_FILTER_ENV) {
+            match env_filters.parse::<Targets>() {
+                Ok(env_targets) => targets = targets.with_targets(env_targets),
+                Err(err) => eprintln!("Ignoring invalid {LOG_FILTER_ENV}={env_filters:?}: {err}"),
+            }
+        }
+
+        let mut layers = Vec::new();
+        if self.stdout {
+            layers.push(self.format_layer(std::io::stdout, true));
+        }
+        if let Some(path) = &self.file {
+            let file = File::create(path).map_err(|source| LogError::File {
+                path: path.clone(),
+                source,
+            })?;
+            layers.push(self.format_layer(Mutex::new(file), false));
+        }
+
+        tracing_subscriber::registry()
+            .with(layers)
+            .with(targets)
+            .try_init()?;
+
+        Ok(())
+    }
+
+    fn format_layer<W>(&self, writer: W, ansi: bool) -> Box<dyn Layer<Registry> + Send + Sync>
+    where
+        W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+    {
+        let layer = tracing_subscriber::fmt::layer()
+            .with_writer(writer)
+            .with_ansi(ansi);
+
+        match self.format {
+            LogFormat::Full => layer.boxed(),
+            LogFormat::Compact => layer.compact().boxed(),
+            LogFormat::Json => layer.json().boxed(),
+        }
+    }
+}
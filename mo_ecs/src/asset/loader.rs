@@ -0,0 +1,94 @@
+//! Worker threads loading assets in the background
+use super::AssetServer;
+use std::{
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+/// Applies the result of a finished load to the server, run on the main thread
+pub(crate) type Completion = Box<dyn FnOnce(&mut AssetServer) + Send>;
+
+/// Loads an asset on a worker thread
+pub(crate) type Job = Box<dyn FnOnce() -> Completion + Send>;
+
+const MAX_WORKERS: usize = 4;
+
+/// Fixed pool of worker threads. The workers exit when the loader is dropped.
+pub(crate) struct AssetLoader {
+    jobs: Sender<Job>,
+    // `Receiver` is not `Sync`, the lock only makes the server usable as a resource.
+    completions: Mutex<Receiver<Completion>>,
+    pending: usize,
+}
+
+impl AssetLoader {
+    pub fn new() -> Self {
+        let (jobs, job_receiver) = mpsc::channel::<Job>();
+        let (completion_sender, completions) = mpsc::channel();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+
+        let worker_count = thread::available_parallelism()
+            .map_or(1, |count| count.get())
+            .min(MAX_WORKERS);
+        for index in 0..worker_count {
+            let job_receiver = job_receiver.clone();
+            let completion_sender = completion_sender.clone();
+            thread::Builder::new()
+                .name(format!("asset-loader-{index}"))
+                .spawn(move || worker(job_receiver, completion_sender))
+                .expect("Spawning asset loader thread failed");
+        }
+
+        tracing::info!("ECS - Asset loader started with {worker_count} workers.");
+
+        Self {
+            jobs,
+            completions: Mutex::new(completions),
+            pending: 0,
+        }
+    }
+
+    pub fn spawn(&mut self, job: Job) {
+        self.pending += 1;
+        self.jobs
+            .send(job)
+            .expect("Asset loader workers must outlive the loader");
+    }
+
+    /// Number of jobs whose completion was not taken yet
+    pub fn pending(&self) -> usize {
+        self.pending
+    }
+
+    /// Takes the completions of the finished jobs
+    pub fn finished(&mut self) -> Vec<Completion> {
+        let completions: Vec<_> = self
+            .completions
+            .get_mut()
+            .unwrap_or_else(|err| err.into_inner())
+            .try_iter()
+            .collect();
+        self.pending -= completions.len();
+        completions
+    }
+}
+
+fn worker(jobs: Arc<Mutex<Receiver<Job>>>, completions: Sender<Completion>) {
+    loop {
+        // Release the lock before running the job so the other workers can take the next one.
+        let job = match jobs.lock() {
+            Ok(jobs) => jobs.recv(),
+            Err(_) => return,
+        };
+        let Ok(job) = job else {
+            return;
+        };
+
+        if completions.send(job()).is_err() {
+            return;
+        }
+    }
+}
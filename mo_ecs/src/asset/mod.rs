@@ -0,0 +1,465 @@
+//! Shared, reference counted assets addressed by path
+mod loader;
+
+use crate::{
+    model::{Model, ModelError},
+    resource::IBLResource,
+};
+use bevy_ecs::prelude::*;
+use loader::{AssetLoader, Completion};
+use mo_vk::{Texture, TextureCreateInfo};
+use std::{
+    collections::HashMap,
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Weak},
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum AssetError {
+    #[error("{0}")]
+    Model(#[from] ModelError),
+    #[error("{0}")]
+    Image(#[from] image::ImageError),
+    #[error("loader panicked")]
+    Panicked,
+}
+
+/// Load state of an asset, see [`AssetServer::load_state`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ELoadState {
+    /// Being loaded by a worker thread
+    Loading,
+    Loaded,
+    /// The load failed, the error was logged
+    Failed,
+}
+
+/// Unique id of a loaded asset. Ids are never reused, a reloaded path gets a new one.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AssetId(u64);
+
+/// A type the [`AssetServer`] can load and cache
+pub trait Asset: Send + Sync + Sized + 'static {
+    /// Loads the asset, `path` is resolved the same way the loader of the type does.
+    fn load(path: &str) -> Result<Self, AssetError>;
+
+    fn storage(server: &AssetServer) -> &Assets<Self>;
+
+    fn storage_mut(server: &mut AssetServer) -> &mut Assets<Self>;
+}
+
+impl Asset for Model {
+    fn load(path: &str) -> Result<Self, AssetError> {
+        Ok(Model::try_load_gltf(path)?)
+    }
+
+    fn storage(server: &AssetServer) -> &Assets<Self> {
+        &server.models
+    }
+
+    fn storage_mut(server: &mut AssetServer) -> &mut Assets<Self> {
+        &mut server.models
+    }
+}
+
+impl Asset for Texture {
+    /// Loads an image from `resources/tex`, `.hdr` and `.exr` files as float textures.
+    fn load(path: &str) -> Result<Self, AssetError> {
+        let create_info = if IBLResource::is_environment_file(path) {
+            TextureCreateInfo::default_hdr()
+        } else {
+            TextureCreateInfo::default()
+        };
+        Ok(Texture::try_load_from_file(path, &create_info)?)
+    }
+
+    fn storage(server: &AssetServer) -> &Assets<Self> {
+        &server.textures
+    }
+
+    fn storage_mut(server: &mut AssetServer) -> &mut Assets<Self> {
+        &mut server.textures
+    }
+}
+
+impl Asset for IBLResource {
+    /// Prepares an environment, `path` is a name or a file as taken by [`IBLResource::new`].
+    fn load(path: &str) -> Result<Self, AssetError> {
+        Ok(IBLResource::try_new(path)?)
+    }
+
+    fn storage(server: &AssetServer) -> &Assets<Self> {
+        &server.environments
+    }
+
+    fn storage_mut(server: &mut AssetServer) -> &mut Assets<Self> {
+        &mut server.environments
+    }
+}
+
+struct HandleInner {
+    id: AssetId,
+    path: String,
+}
+
+/// Strong reference to an asset of the [`AssetServer`]
+///
+/// The asset stays loaded while at least one handle exists. Entities hold a `Handle<Model>` to
+/// render a model, several entities may share the same one.
+#[derive(Component)]
+pub struct Handle<T: Asset> {
+    inner: Arc<HandleInner>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Asset> Handle<T> {
+    pub fn id(&self) -> AssetId {
+        self.inner.id
+    }
+
+    /// Path the asset was loaded from
+    pub fn path(&self) -> &str {
+        &self.inner.path
+    }
+
+    /// Number of handles to the asset, including this one
+    pub fn reference_count(&self) -> usize {
+        Arc::strong_count(&self.inner)
+    }
+}
+
+impl<T: Asset> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Asset> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id() == other.id()
+    }
+}
+
+impl<T: Asset> Eq for Handle<T> {}
+
+impl<T: Asset> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id().hash(state);
+    }
+}
+
+impl<T: Asset> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Handle")
+            .field("id", &self.inner.id)
+            .field("path", &self.inner.path)
+            .finish()
+    }
+}
+
+struct AssetEntry<T> {
+    handle: Weak<HandleInner>,
+    asset: Option<Arc<T>>,
+    state: ELoadState,
+}
+
+/// Loaded assets of one type
+pub struct Assets<T> {
+    entries: HashMap<AssetId, AssetEntry<T>>,
+    ids: HashMap<String, AssetId>,
+}
+
+impl<T> Default for Assets<T> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+            ids: HashMap::new(),
+        }
+    }
+}
+
+impl<T> Assets<T> {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Asset management service
+///
+/// Loads models, textures and environments once per path and hands out [`Handle`]s to them.
+/// [`AssetServer::load`] loads on worker threads, the results are applied by
+/// [`AssetServer::update_loading_assets`]. Assets without handles are unloaded by
+/// [`AssetServer::free_unused_assets`]. The application runs both at the end of the frame.
+#[derive(Resource, Default)]
+pub struct AssetServer {
+    models: Assets<Model>,
+    textures: Assets<Texture>,
+    environments: Assets<IBLResource>,
+    next_id: u64,
+    /// Started by the first background load
+    loader: Option<AssetLoader>,
+    /// Incremented by [`AssetServer::reload_all`], loads started before belong to the old
+    /// Vulkan context.
+    generation: u64,
+}
+
+impl AssetServer {
+    /// Returns a handle to the asset at `path` and loads it on a worker thread if it is not
+    /// loaded or loading yet. Until the load finishes [`AssetServer::get`] returns `None`.
+    pub fn load<T: Asset>(&mut self, path: &str) -> Handle<T> {
+        if let Some(handle) = self.get_handle(path) {
+            if self.load_state(&handle) != ELoadState::Failed {
+                return handle;
+            }
+        }
+
+        let handle = self.insert_entry(path, None, ELoadState::Loading);
+        self.spawn_load::<T>(handle.id(), path.to_string());
+        handle
+    }
+
+    /// Same as [`AssetServer::load`], but loads on the calling thread and returns the error
+    pub fn load_blocking<T: Asset>(&mut self, path: &str) -> Result<Handle<T>, AssetError> {
+        if let Some(handle) = self.get_handle(path) {
+            if self.load_state(&handle) == ELoadState::Loaded {
+                return Ok(handle);
+            }
+        }
+
+        let asset = T::load(path)?;
+        tracing::info!("ECS - Asset {path} loaded.");
+        Ok(self.add(path, asset))
+    }
+
+    /// Adds an asset loaded elsewhere under `path`. A later [`AssetServer::load`] of the same
+    /// path returns this asset.
+    pub fn add<T: Asset>(&mut self, path: &str, asset: T) -> Handle<T> {
+        self.insert_entry(path, Some(Arc::new(asset)), ELoadState::Loaded)
+    }
+
+    fn insert_entry<T: Asset>(
+        &mut self,
+        path: &str,
+        asset: Option<Arc<T>>,
+        state: ELoadState,
+    ) -> Handle<T> {
+        let id = AssetId(self.next_id);
+        self.next_id += 1;
+
+        let inner = Arc::new(HandleInner {
+            id,
+            path: path.to_string(),
+        });
+        let storage = T::storage_mut(self);
+        storage.entries.insert(
+            id,
+            AssetEntry {
+                handle: Arc::downgrade(&inner),
+                asset,
+                state,
+            },
+        );
+        storage.ids.insert(path.to_string(), id);
+
+        Handle {
+            inner,
+            marker: PhantomData,
+        }
+    }
+
+    fn spawn_load<T: Asset>(&mut self, id: AssetId, path: String) {
+        let generation = self.generation;
+        let loader = self.loader.get_or_insert_with(AssetLoader::new);
+        loader.spawn(Box::new(move || {
+            // A panicking loader must not leave the asset loading forever.
+            let result = panic::catch_unwind(AssertUnwindSafe(|| T::load(&path)))
+                .unwrap_or(Err(AssetError::Panicked));
+            Box::new(move |server: &mut AssetServer| {
+                server.finish_load(id, path, generation, result)
+            }) as Completion
+        }));
+    }
+
+    fn finish_load<T: Asset>(
+        &mut self,
+        id: AssetId,
+        path: String,
+        generation: u64,
+        result: Result<T, AssetError>,
+    ) {
+        if generation != self.generation {
+            tracing::info!("ECS - Asset {path} was loaded for a replaced context, reloading.");
+            self.spawn_load::<T>(id, path);
+            return;
+        }
+
+        let Some(entry) = T::storage_mut(self).entries.get_mut(&id) else {
+            // Every handle was dropped while loading.
+            return;
+        };
+
+        match result {
+            Ok(asset) => {
+                entry.asset = Some(Arc::new(asset));
+                entry.state = ELoadState::Loaded;
+                tracing::info!("ECS - Asset {path} loaded.");
+            }
+            Err(err) => {
+                entry.state = ELoadState::Failed;
+                tracing::error!("ECS - Loading asset {path} failed: {err}");
+            }
+        }
+    }
+
+    /// Returns a new handle to the asset at `path` if it is still loaded
+    pub fn get_handle<T: Asset>(&self, path: &str) -> Option<Handle<T>> {
+        let storage = T::storage(self);
+        let id = storage.ids.get(path)?;
+        let inner = storage.entries.get(id)?.handle.upgrade()?;
+        Some(Handle {
+            inner,
+            marker: PhantomData,
+        })
+    }
+
+    /// Returns the asset, `None` while it is loading or if the load failed
+    pub fn get<T: Asset>(&self, handle: &Handle<T>) -> Option<&T> {
+        self.get_shared(handle).map(|asset| asset.as_ref())
+    }
+
+    /// Returns the shared asset, which can outlive the handle
+    pub fn get_shared<T: Asset>(&self, handle: &Handle<T>) -> Option<&Arc<T>> {
+        T::storage(self)
+            .entries
+            .get(&handle.id())
+            .and_then(|entry| entry.asset.as_ref())
+    }
+
+    pub fn load_state<T: Asset>(&self, handle: &Handle<T>) -> ELoadState {
+        T::storage(self)
+            .entries
+            .get(&handle.id())
+            .map_or(ELoadState::Failed, |entry| entry.state)
+    }
+
+    pub fn is_loaded<T: Asset>(&self, path: &str) -> bool {
+        self.get_handle::<T>(path)
+            .is_some_and(|handle| self.load_state(&handle) == ELoadState::Loaded)
+    }
+
+    /// Returns true while background loads are running
+    pub fn is_loading(&self) -> bool {
+        self.loader
+            .as_ref()
+            .is_some_and(|loader| loader.pending() > 0)
+    }
+
+    pub fn assets<T: Asset>(&self) -> &Assets<T> {
+        T::storage(self)
+    }
+
+    /// Returns true if an asset has no handle left
+    pub fn has_unused(&self) -> bool {
+        has_unused(&self.models) || has_unused(&self.textures) || has_unused(&self.environments)
+    }
+
+    /// Unloads the assets without handles and returns how many were unloaded
+    pub fn free_unused(&mut self) -> usize {
+        free_unused(&mut self.models)
+            + free_unused(&mut self.textures)
+            + free_unused(&mut self.environments)
+    }
+
+    /// Loads every asset again from its path, used after the Vulkan context was replaced.
+    /// Assets failing to load are removed, their handles resolve to nothing.
+    pub fn reload_all(&mut self) {
+        self.generation += 1;
+        reload_all(&mut self.models);
+        reload_all(&mut self.textures);
+        reload_all(&mut self.environments);
+    }
+
+    /// This system applies the results of the finished background loads.
+    pub fn update_loading_assets(mut asset_server: ResMut<AssetServer>) {
+        // Only borrow mutably when needed, so the resource is not marked changed every frame.
+        if !asset_server.is_loading() {
+            return;
+        }
+
+        let finished = match asset_server.loader.as_mut() {
+            Some(loader) => loader.finished(),
+            None => return,
+        };
+        for completion in finished {
+            completion(&mut asset_server);
+        }
+    }
+
+    /// This system unloads the assets without handles.
+    pub fn free_unused_assets(mut asset_server: ResMut<AssetServer>) {
+        // Only borrow mutably when needed, so the resource is not marked changed every frame.
+        if asset_server.has_unused() {
+            asset_server.free_unused();
+        }
+    }
+}
+
+fn has_unused<T>(storage: &Assets<T>) -> bool {
+    storage
+        .entries
+        .values()
+        .any(|entry| entry.handle.strong_count() == 0)
+}
+
+fn free_unused<T>(storage: &mut Assets<T>) -> usize {
+    let unused: Vec<AssetId> = storage
+        .entries
+        .iter()
+        .filter(|(_, entry)| entry.handle.strong_count() == 0)
+        .map(|(id, _)| *id)
+        .collect();
+
+    for id in &unused {
+        storage.entries.remove(id);
+    }
+    storage.ids.retain(|path, id| {
+        let loaded = storage.entries.contains_key(id);
+        if !loaded {
+            tracing::info!("ECS - Asset {path} unloaded.");
+        }
+        loaded
+    });
+
+    unused.len()
+}
+
+/// Reloads the loaded assets, loading ones are reloaded when their load finishes
+fn reload_all<T: Asset>(storage: &mut Assets<T>) {
+    for (path, id) in &storage.ids {
+        let Some(entry) = storage.entries.get_mut(id) else {
+            continue;
+        };
+        if entry.state != ELoadState::Loaded {
+            continue;
+        }
+        match T::load(path) {
+            Ok(asset) => entry.asset = Some(Arc::new(asset)),
+            Err(err) => {
+                tracing::error!("ECS - Reloading asset {path} failed: {err}");
+                entry.asset = None;
+                entry.state = ELoadState::Failed;
+            }
+        }
+    }
+}
@@ -0,0 +1,630 @@
+//! data structure and constructors
+use bevy_math::{Vec3, Vec4};
+use std::convert::From;
+use std::ops::{Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Sub, SubAssign};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Color space of the RGB channels of a [`FColor`]. Alpha is always linear.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EColorSpace {
+    /// Linear light, what the shaders compute with
+    #[default]
+    Linear,
+    /// sRGB gamma encoded, as used by color pickers, hex codes and 8 bit images
+    Srgb,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ColorParseError {
+    #[error("hex color {0:?} must have 3, 4, 6 or 8 digits")]
+    InvalidLength(String),
+    #[error("hex color {0:?} contains a non hexadecimal digit")]
+    InvalidDigit(String),
+}
+
+/// RGBA Color.
+///
+/// The struct does not know its color space, APIs taking a color either document the space they
+/// expect or take an [`EColorSpace`] along with it.
+#[derive(Default, Copy, Clone, Debug, PartialEq)]
+pub struct FColor {
+    /// Red channel. Should be in range from 0 to 1.
+    pub r: f32,
+    /// Green channel. Should be in range from 0 to 1.
+    pub g: f32,
+    /// Blue channel. Should be in range from 0 to 1.
+    pub b: f32,
+    /// Alpha channel. Should be in range from 0 to 1.
+    pub a: f32,
+}
+
+impl FColor {
+    /// RGB Constructor, values should be in range from 0 to 1. Alpha will be 1.
+    pub fn rgb(r: f32, g: f32, b: f32) -> Self {
+        Self { r, g, b, a: 1.0 }
+    }
+
+    /// RGBA Constructor. Values should be in range from 0 to 1.
+    pub fn rgba(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// 8 bit per channel constructor, values are mapped from 0..=255 to 0..=1.
+    pub fn rgba8(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self::rgba(
+            r as f32 / 255.0,
+            g as f32 / 255.0,
+            b as f32 / 255.0,
+            a as f32 / 255.0,
+        )
+    }
+
+    /// Parses `#RGB`, `#RGBA`, `#RRGGBB` or `#RRGGBBAA`, the `#` is optional.
+    ///
+    /// Hex codes are sRGB encoded, convert the result with [`FColor::srgb_to_linear`] before
+    /// using it for lighting.
+    pub fn from_hex(hex: &str) -> Result<Self, ColorParseError> {
+        let digits = hex.trim().trim_start_matches('#');
+        let invalid_digit = || ColorParseError::InvalidDigit(hex.to_string());
+        if !digits.chars().all(|chr| chr.is_ascii_hexdigit()) {
+            return Err(invalid_digit());
+        }
+
+        let channel = |index: usize, width: usize| -> Result<u8, ColorParseError> {
+            let value = u8::from_str_radix(&digits[index * width..(index + 1) * width], 16)
+                .map_err(|_| invalid_digit())?;
+            // A single digit is repeated, `#f80` is `#ff8800`
+            Ok(if width == 1 { value * 17 } else { value })
+        };
+
+        match digits.len() {
+            3 | 4 | 6 | 8 => {
+                let width = if digits.len() <= 4 { 1 } else { 2 };
+                let alpha = if matches!(digits.len(), 3 | 6) {
+                    255
+                } else {
+                    channel(3, width)?
+                };
+                Ok(Self::rgba8(
+                    channel(0, width)?,
+                    channel(1, width)?,
+                    channel(2, width)?,
+                    alpha,
+                ))
+            }
+            _ => Err(ColorParseError::InvalidLength(hex.to_string())),
+        }
+    }
+
+    /// Formats the color as `#RRGGBBAA`, channels are clamped to 0..=1.
+    pub fn to_hex(&self) -> String {
+        let [r, g, b, a] = self.to_rgba8();
+        format!("#{r:02x}{g:02x}{b:02x}{a:02x}")
+    }
+
+    /// Channels clamped to 0..=1 and mapped to 0..=255.
+    pub fn to_rgba8(&self) -> [u8; 4] {
+        let quantize = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        [
+            quantize(self.r),
+            quantize(self.g),
+            quantize(self.b),
+            quantize(self.a),
+        ]
+    }
+
+    /// Creates a color from hue in degrees, saturation and value in 0..=1. Alpha will be 1.
+    ///
+    /// HSV describes the encoded RGB values, so the result is in the same space the hue was
+    /// picked in, usually sRGB.
+    pub fn hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let chroma = value * saturation;
+        Self::from_hue_chroma(hue, chroma, value - chroma)
+    }
+
+    /// Creates a color from hue in degrees, saturation and lightness in 0..=1. Alpha will be 1.
+    ///
+    /// Like [`FColor::hsv`], HSL describes the encoded RGB values.
+    pub fn hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        Self::from_hue_chroma(hue, chroma, lightness - chroma * 0.5)
+    }
+
+    /// Returns `(hue in degrees, saturation, value)`, alpha is ignored.
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let (hue, chroma, max, _min) = self.hue_chroma();
+        let saturation = if max > 0.0 { chroma / max } else { 0.0 };
+        (hue, saturation, max)
+    }
+
+    /// Returns `(hue in degrees, saturation, lightness)`, alpha is ignored.
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let (hue, chroma, max, min) = self.hue_chroma();
+        let lightness = (max + min) * 0.5;
+        let saturation = if lightness > 0.0 && lightness < 1.0 {
+            chroma / (1.0 - (2.0 * lightness - 1.0).abs())
+        } else {
+            0.0
+        };
+        (hue, saturation, lightness)
+    }
+
+    /// Linear RGB color of a black body at `kelvin` degrees, normalized so the brightest channel
+    /// is 1. Valid from 1000 K (candle light) to 40000 K (clear blue sky), 6500 K is about white.
+    pub fn from_temperature(kelvin: f32) -> Self {
+        // Tanner Helland's fit of the black body spectrum, in sRGB
+        let temperature = kelvin.clamp(1000.0, 40000.0) / 100.0;
+
+        let r = if temperature <= 66.0 {
+            255.0
+        } else {
+            329.698_73 * (temperature - 60.0).powf(-0.133_204_76)
+        };
+        let g = if temperature <= 66.0 {
+            99.470_8 * temperature.ln() - 161.119_57
+        } else {
+            288.122_16 * (temperature - 60.0).powf(-0.075_514_85)
+        };
+        let b = if temperature >= 66.0 {
+            255.0
+        } else if temperature <= 19.0 {
+            0.0
+        } else {
+            138.517_73 * (temperature - 10.0).ln() - 305.044_8
+        };
+
+        let srgb = Self::rgb(
+            (r / 255.0).clamp(0.0, 1.0),
+            (g / 255.0).clamp(0.0, 1.0),
+            (b / 255.0).clamp(0.0, 1.0),
+        );
+        let linear = srgb.srgb_to_linear();
+        let max = linear.r.max(linear.g).max(linear.b);
+        Self::rgb(linear.r / max, linear.g / max, linear.b / max)
+    }
+
+    /// Converts sRGB encoded channels to linear light. Alpha is unchanged.
+    pub fn srgb_to_linear(self) -> Self {
+        let convert = |value: f32| {
+            if value <= 0.04045 {
+                value / 12.92
+            } else {
+                ((value + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        Self::rgba(convert(self.r), convert(self.g), convert(self.b), self.a)
+    }
+
+    /// Converts linear light channels to sRGB encoding. Alpha is unchanged.
+    pub fn linear_to_srgb(self) -> Self {
+        let convert = |value: f32| {
+            if value <= 0.003_130_8 {
+                value * 12.92
+            } else {
+                1.055 * value.powf(1.0 / 2.4) - 0.055
+            }
+        };
+        Self::rgba(convert(self.r), convert(self.g), convert(self.b), self.a)
+    }
+
+    /// Converts the color from the `from` space to the `to` space.
+    pub fn convert(self, from: EColorSpace, to: EColorSpace) -> Self {
+        match (from, to) {
+            (EColorSpace::Srgb, EColorSpace::Linear) => self.srgb_to_linear(),
+            (EColorSpace::Linear, EColorSpace::Srgb) => self.linear_to_srgb(),
+            _ => self,
+        }
+    }
+
+    /// Returns the color in linear light, `space` is the space it is in now.
+    pub fn to_linear(self, space: EColorSpace) -> Self {
+        self.convert(space, EColorSpace::Linear)
+    }
+
+    /// Relative luminance (Rec. 709) of a linear color.
+    pub fn luminance(&self) -> f32 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
+    /// Multiplies the RGB channels by alpha.
+    pub fn premultiplied(self) -> Self {
+        Self::rgba(self.r * self.a, self.g * self.a, self.b * self.a, self.a)
+    }
+
+    /// Divides the RGB channels by alpha. A fully transparent color becomes transparent black.
+    pub fn unpremultiplied(self) -> Self {
+        if self.a <= 0.0 {
+            return Self::rgba(0.0, 0.0, 0.0, 0.0);
+        }
+        Self::rgba(self.r / self.a, self.g / self.a, self.b / self.a, self.a)
+    }
+
+    /// Replaces the alpha channel.
+    pub fn with_alpha(self, a: f32) -> Self {
+        Self { a, ..self }
+    }
+
+    /// Linear interpolation of all channels, `t` is not clamped.
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+
+    /// Channels clamped to 0..=1.
+    pub fn clamped(self) -> Self {
+        Self::rgba(
+            self.r.clamp(0.0, 1.0),
+            self.g.clamp(0.0, 1.0),
+            self.b.clamp(0.0, 1.0),
+            self.a.clamp(0.0, 1.0),
+        )
+    }
+
+    fn from_hue_chroma(hue: f32, chroma: f32, offset: f32) -> Self {
+        let sector = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+        let (r, g, b) = match sector as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        Self::rgb(r + offset, g + offset, b + offset)
+    }
+
+    /// Returns `(hue in degrees, chroma, max channel, min channel)`
+    fn hue_chroma(&self) -> (f32, f32, f32, f32) {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        let chroma = max - min;
+
+        let hue = if chroma == 0.0 {
+            0.0
+        } else if max == self.r {
+            60.0 * ((self.g - self.b) / chroma).rem_euclid(6.0)
+        } else if max == self.g {
+            60.0 * ((self.b - self.r) / chroma + 2.0)
+        } else {
+            60.0 * ((self.r - self.g) / chroma + 4.0)
+        };
+
+        (hue, chroma, max, min)
+    }
+
+    /// Red color (r: 1.0, g: 0.0, b: 0.0)
+    pub fn red() -> Self {
+        Self::rgb(1.0, 0.0, 0.0)
+    }
+
+    /// Grey color
+    pub fn grey() -> Self {
+        Self::rgb(0.6, 0.6, 0.6)
+    }
+
+    /// Green color (r: 0.0, g: 1.0, b: 0.0)
+    pub fn green() -> Self {
+        Self::rgb(0.0, 1.0, 0.0)
+    }
+
+    /// Blue color (r: 0.0, g: 0.0, b: 1.0)
+    pub fn blue() -> Self {
+        Self::rgb(0.0, 0.0, 1.0)
+    }
+
+    /// Cyan color (r: 0.0, g: 1.0, b: 1.0)
+    pub fn cyan() -> Self {
+        Self::rgb(0.0, 1.0, 1.0)
+    }
+
+    /// Magenta color (r: 1.0, g: 0.0, b: 1.0)
+    pub fn magenta() -> Self {
+        Self::rgb(1.0, 0.0, 1.0)
+    }
+
+    /// Yellow color (r: 1.0, g: 1.0, b: 0.0)
+    pub fn yellow() -> Self {
+        Self::rgb(1.0, 1.0, 0.0)
+    }
+
+    /// White color (r: 1.0, g: 1.0, b: 1.0)
+    pub fn white() -> Self {
+        Self::rgb(1.0, 1.0, 1.0)
+    }
+
+    /// Black color (r: 0.0, g: 0.0, b: 0.0)
+    pub fn black() -> Self {
+        Self::rgb(0.0, 0.0, 0.0)
+    }
+
+    /// Orange color (r: 1.0, g: 0.5, b: 0.0)
+    pub fn orange() -> Self {
+        Self::rgb(1.0, 0.5, 0.0)
+    }
+}
+
+impl FromStr for FColor {
+    type Err = ColorParseError;
+
+    /// Parses a hex color, see [`FColor::from_hex`]
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        Self::from_hex(hex)
+    }
+}
+
+impl Mul<f32> for FColor {
+    type Output = Self;
+
+    /// Multiply Color by f32. Result is not clamped.
+    fn mul(self, rhs: f32) -> Self::Output {
+        Self {
+            r: self.r * rhs,
+            g: self.g * rhs,
+            b: self.b * rhs,
+            a: self.a * rhs,
+        }
+    }
+}
+
+impl Mul<FColor> for FColor {
+    type Output = Self;
+
+    /// Multiply channel by channel, e.g. to tint a color. Result is not clamped.
+    fn mul(self, rhs: FColor) -> Self::Output {
+        Self {
+            r: self.r * rhs.r,
+            g: self.g * rhs.g,
+            b: self.b * rhs.b,
+            a: self.a * rhs.a,
+        }
+    }
+}
+
+impl Div<f32> for FColor {
+    type Output = Self;
+
+    /// Divide Color by f32. Result is not clamped.
+    fn div(self, rhs: f32) -> Self::Output {
+        self * (1.0 / rhs)
+    }
+}
+
+impl Add for FColor {
+    type Output = Self;
+
+    /// Add channel by channel. Result is not clamped.
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            r: self.r + rhs.r,
+            g: self.g + rhs.g,
+            b: self.b + rhs.b,
+            a: self.a + rhs.a,
+        }
+    }
+}
+
+impl Sub for FColor {
+    type Output = Self;
+
+    /// Subtract channel by channel. Result is not clamped.
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self {
+            r: self.r - rhs.r,
+            g: self.g - rhs.g,
+            b: self.b - rhs.b,
+            a: self.a - rhs.a,
+        }
+    }
+}
+
+impl MulAssign<f32> for FColor {
+    fn mul_assign(&mut self, rhs: f32) {
+        *self = *self * rhs;
+    }
+}
+
+impl MulAssign<FColor> for FColor {
+    fn mul_assign(&mut self, rhs: FColor) {
+        *self = *self * rhs;
+    }
+}
+
+impl DivAssign<f32> for FColor {
+    fn div_assign(&mut self, rhs: f32) {
+        *self = *self / rhs;
+    }
+}
+
+impl AddAssign for FColor {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for FColor {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Index<i32> for FColor {
+    type Output = f32;
+
+    fn index(&self, index: i32) -> &Self::Output {
+        match index {
+            0 => &self.r,
+            1 => &self.g,
+            2 => &self.b,
+            3 => &self.a,
+            _ => panic!("Color index is out of range."),
+        }
+    }
+}
+
+impl IndexMut<i32> for FColor {
+    fn index_mut(&mut self, index: i32) -> &mut Self::Output {
+        match index {
+            0 => &mut self.r,
+            1 => &mut self.g,
+            2 => &mut self.b,
+            3 => &mut self.a,
+            _ => panic!("Color index is out of range."),
+        }
+    }
+}
+
+impl From<[f32; 3]> for FColor {
+    fn from(rgb: [f32; 3]) -> Self {
+        Self {
+            r: rgb[0],
+            g: rgb[1],
+            b: rgb[2],
+            a: 1.0,
+        }
+    }
+}
+
+impl From<[f32; 4]> for FColor {
+    fn from(rgba: [f32; 4]) -> Self {
+        Self {
+            r: rgba[0],
+            g: rgba[1],
+            b: rgba[2],
+            a: rgba[3],
+        }
+    }
+}
+
+impl From<FColor> for [f32; 4] {
+    fn from(rgba: FColor) -> Self {
+        [rgba.r, rgba.g, rgba.b, rgba.a]
+    }
+}
+
+impl From<FColor> for [f32; 3] {
+    fn from(color: FColor) -> Self {
+        [color.r, color.g, color.b]
+    }
+}
+
+impl From<Vec3> for FColor {
+    fn from(rgb: Vec3) -> Self {
+        Self::rgb(rgb.x, rgb.y, rgb.z)
+    }
+}
+
+impl From<Vec4> for FColor {
+    fn from(rgba: Vec4) -> Self {
+        Self::rgba(rgba.x, rgba.y, rgba.z, rgba.w)
+    }
+}
+
+impl From<FColor> for Vec3 {
+    fn from(color: FColor) -> Self {
+        Vec3::new(color.r, color.g, color.b)
+    }
+}
+
+impl From<FColor> for Vec4 {
+    fn from(color: FColor) -> Self {
+        Vec4::new(color.r, color.g, color.b, color.a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: FColor, expected: FColor) {
+        let channels = |color: FColor| <[f32; 4]>::from(color);
+        for (a, e) in channels(actual).into_iter().zip(channels(expected)) {
+            assert!((a - e).abs() < 1e-3, "{actual:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn srgb_linear_round_trip() {
+        let srgb = FColor::rgba(0.5, 0.02, 1.0, 0.25);
+        let linear = srgb.srgb_to_linear();
+        assert_close(linear, FColor::rgba(0.214_04, 0.001_548, 1.0, 0.25));
+        assert_close(linear.linear_to_srgb(), srgb);
+        assert_close(
+            FColor::rgb(0.5, 0.5, 0.5).linear_to_srgb(),
+            FColor::rgb(0.735_36, 0.735_36, 0.735_36),
+        );
+    }
+
+    #[test]
+    fn hsv_round_trip() {
+        assert_close(FColor::hsv(0.0, 1.0, 1.0), FColor::red());
+        assert_close(FColor::hsv(120.0, 1.0, 1.0), FColor::green());
+        assert_close(FColor::hsv(-120.0, 1.0, 1.0), FColor::blue());
+
+        let color = FColor::rgb(0.2, 0.4, 0.8);
+        let (hue, saturation, value) = color.to_hsv();
+        assert!((hue - 220.0).abs() < 1e-3);
+        assert!((saturation - 0.75).abs() < 1e-3);
+        assert!((value - 0.8).abs() < 1e-3);
+        assert_close(FColor::hsv(hue, saturation, value), color);
+    }
+
+    #[test]
+    fn hsl_round_trip() {
+        assert_close(FColor::hsl(240.0, 1.0, 0.5), FColor::blue());
+        assert_close(FColor::hsl(0.0, 0.0, 1.0), FColor::white());
+
+        let color = FColor::rgb(0.2, 0.4, 0.8);
+        let (hue, saturation, lightness) = color.to_hsl();
+        assert!((hue - 220.0).abs() < 1e-3);
+        assert!((saturation - 0.6).abs() < 1e-3);
+        assert!((lightness - 0.5).abs() < 1e-3);
+        assert_close(FColor::hsl(hue, saturation, lightness), color);
+    }
+
+    #[test]
+    fn hex() {
+        assert_eq!(
+            FColor::from_hex("#ff8000"),
+            Ok(FColor::rgba8(255, 128, 0, 255))
+        );
+        assert_eq!(FColor::from_hex("f80"), Ok(FColor::rgba8(255, 136, 0, 255)));
+        assert_eq!(
+            FColor::from_hex("#f808"),
+            Ok(FColor::rgba8(255, 136, 0, 136))
+        );
+        assert_eq!(
+            "#12345678".parse::<FColor>(),
+            Ok(FColor::rgba8(0x12, 0x34, 0x56, 0x78))
+        );
+        assert_eq!(
+            FColor::from_hex("#12345"),
+            Err(ColorParseError::InvalidLength("#12345".to_string()))
+        );
+        assert_eq!(
+            FColor::from_hex("#zzz"),
+            Err(ColorParseError::InvalidDigit("#zzz".to_string()))
+        );
+
+        assert_eq!(FColor::rgb(1.0, 0.5, 0.0).to_hex(), "#ff8000ff");
+        assert_eq!(FColor::rgba(2.0, -1.0, 0.2, 0.0).to_hex(), "#ff003300");
+    }
+
+    #[test]
+    fn temperature() {
+        // A candle is red with a little green and no blue.
+        assert_close(
+            FColor::from_temperature(1000.0),
+            FColor::rgb(1.0, 0.057_7, 0.0),
+        );
+
+        // Daylight is about white.
+        let daylight = FColor::from_temperature(6500.0);
+        assert_close(daylight, FColor::rgb(1.0, 0.992, 0.956));
+
+        // Clear sky is blue.
+        let sky = FColor::from_temperature(40000.0);
+        assert_eq!(sky.b, 1.0);
+        assert!(sky.r < sky.g && sky.g < sky.b);
+    }
+}
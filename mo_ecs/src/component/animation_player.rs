@@ -0,0 +1,172 @@
+use bevy_ecs::prelude::*;
+use std::time::Duration;
+
+/// Plays the animations of a [`SceneRoot`](crate::component::SceneRoot) on its node entities
+///
+/// Clips are indices into [`Model::animations`](crate::model::Model::animations).
+/// [`play_animations`](crate::system::play_animations) advances the player by the
+/// [`Timer`](crate::resource::Timer) delta, add it to the runtime schedule after
+/// [`Timer::update_timer`](crate::resource::Timer::update_timer).
+#[derive(Component, Debug, Clone)]
+pub struct AnimationPlayer {
+    /// Playback rate, negative values play backwards
+    pub speed: f32,
+    /// Wraps around at the end of the clip instead of holding the last frame
+    pub looping: bool,
+    paused: bool,
+    current: Option<ActiveClip>,
+    /// Clip faded out by [`AnimationPlayer::cross_fade`]
+    previous: Option<ActiveClip>,
+    fade_duration: f32,
+    fade_elapsed: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ActiveClip {
+    clip: usize,
+    time: f32,
+    finished: bool,
+}
+
+impl ActiveClip {
+    fn new(clip: usize) -> Self {
+        Self {
+            clip,
+            time: 0.0,
+            finished: false,
+        }
+    }
+
+    fn advance(&mut self, delta: f32, duration: Option<f32>, looping: bool) {
+        let Some(duration) = duration.filter(|duration| *duration > 0.0) else {
+            self.finished = true;
+            return;
+        };
+
+        self.time += delta;
+        if looping {
+            self.time = self.time.rem_euclid(duration);
+        } else if (delta > 0.0 && self.time >= duration) || (delta < 0.0 && self.time <= 0.0) {
+            self.time = self.time.clamp(0.0, duration);
+            self.finished = true;
+        }
+    }
+}
+
+impl AnimationPlayer {
+    pub fn new() -> Self {
+        Self {
+            speed: 1.0,
+            looping: true,
+            paused: false,
+            current: None,
+            previous: None,
+            fade_duration: 0.0,
+            fade_elapsed: 0.0,
+        }
+    }
+
+    /// Plays `clip` from the start, replacing the current clip at once
+    pub fn play(&mut self, clip: usize) {
+        self.current = Some(ActiveClip::new(clip));
+        self.previous = None;
+        self.paused = false;
+    }
+
+    /// Plays `clip` from the start, blending from the current clip over `duration`
+    pub fn cross_fade(&mut self, clip: usize, duration: Duration) {
+        if self.current.is_none() || duration.is_zero() {
+            self.play(clip);
+            return;
+        }
+
+        self.previous = self.current.replace(ActiveClip::new(clip));
+        self.fade_duration = duration.as_secs_f32();
+        self.fade_elapsed = 0.0;
+        self.paused = false;
+    }
+
+    /// Stops playing, the nodes keep their last pose
+    pub fn stop(&mut self) {
+        self.current = None;
+        self.previous = None;
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Moves the current clip to `time` in seconds
+    pub fn seek(&mut self, time: f32) {
+        if let Some(current) = &mut self.current {
+            current.time = time;
+            current.finished = false;
+        }
+    }
+
+    /// Index of the clip playing or fading in
+    pub fn current_clip(&self) -> Option<usize> {
+        self.current.map(|current| current.clip)
+    }
+
+    /// Playback time of the current clip in seconds
+    pub fn time(&self) -> f32 {
+        self.current.map_or(0.0, |current| current.time)
+    }
+
+    /// Returns true if a non looping clip reached its end
+    pub fn is_finished(&self) -> bool {
+        self.current.is_some_and(|current| current.finished)
+    }
+
+    /// Returns true if [`AnimationPlayer::advance`] changes the pose
+    pub(crate) fn is_playing(&self) -> bool {
+        !self.paused
+            && (self.previous.is_some() || self.current.is_some_and(|current| !current.finished))
+    }
+
+    /// Advances the clips by `delta` seconds, `duration` returns the duration of a clip or `None`
+    /// if the model has no such clip
+    pub(crate) fn advance(&mut self, delta: f32, duration: impl Fn(usize) -> Option<f32>) {
+        let delta = delta * self.speed;
+        for clip in self.current.iter_mut().chain(&mut self.previous) {
+            clip.advance(delta, duration(clip.clip), self.looping);
+        }
+
+        if self.previous.is_some() {
+            self.fade_elapsed += delta.abs();
+            if self.fade_elapsed >= self.fade_duration {
+                self.previous = None;
+            }
+        }
+    }
+
+    /// Clips to sample with their blend weights, which add up to one
+    pub(crate) fn weighted_clips(&self) -> impl Iterator<Item = (usize, f32, f32)> + '_ {
+        let fade = match self.previous {
+            Some(_) => (self.fade_elapsed / self.fade_duration).clamp(0.0, 1.0),
+            None => 1.0,
+        };
+        let previous = self
+            .previous
+            .map(|previous| (previous.clip, previous.time, 1.0 - fade));
+        let current = self
+            .current
+            .map(|current| (current.clip, current.time, fade));
+        previous.into_iter().chain(current)
+    }
+}
+
+impl Default for AnimationPlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
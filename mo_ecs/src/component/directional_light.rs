@@ -1,11 +1,16 @@
-use crate::component::Transform;
+use crate::{
+    color::{EColorSpace, FColor},
+    component::Transform,
+};
 use bevy_ecs::prelude::*;
 use bevy_math::prelude::*;
 
 #[derive(Component)]
 pub struct DirectionalLight {
     pub transform: Transform,
-    pub color: Vec3,
+    /// Light color in [`DirectionalLight::color_space`], converted to linear when rendering
+    pub color: FColor,
+    pub color_space: EColorSpace,
     pub intensity: f32,
     pub is_shadow_caster: bool,
     pub shadow_width: f32,
@@ -15,7 +20,8 @@ pub struct DirectionalLight {
 impl Default for DirectionalLight {
     fn default() -> Self {
         Self {
-            color: Vec3::new(1.0, 1.0, 1.0),
+            color: FColor::white(),
+            color_space: EColorSpace::Linear,
             intensity: 1.0,
             is_shadow_caster: false,
             shadow_width: 8.0,
@@ -26,9 +32,16 @@ impl Default for DirectionalLight {
 }
 
 impl DirectionalLight {
-    pub fn new(transform: Transform, color: Vec3, intensity: f32, is_shadow_caster: bool) -> Self {
+    pub fn new(
+        transform: Transform,
+        color: FColor,
+        color_space: EColorSpace,
+        intensity: f32,
+        is_shadow_caster: bool,
+    ) -> Self {
         Self {
             color,
+            color_space,
             intensity,
             is_shadow_caster,
             transform,
@@ -36,6 +49,27 @@ impl DirectionalLight {
         }
     }
 
+    /// Light colored like a black body at `kelvin` degrees, see [`FColor::from_temperature`]
+    pub fn from_temperature(
+        transform: Transform,
+        kelvin: f32,
+        intensity: f32,
+        is_shadow_caster: bool,
+    ) -> Self {
+        Self::new(
+            transform,
+            FColor::from_temperature(kelvin),
+            EColorSpace::Linear,
+            intensity,
+            is_shadow_caster,
+        )
+    }
+
+    /// Light color in linear space, as the shaders expect it
+    pub fn linear_color(&self) -> FColor {
+        self.color.to_linear(self.color_space)
+    }
+
     pub fn proj_view(&self) -> Mat4 {
         let direction = -self.transform.direction();
         let right = direction.cross(Vec3::Y).normalize();
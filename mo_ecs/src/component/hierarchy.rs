@@ -0,0 +1,36 @@
+use bevy_ecs::prelude::*;
+use bevy_math::{Mat4, Vec3};
+
+/// Parent of an entity, its [`Transform`](crate::component::Transform) is relative to the parent
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Parent(pub Entity);
+
+/// Children of an entity, kept in sync with their [`Parent`] components
+#[derive(Component, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Children(pub Vec<Entity>);
+
+/// World space transform of an entity
+///
+/// Computed from the [`Transform`](crate::component::Transform) of the entity and its parents by
+/// [`propagate_transforms`](crate::system::propagate_transforms), which the application runs at
+/// the end of each frame. Added automatically with a `Transform`.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct GlobalTransform(pub Mat4);
+
+impl GlobalTransform {
+    pub const IDENTITY: Self = Self(Mat4::IDENTITY);
+
+    pub fn matrix(&self) -> Mat4 {
+        self.0
+    }
+
+    pub fn translation(&self) -> Vec3 {
+        self.0.w_axis.truncate()
+    }
+}
+
+impl Default for GlobalTransform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
@@ -0,0 +1,18 @@
+use crate::model::Model;
+use bevy_ecs::prelude::*;
+
+/// Active KHR_materials_variants variant of an entity with a `Handle<Model>` or a
+/// [`SceneRoot`](crate::component::SceneRoot), an index into [`Model::variants`]
+///
+/// `None` and variants without a mapping for a mesh keep its default material. The node entities
+/// of a scene follow their root, see
+/// [`sync_material_variants`](crate::system::sync_material_variants).
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MaterialVariant(pub Option<usize>);
+
+impl MaterialVariant {
+    /// The variant named `name`, the default materials if the model has none of that name
+    pub fn named(model: &Model, name: &str) -> Self {
+        Self(model.find_variant(name))
+    }
+}
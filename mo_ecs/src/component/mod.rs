@@ -1,5 +1,21 @@
+pub mod animation_player;
 pub mod directional_light;
+pub mod hierarchy;
+pub mod material_variant;
+pub mod morph_weights;
+pub mod name;
+pub mod point_light;
+pub mod scene;
+pub mod spot_light;
 pub mod transform;
 
+pub use animation_player::AnimationPlayer;
 pub use directional_light::DirectionalLight;
+pub use hierarchy::{Children, GlobalTransform, Parent};
+pub use material_variant::MaterialVariant;
+pub use morph_weights::MorphWeights;
+pub use name::Name;
+pub use point_light::PointLight;
+pub use scene::{SceneInstance, SceneNode, SceneRoot};
+pub use spot_light::SpotLight;
 pub use transform::Transform;
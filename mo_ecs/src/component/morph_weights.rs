@@ -0,0 +1,10 @@
+use bevy_ecs::prelude::*;
+
+/// Morph target weights of the meshes of a [`SceneNode`](crate::component::SceneNode), or of
+/// every mesh of an entity with a `Handle<Model>`
+///
+/// Spawned with the default weights of the file for nodes with morph targets and written by
+/// [`play_animations`](crate::system::play_animations) for animated weights. Meshes without the
+/// component use their default weights, missing weights count as zero.
+#[derive(Component, Debug, Clone, Default, PartialEq)]
+pub struct MorphWeights(pub Vec<f32>);
@@ -0,0 +1,22 @@
+use bevy_ecs::prelude::*;
+use std::fmt;
+
+/// Human readable name of an entity, e.g. the name of a glTF node
+#[derive(Component, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Name(pub String);
+
+impl Name {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self(name.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
@@ -0,0 +1,53 @@
+use crate::{
+    color::{EColorSpace, FColor},
+    component::Transform,
+};
+use bevy_ecs::prelude::*;
+
+/// Light shining in all directions from the translation of [`PointLight::transform`]
+#[derive(Component)]
+pub struct PointLight {
+    pub transform: Transform,
+    /// Light color in [`PointLight::color_space`], converted to linear when rendering
+    pub color: FColor,
+    pub color_space: EColorSpace,
+    /// Falls off with the inverse square of the distance
+    pub intensity: f32,
+    /// Distance at which the light fades out, `None` for an unlimited range
+    pub range: Option<f32>,
+}
+
+impl Default for PointLight {
+    fn default() -> Self {
+        Self {
+            transform: Transform::default(),
+            color: FColor::white(),
+            color_space: EColorSpace::Linear,
+            intensity: 1.0,
+            range: None,
+        }
+    }
+}
+
+impl PointLight {
+    pub fn new(
+        transform: Transform,
+        color: FColor,
+        color_space: EColorSpace,
+        intensity: f32,
+        range: Option<f32>,
+    ) -> Self {
+        Self {
+            transform,
+            color,
+            color_space,
+            intensity,
+            range,
+        }
+    }
+
+    /// Light color in linear space, as the shaders expect it
+    pub fn linear_color(&self) -> FColor {
+        self.color.to_linear(self.color_space)
+    }
+}
@@ -0,0 +1,27 @@
+use crate::{asset::Handle, model::Model};
+use bevy_ecs::prelude::*;
+
+/// Imports a model as an entity hierarchy
+///
+/// Once the model is loaded, [`spawn_scene_nodes`](crate::system::spawn_scene_nodes) spawns an
+/// entity with a [`SceneNode`] for every glTF node as children of this entity, so parts of the
+/// model can be moved on their own. Use a plain `Handle<Model>` to render the model as a whole.
+#[derive(Component, Debug, Clone)]
+pub struct SceneRoot(pub Handle<Model>);
+
+/// Node entities of a [`SceneRoot`], inserted once they are spawned
+#[derive(Component, Debug, Clone, Default)]
+pub struct SceneInstance {
+    /// Entity of every node by node index, `None` for nodes outside of the scenes
+    pub nodes: Vec<Option<Entity>>,
+}
+
+/// Entity spawned for a glTF node, it renders the meshes of the node
+#[derive(Component, Debug, Clone)]
+pub struct SceneNode {
+    pub model: Handle<Model>,
+    /// Index into [`Model::nodes`]
+    pub node: usize,
+    /// Entity with the [`SceneRoot`] and [`SceneInstance`]
+    pub root: Entity,
+}
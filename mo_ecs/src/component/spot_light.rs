@@ -0,0 +1,72 @@
+use crate::{
+    color::{EColorSpace, FColor},
+    component::Transform,
+};
+use bevy_ecs::prelude::*;
+use std::f32::consts::FRAC_PI_4;
+
+/// Light shining in a cone along the forward direction (-Z) of [`SpotLight::transform`]
+#[derive(Component)]
+pub struct SpotLight {
+    pub transform: Transform,
+    /// Light color in [`SpotLight::color_space`], converted to linear when rendering
+    pub color: FColor,
+    pub color_space: EColorSpace,
+    /// Falls off with the inverse square of the distance
+    pub intensity: f32,
+    /// Distance at which the light fades out, `None` for an unlimited range
+    pub range: Option<f32>,
+    /// Angle from the axis in radians where the light starts to fade out
+    pub inner_cone_angle: f32,
+    /// Angle from the axis in radians where the light is gone
+    pub outer_cone_angle: f32,
+}
+
+impl Default for SpotLight {
+    fn default() -> Self {
+        Self {
+            transform: Transform::default(),
+            color: FColor::white(),
+            color_space: EColorSpace::Linear,
+            intensity: 1.0,
+            range: None,
+            inner_cone_angle: 0.0,
+            outer_cone_angle: FRAC_PI_4,
+        }
+    }
+}
+
+impl SpotLight {
+    pub fn new(
+        transform: Transform,
+        color: FColor,
+        color_space: EColorSpace,
+        intensity: f32,
+        inner_cone_angle: f32,
+        outer_cone_angle: f32,
+    ) -> Self {
+        Self {
+            transform,
+            color,
+            color_space,
+            intensity,
+            inner_cone_angle,
+            outer_cone_angle,
+            ..Default::default()
+        }
+    }
+
+    /// Light color in linear space, as the shaders expect it
+    pub fn linear_color(&self) -> FColor {
+        self.color.to_linear(self.color_space)
+    }
+
+    /// Scale and offset turning the cosine of the angle to the axis into the cone attenuation,
+    /// as KHR_lights_punctual describes it
+    pub fn cone_scale_offset(&self) -> (f32, f32) {
+        let cos_inner = self.inner_cone_angle.cos();
+        let cos_outer = self.outer_cone_angle.cos();
+        let scale = 1.0 / (cos_inner - cos_outer).max(0.001);
+        (scale, -cos_outer * scale)
+    }
+}
@@ -1,9 +1,11 @@
+use crate::component::GlobalTransform;
 use bevy_ecs::prelude::Component;
 use bevy_math::{Dir3, Mat3, Mat4, Quat, Vec3};
 use core::ops::Mul;
 
 /// 描述了实体的位置信息。如果实体有父实体，那么它的位置是相对于其父实体的位置。
 #[derive(Debug, PartialEq, Clone, Copy, Component)]
+#[require(GlobalTransform)]
 pub struct Transform {
     /// Position of the entity.
     pub translation: Vec3,
@@ -1,3 +1,6 @@
+pub mod asset;
+pub mod color;
 pub mod component;
 pub mod model;
 pub mod resource;
+pub mod system;
@@ -0,0 +1,295 @@
+//! Animation clips imported from glTF
+use bevy_math::Quat;
+use gltf::animation::{util::ReadOutputs, Interpolation};
+
+/// How a channel interpolates between two keyframes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EInterpolation {
+    Linear,
+    /// Holds the value of a keyframe until the next one
+    Step,
+    /// Hermite spline through the keyframes, with in and out tangents stored per keyframe
+    CubicSpline,
+}
+
+/// Node property animated by a channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EAnimationProperty {
+    Translation,
+    Rotation,
+    Scale,
+    /// Weights of the morph targets of the meshes of the node
+    MorphWeights,
+}
+
+/// Keyframes of one property of one node
+pub struct AnimationChannel {
+    /// Index into [`Model::nodes`](crate::model::Model::nodes)
+    pub node: usize,
+    pub property: EAnimationProperty,
+    pub interpolation: EInterpolation,
+    /// Keyframe times in seconds, in ascending order
+    pub times: Vec<f32>,
+    /// Flattened keyframe values of `stride` floats each. Rotations are `[x, y, z, w]`
+    /// quaternions. Cubic spline keyframes store the in-tangent, the value and the out-tangent.
+    pub values: Vec<f32>,
+    /// Floats per value: 3 for translation and scale, 4 for rotation and the morph target count
+    /// for weights
+    pub stride: usize,
+}
+
+/// A glTF animation, played by an [`AnimationPlayer`](crate::component::AnimationPlayer)
+pub struct AnimationClip {
+    pub name: Option<String>,
+    /// Time of the last keyframe in seconds
+    pub duration: f32,
+    pub channels: Vec<AnimationChannel>,
+}
+
+impl AnimationClip {
+    pub(crate) fn from_gltf(animation: &gltf::Animation, buffers: &[gltf::buffer::Data]) -> Self {
+        let channels: Vec<AnimationChannel> = animation
+            .channels()
+            .filter_map(|channel| {
+                let reader = channel.reader(|buffer| Some(&buffers[buffer.index()]));
+                let times: Vec<f32> = reader.read_inputs()?.collect();
+                let (property, values): (_, Vec<f32>) = match reader.read_outputs()? {
+                    ReadOutputs::Translations(translations) => (
+                        EAnimationProperty::Translation,
+                        translations.flatten().collect(),
+                    ),
+                    ReadOutputs::Rotations(rotations) => (
+                        EAnimationProperty::Rotation,
+                        rotations.into_f32().flatten().collect(),
+                    ),
+                    ReadOutputs::Scales(scales) => {
+                        (EAnimationProperty::Scale, scales.flatten().collect())
+                    }
+                    ReadOutputs::MorphTargetWeights(weights) => (
+                        EAnimationProperty::MorphWeights,
+                        weights.into_f32().collect(),
+                    ),
+                };
+                let interpolation = match channel.sampler().interpolation() {
+                    Interpolation::Linear => EInterpolation::Linear,
+                    Interpolation::Step => EInterpolation::Step,
+                    Interpolation::CubicSpline => EInterpolation::CubicSpline,
+                };
+
+                let values_per_keyframe = match interpolation {
+                    EInterpolation::CubicSpline => 3,
+                    _ => 1,
+                };
+                let stride = match property {
+                    EAnimationProperty::Translation | EAnimationProperty::Scale => 3,
+                    EAnimationProperty::Rotation => 4,
+                    EAnimationProperty::MorphWeights => {
+                        values.len() / (times.len() * values_per_keyframe).max(1)
+                    }
+                };
+                if times.is_empty()
+                    || stride == 0
+                    || values.len() != times.len() * values_per_keyframe * stride
+                {
+                    tracing::warn!(
+                        "ECS - Skipped malformed {property:?} channel of animation {:?}.",
+                        animation.name()
+                    );
+                    return None;
+                }
+
+                Some(AnimationChannel {
+                    node: channel.target().node().index(),
+                    property,
+                    interpolation,
+                    times,
+                    values,
+                    stride,
+                })
+            })
+            .collect();
+
+        let duration = channels
+            .iter()
+            .filter_map(|channel| channel.times.last().copied())
+            .fold(0.0, f32::max);
+
+        Self {
+            name: animation.name().map(str::to_string),
+            duration,
+            channels,
+        }
+    }
+}
+
+impl AnimationChannel {
+    /// Samples the channel at `time` in seconds into `out`, which must hold `stride` floats.
+    /// Times outside of the keyframes hold the first or last value.
+    pub fn sample(&self, time: f32, out: &mut [f32]) {
+        let next = self
+            .times
+            .partition_point(|&keyframe_time| keyframe_time <= time);
+        if next == 0 {
+            out.copy_from_slice(self.value(0));
+            return;
+        }
+        if next == self.times.len() {
+            out.copy_from_slice(self.value(next - 1));
+            return;
+        }
+
+        let previous = next - 1;
+        let delta = self.times[next] - self.times[previous];
+        let t = if delta > 0.0 {
+            (time - self.times[previous]) / delta
+        } else {
+            0.0
+        };
+
+        match self.interpolation {
+            EInterpolation::Step => out.copy_from_slice(self.value(previous)),
+            EInterpolation::Linear if self.property == EAnimationProperty::Rotation => {
+                let start = Quat::from_slice(self.value(previous));
+                let end = Quat::from_slice(self.value(next));
+                start.slerp(end, t).write_to_slice(out);
+            }
+            EInterpolation::Linear => {
+                let start = self.value(previous);
+                let end = self.value(next);
+                for (out, (start, end)) in out.iter_mut().zip(start.iter().zip(end)) {
+                    *out = start + (end - start) * t;
+                }
+            }
+            EInterpolation::CubicSpline => {
+                let t2 = t * t;
+                let t3 = t2 * t;
+                let start = self.value(previous);
+                let start_tangent = self.cubic_value(previous, 2);
+                let end = self.value(next);
+                let end_tangent = self.cubic_value(next, 0);
+                for (i, out) in out.iter_mut().enumerate() {
+                    *out = (2.0 * t3 - 3.0 * t2 + 1.0) * start[i]
+                        + (t3 - 2.0 * t2 + t) * delta * start_tangent[i]
+                        + (-2.0 * t3 + 3.0 * t2) * end[i]
+                        + (t3 - t2) * delta * end_tangent[i];
+                }
+                if self.property == EAnimationProperty::Rotation {
+                    Quat::from_slice(out).normalize().write_to_slice(out);
+                }
+            }
+        }
+    }
+
+    /// Value of keyframe `index`
+    fn value(&self, index: usize) -> &[f32] {
+        match self.interpolation {
+            EInterpolation::CubicSpline => self.cubic_value(index, 1),
+            _ => &self.values[index * self.stride..(index + 1) * self.stride],
+        }
+    }
+
+    /// In-tangent, value or out-tangent of cubic spline keyframe `index`, for `element` 0, 1 or 2
+    fn cubic_value(&self, index: usize, element: usize) -> &[f32] {
+        let offset = (index * 3 + element) * self.stride;
+        &self.values[offset..offset + self.stride]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn channel(
+        property: EAnimationProperty,
+        interpolation: EInterpolation,
+        times: Vec<f32>,
+        values: Vec<f32>,
+        stride: usize,
+    ) -> AnimationChannel {
+        AnimationChannel {
+            node: 0,
+            property,
+            interpolation,
+            times,
+            values,
+            stride,
+        }
+    }
+
+    fn sample<const N: usize>(channel: &AnimationChannel, time: f32) -> [f32; N] {
+        let mut out = [0.0; N];
+        channel.sample(time, &mut out);
+        out
+    }
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-5, "{actual:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn step() {
+        let channel = channel(
+            EAnimationProperty::Translation,
+            EInterpolation::Step,
+            vec![1.0, 2.0],
+            vec![0.0, 0.0, 0.0, 2.0, 4.0, 6.0],
+            3,
+        );
+        assert_eq!(sample(&channel, 0.0), [0.0, 0.0, 0.0]);
+        assert_eq!(sample(&channel, 1.75), [0.0, 0.0, 0.0]);
+        assert_eq!(sample(&channel, 2.0), [2.0, 4.0, 6.0]);
+        assert_eq!(sample(&channel, 3.0), [2.0, 4.0, 6.0]);
+    }
+
+    #[test]
+    fn linear() {
+        let channel = channel(
+            EAnimationProperty::Translation,
+            EInterpolation::Linear,
+            vec![1.0, 2.0],
+            vec![0.0, 0.0, 0.0, 2.0, 4.0, 6.0],
+            3,
+        );
+        assert_eq!(sample(&channel, 0.0), [0.0, 0.0, 0.0]);
+        assert_close(&sample::<3>(&channel, 1.25), &[0.5, 1.0, 1.5]);
+        assert_eq!(sample(&channel, 2.5), [2.0, 4.0, 6.0]);
+    }
+
+    #[test]
+    fn slerp() {
+        let end = Quat::from_rotation_z(FRAC_PI_2);
+        let mut values = Quat::IDENTITY.to_array().to_vec();
+        values.extend(end.to_array());
+        let channel = channel(
+            EAnimationProperty::Rotation,
+            EInterpolation::Linear,
+            vec![0.0, 1.0],
+            values,
+            4,
+        );
+        // Halfway is a quarter turn, a component-wise lerp would not be unit length.
+        let half = Quat::from_rotation_z(FRAC_PI_2 * 0.5).to_array();
+        assert_close(&sample::<4>(&channel, 0.5), &half);
+        assert_close(&sample::<4>(&channel, 1.0), &end.to_array());
+    }
+
+    #[test]
+    fn cubic_spline() {
+        // In-tangent, value, out-tangent of two keyframes two seconds apart
+        let channel = channel(
+            EAnimationProperty::MorphWeights,
+            EInterpolation::CubicSpline,
+            vec![0.0, 2.0],
+            vec![9.0, 0.0, 4.0, 0.0, 1.0, 9.0],
+            1,
+        );
+        assert_close(&sample::<1>(&channel, 0.0), &[0.0]);
+        // h00 = 0.5, h10 = 0.125, h01 = 0.5, h11 = -0.125 at the midpoint, the tangents are
+        // scaled by the keyframe distance: 0.125 * 2 * 4 + 0.5 * 1 - 0.125 * 2 * 0
+        assert_close(&sample::<1>(&channel, 1.0), &[1.5]);
+        assert_close(&sample::<1>(&channel, 2.0), &[1.0]);
+    }
+}
@@ -0,0 +1,44 @@
+use crate::{
+    component::Transform,
+    resource::camera::{CameraSettings, OrthographicCameraSize},
+};
+use bevy_math::Mat4;
+
+/// A glTF camera, placed by the node it is attached to. Apply it to the active camera with
+/// [`Camera::set_preset`](crate::resource::Camera::set_preset).
+#[derive(Debug, Clone)]
+pub struct CameraPreset {
+    /// Name of the node, or of the camera if the node has none
+    pub name: Option<String>,
+    pub settings: CameraSettings,
+    /// Model space transform of the node in the rest pose, looking along its -Z axis
+    pub transform: Transform,
+}
+
+impl CameraPreset {
+    pub fn from_gltf(node: &gltf::Node, camera: &gltf::Camera, transform: Mat4) -> Self {
+        let settings = match camera.projection() {
+            gltf::camera::Projection::Perspective(perspective) => CameraSettings::Perspective {
+                fov: perspective.yfov(),
+                near: perspective.znear(),
+                // An infinite projection has no far plane.
+                far: perspective.zfar().unwrap_or(1000.0),
+            },
+            // The magnifications are half the size of the view, the height is kept when the
+            // window aspect ratio differs from the authored one.
+            gltf::camera::Projection::Orthographic(orthographic) => CameraSettings::Orthographic {
+                width: orthographic.xmag() * 2.0,
+                height: orthographic.ymag() * 2.0,
+                size: OrthographicCameraSize::FixedHeight,
+                near: orthographic.znear(),
+                far: orthographic.zfar(),
+            },
+        };
+
+        Self {
+            name: node.name().or(camera.name()).map(str::to_string),
+            settings,
+            transform: Transform::from_matrix(transform),
+        }
+    }
+}
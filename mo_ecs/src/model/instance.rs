@@ -0,0 +1,115 @@
+use crate::{
+    asset::{AssetServer, ELoadState, Handle},
+    component::{GlobalTransform, MaterialVariant, SceneNode, SceneRoot},
+    model::{Model, SkinnedVertices, StaticVertex},
+};
+use bevy_ecs::world::EntityRef;
+use bevy_math::Mat4;
+use vulkano::buffer::Subbuffer;
+
+/// Meshes an entity renders, shared by the render passes
+pub struct ModelInstance<'w> {
+    pub handle: &'w Handle<Model>,
+    pub model: &'w Model,
+    pub meshes: Vec<MeshDraw<'w>>,
+}
+
+/// A mesh to draw
+pub struct MeshDraw<'w> {
+    /// Index into [`Model::meshes`]
+    pub index: usize,
+    /// Material of the active [`MaterialVariant`], see [`Model::material`]
+    pub material: usize,
+    pub world: Mat4,
+    /// Deformed meshes use the vertices of the skinning pass, skinned ones with an identity
+    /// [`MeshDraw::world`]
+    pub vertex_buffer: &'w Subbuffer<[StaticVertex]>,
+}
+
+impl<'w> ModelInstance<'w> {
+    /// Returns the loaded meshes of an entity with a [`GlobalTransform`] and either a
+    /// `Handle<Model>`, drawing the whole model, or a [`SceneNode`], drawing the meshes of the
+    /// node. Meshes missing from `skinned_vertices` are drawn in their bind pose.
+    pub fn from_entity(
+        entity: EntityRef<'w>,
+        asset_server: &'w AssetServer,
+        skinned_vertices: Option<&'w SkinnedVertices>,
+    ) -> Option<Self> {
+        let global_transform = entity.get::<GlobalTransform>()?.matrix();
+
+        let (handle, model, meshes): (_, _, Vec<(usize, Mat4)>) =
+            if let Some(handle) = entity.get::<Handle<Model>>() {
+                let model = asset_server.get(handle)?;
+                let meshes = model
+                    .meshes
+                    .iter()
+                    .enumerate()
+                    .map(|(index, mesh)| (index, global_transform * mesh.world))
+                    .collect();
+                (handle, model, meshes)
+            } else {
+                let node = entity.get::<SceneNode>()?;
+                let model = asset_server.get(&node.model)?;
+                let meshes = model
+                    .nodes
+                    .get(node.node)?
+                    .meshes
+                    .iter()
+                    .map(|&index| (index, global_transform))
+                    .collect();
+                (&node.model, model, meshes)
+            };
+
+        let variant = entity
+            .get::<MaterialVariant>()
+            .and_then(|variant| variant.0);
+        let material = |index: usize| {
+            variant
+                .and_then(|variant| model.meshes[index].variants.get(variant).copied().flatten())
+                .map_or(index, |variant_material| {
+                    model.meshes.len() + variant_material
+                })
+        };
+
+        let meshes = meshes
+            .into_iter()
+            .map(|(index, world)| {
+                let deformed = skinned_vertices
+                    .and_then(|skinned_vertices| skinned_vertices.get(entity.id(), index));
+                match deformed {
+                    // Skinned vertices are in world space, morphed ones in model space.
+                    Some(vertex_buffer) => MeshDraw {
+                        index,
+                        material: material(index),
+                        world: if model.meshes[index].skin.is_some() {
+                            Mat4::IDENTITY
+                        } else {
+                            world
+                        },
+                        vertex_buffer,
+                    },
+                    None => MeshDraw {
+                        index,
+                        material: material(index),
+                        world,
+                        vertex_buffer: &model.meshes[index].primitive.vertex_buffer,
+                    },
+                }
+            })
+            .collect();
+
+        Some(Self {
+            handle,
+            model,
+            meshes,
+        })
+    }
+
+    /// Returns true if the entity renders a model which is still loading
+    pub fn is_loading(entity: EntityRef<'w>, asset_server: &'w AssetServer) -> bool {
+        entity
+            .get::<Handle<Model>>()
+            .or(entity.get::<SceneRoot>().map(|root| &root.0))
+            .is_some_and(|handle| asset_server.load_state(handle) == ELoadState::Loading)
+    }
+}
@@ -1,3 +1,4 @@
+use crate::color::{EColorSpace, FColor};
 use bevy_math::prelude::*;
 
 #[repr(u32)]
@@ -22,17 +23,79 @@ pub enum AlphaMode {
     Blend = 2,
 }
 
+/// Texture slots of a material, in the order of the uv transforms of the GPU material
+#[repr(usize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ETextureSlot {
+    BaseColor,
+    Normal,
+    SurfaceProperties,
+    Occlusion,
+    Emissive,
+    Clearcoat,
+    ClearcoatRoughness,
+    ClearcoatNormal,
+    Sheen,
+    SheenRoughness,
+    Transmission,
+    Thickness,
+    Specular,
+    SpecularColor,
+    Iridescence,
+    IridescenceThickness,
+    Anisotropy,
+}
+
+impl ETextureSlot {
+    pub const COUNT: usize = 17;
+}
+
+/// KHR_texture_transform of a texture slot
+#[derive(Copy, Clone, Debug)]
+pub struct TextureTransform {
+    pub offset: Vec2,
+    /// Counter-clockwise rotation in radians
+    pub rotation: f32,
+    pub scale: Vec2,
+    /// Overrides the uv set of the slot
+    pub tex_coord: Option<u32>,
+}
+
+impl Default for TextureTransform {
+    fn default() -> Self {
+        Self {
+            offset: Vec2::ZERO,
+            rotation: 0.0,
+            scale: Vec2::ONE,
+            tex_coord: None,
+        }
+    }
+}
+
+impl TextureTransform {
+    /// Affine uv transform, translation * rotation * scale as the extension defines it
+    pub fn matrix(&self) -> Mat3 {
+        let (sin, cos) = self.rotation.sin_cos();
+        let rotation =
+            Mat3::from_cols(Vec3::new(cos, -sin, 0.0), Vec3::new(sin, cos, 0.0), Vec3::Z);
+        Mat3::from_translation(self.offset) * rotation * Mat3::from_scale(self.scale)
+    }
+}
+
 /// Note: these are not bindless indexes, but rather the index into the Model specific texture array.
 #[derive(Clone, Debug)]
 pub struct GltfMaterialCPU {
     pub base_color_factor: Vec4,
     pub ormn: Vec4, // occlusion, roughness, metallic, normal strength
     pub specular_glossiness: Vec4,
-    pub sheen_factors: Vec4,
+    pub sheen_factors: Vec4, // sheen color, sheen roughness
+
+    pub clearcoat_transmission_thickness: Vec4, // clearcoat, its roughness, transmission, thickness
+    pub specular_factors: Vec4,                 // specular color, specular factor
+    pub attenuation: Vec4,                      // attenuation color, attenuation distance
 
-    pub clearcoat_transmission_thickness: Vec4,
-    pub specular_factors: Vec4,
-    pub attenuation: Vec4,
+    pub iridescence_factors: Vec4, // factor, ior, thickness minimum, thickness maximum
+    pub anisotropy_factors: Vec4,  // strength, rotation
 
     pub emissive_factor_alpha_cutoff: Vec4,
 
@@ -100,9 +163,14 @@ pub struct GltfMaterialCPU {
     pub anisotropy_sampler: u32,
     pub anisotropy_uv: u32,
 
+    /// Indexed by [`ETextureSlot`]
+    pub texture_transforms: [TextureTransform; ETextureSlot::COUNT],
+
     pub alpha_mode: AlphaMode,
     pub material_type: MaterialType,
     pub ior: f32,
+    /// Multiplier of the emissive factor, emissive colors may exceed 1.0 with it
+    pub emissive_strength: f32,
 }
 
 impl Default for GltfMaterialCPU {
@@ -116,6 +184,8 @@ impl Default for GltfMaterialCPU {
             clearcoat_transmission_thickness: Vec4::ONE,
             specular_factors: Vec4::ONE,
             attenuation: Vec4::ONE,
+            iridescence_factors: Vec4::new(0.0, 1.3, 100.0, 400.0),
+            anisotropy_factors: Vec4::ZERO,
 
             emissive_factor_alpha_cutoff: Vec4::ZERO,
 
@@ -183,9 +253,116 @@ impl Default for GltfMaterialCPU {
             anisotropy_sampler: 0,
             anisotropy_uv: 0,
 
+            texture_transforms: [TextureTransform::default(); ETextureSlot::COUNT],
+
             alpha_mode: AlphaMode::Opaque,
             material_type: MaterialType::None,
             ior: 1.5,
+            emissive_strength: 1.0,
         }
     }
 }
+
+impl GltfMaterialCPU {
+    /// Uv set of a texture slot, a texCoord override of its transform takes precedence.
+    pub fn uv_set(&self, slot: ETextureSlot) -> u32 {
+        let uv = match slot {
+            ETextureSlot::BaseColor => self.base_color_uv,
+            ETextureSlot::Normal => self.normal_uv,
+            ETextureSlot::SurfaceProperties => self.surface_properties_uv,
+            ETextureSlot::Occlusion => self.occlusion_uv,
+            ETextureSlot::Emissive => self.emissive_uv,
+            ETextureSlot::Clearcoat => self.clearcoat_uv,
+            ETextureSlot::ClearcoatRoughness => self.clearcoat_roughness_uv,
+            ETextureSlot::ClearcoatNormal => self.clearcoat_normal_uv,
+            ETextureSlot::Sheen => self.sheen_uv,
+            ETextureSlot::SheenRoughness => self.sheen_roughness_uv,
+            ETextureSlot::Transmission => self.transmission_uv,
+            ETextureSlot::Thickness => self.thickness_uv,
+            ETextureSlot::Specular => self.specular_uv,
+            ETextureSlot::SpecularColor => self.specular_color_uv,
+            ETextureSlot::Iridescence => self.iridescence_uv,
+            ETextureSlot::IridescenceThickness => self.iridescence_thickness_uv,
+            ETextureSlot::Anisotropy => self.anisotropy_uv,
+        };
+        self.texture_transforms[slot as usize]
+            .tex_coord
+            .unwrap_or(uv)
+    }
+
+    /// Transmissive materials are drawn after the opaque scene, which they refract.
+    pub fn is_transmissive(&self) -> bool {
+        self.clearcoat_transmission_thickness.z > 0.0
+    }
+
+    /// Linear base color factor
+    pub fn base_color(&self) -> FColor {
+        self.base_color_factor.into()
+    }
+
+    /// Sets the base color factor from a color in `space`, stored linear as glTF and the shaders
+    /// expect it.
+    pub fn set_base_color(&mut self, color: FColor, space: EColorSpace) {
+        self.base_color_factor = color.to_linear(space).into();
+    }
+
+    /// Linear emissive factor, alpha is 1
+    pub fn emissive_color(&self) -> FColor {
+        self.emissive_factor_alpha_cutoff.truncate().into()
+    }
+
+    /// Sets the emissive factor from a color in `space`, alpha is ignored.
+    pub fn set_emissive_color(&mut self, color: FColor, space: EColorSpace) {
+        let alpha_cutoff = self.emissive_factor_alpha_cutoff.w;
+        let linear: Vec3 = color.to_linear(space).into();
+        self.emissive_factor_alpha_cutoff = linear.extend(alpha_cutoff);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    #[test]
+    fn default_transform_is_identity() {
+        assert_eq!(TextureTransform::default().matrix(), Mat3::IDENTITY);
+    }
+
+    #[test]
+    fn rotation_turns_uvs_towards_negative_v() {
+        let transform = TextureTransform {
+            rotation: FRAC_PI_2,
+            ..Default::default()
+        };
+        let matrix = transform.matrix();
+        // v points down in uv space, so counter-clockwise on screen turns u towards -v.
+        assert!(matrix
+            .transform_point2(Vec2::X)
+            .abs_diff_eq(Vec2::new(0.0, -1.0), 1e-6));
+        assert!(matrix
+            .transform_point2(Vec2::Y)
+            .abs_diff_eq(Vec2::new(1.0, 0.0), 1e-6));
+    }
+
+    #[test]
+    fn scale_then_rotate_then_offset() {
+        let transform = TextureTransform {
+            offset: Vec2::new(0.5, 0.25),
+            rotation: FRAC_PI_2,
+            scale: Vec2::new(2.0, 3.0),
+            tex_coord: None,
+        };
+        // u' = cos * sx * u + sin * sy * v + tx, v' = -sin * sx * u + cos * sy * v + ty
+        let expected = Mat3::from_cols(
+            Vec3::new(0.0, -2.0, 0.0),
+            Vec3::new(3.0, 0.0, 0.0),
+            Vec3::new(0.5, 0.25, 1.0),
+        );
+        let matrix = transform.matrix();
+        assert!(matrix.abs_diff_eq(expected, 1e-6), "{matrix}");
+        assert!(matrix
+            .transform_point2(Vec2::ONE)
+            .abs_diff_eq(Vec2::new(3.5, -1.75), 1e-6));
+    }
+}
@@ -1,38 +1,216 @@
-use bevy_ecs::prelude::*;
-use bevy_math::{Mat4, Vec2, Vec3, Vec4};
+use crate::color::FColor;
+use crate::component::Transform;
+use bevy_math::{Mat4, Quat, Vec2, Vec3, Vec4};
 use mo_vk::{Texture, TextureCreateInfo};
+use thiserror::Error;
 use vulkano::format::Format;
 
+pub mod animation;
+pub mod camera;
+pub mod instance;
 pub mod material;
 pub mod primitives;
+pub mod skin;
 
+pub use animation::{AnimationChannel, AnimationClip, EAnimationProperty, EInterpolation};
+pub use camera::CameraPreset;
+pub use instance::{MeshDraw, ModelInstance};
 pub use material::*;
 pub use primitives::*;
+pub use skin::{Skin, SkinnedVertices};
 
 pub const DEFAULT_TEXTURE_MAP: u32 = u32::MAX;
 
-#[derive(Component)]
+/// Meshes and textures of a glTF file, shared by entities through a
+/// [`Handle<Model>`](crate::asset::Handle) from the [`AssetServer`](crate::asset::AssetServer)
 pub struct Model {
     pub meshes: Vec<Mesh>,
     pub textures: Vec<Texture>,
+    /// glTF nodes, in the order of the file
+    pub nodes: Vec<ModelNode>,
+    /// Indices of the root nodes of the default scene, or the first scene without a default
+    pub root_nodes: Vec<usize>,
+    pub skins: Vec<Skin>,
+    pub animations: Vec<AnimationClip>,
+    /// Camera of every node with one, in the order of the nodes
+    pub cameras: Vec<CameraPreset>,
+    /// Names of the KHR_materials_variants variants, selected with a
+    /// [`MaterialVariant`](crate::component::MaterialVariant)
+    pub variants: Vec<String>,
+    /// Materials of the variants, indexed by [`Mesh::variants`]
+    pub variant_materials: Vec<GltfMaterialCPU>,
+    /// Path passed to [`Model::load_gltf`]
+    pub path: String,
+}
+
+/// A glTF node, spawned as an entity when the model is imported through a
+/// [`SceneRoot`](crate::component::SceneRoot)
+pub struct ModelNode {
+    pub name: Option<String>,
+    /// Transform relative to the parent node
+    pub transform: Transform,
+    pub children: Vec<usize>,
+    /// Indices into [`Model::meshes`], drawn with the transform of the node instead of
+    /// [`Mesh::world`]
+    pub meshes: Vec<usize>,
+    /// Default weights of the morph targets of the meshes, empty if they have none
+    pub morph_weights: Vec<f32>,
+    /// KHR_lights_punctual light of the node
+    pub light: Option<NodeLight>,
+}
+
+/// A KHR_lights_punctual light, spawned as a light component on the entity of its node
+#[derive(Clone, Debug)]
+pub struct NodeLight {
+    pub name: Option<String>,
+    /// Linear color
+    pub color: FColor,
+    /// Lux for directional lights, candela for point and spot lights
+    pub intensity: f32,
+    /// Distance at which point and spot lights fade out, `None` for an unlimited range
+    pub range: Option<f32>,
+    pub kind: ENodeLightKind,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum ENodeLightKind {
+    Directional,
+    Point,
+    /// Cone angles from the axis in radians
+    Spot {
+        inner_cone_angle: f32,
+        outer_cone_angle: f32,
+    },
+}
+
+impl NodeLight {
+    fn from_gltf(light: &gltf::khr_lights_punctual::Light) -> Self {
+        let kind = match light.kind() {
+            gltf::khr_lights_punctual::Kind::Directional => ENodeLightKind::Directional,
+            gltf::khr_lights_punctual::Kind::Point => ENodeLightKind::Point,
+            gltf::khr_lights_punctual::Kind::Spot {
+                inner_cone_angle,
+                outer_cone_angle,
+            } => ENodeLightKind::Spot {
+                inner_cone_angle,
+                outer_cone_angle,
+            },
+        };
+        Self {
+            name: light.name().map(str::to_string),
+            color: FColor::from(light.color()),
+            intensity: light.intensity(),
+            range: light.range(),
+            kind,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ModelError {
+    #[error("{0}")]
+    Gltf(#[from] gltf::Error),
+    #[error("primitive of mesh {mesh:?} has no {attribute}")]
+    MissingAttribute {
+        mesh: String,
+        attribute: &'static str,
+    },
+    #[error("{attribute} of mesh {mesh:?} has {count} elements, expected {expected}")]
+    AttributeCountMismatch {
+        mesh: String,
+        attribute: &'static str,
+        count: usize,
+        expected: usize,
+    },
+    #[error("mesh {mesh:?} references vertex {index} of {count}")]
+    InvalidIndex {
+        mesh: String,
+        index: u32,
+        count: usize,
+    },
+    #[error("mesh {mesh:?} references joint {joint} of a skin with {count} joints")]
+    InvalidJoint {
+        mesh: String,
+        joint: u32,
+        count: usize,
+    },
+    #[error("image {0} has an invalid size")]
+    InvalidImage(usize),
 }
 
 impl Model {
     pub fn load_gltf(path: &str) -> Model {
+        match Self::try_load_gltf(path) {
+            Ok(model) => model,
+            Err(err) => panic!("Loading model {} failed with error: {}", path, err),
+        }
+    }
+
+    /// Same as [`Model::load_gltf`], but returns the error if the file is not a valid glTF
+    /// model. Absolute paths are not resolved relative to `resources/gltf`.
+    pub fn try_load_gltf(path: &str) -> Result<Model, ModelError> {
         let root = std::env::current_dir().expect("Current working directory must be accessible");
         let path_buf = root.join("resources").join("gltf").join(path);
 
-        let (gltf, buffers, mut images) = match gltf::import(path_buf.clone()) {
-            Ok(result) => result,
-            Err(err) => panic!("Loading model {} failed with error: {}", path, err),
-        };
+        let (gltf, buffers, mut images) = gltf::import(path_buf.clone())?;
+        // Other scenes would be spawned on top of it, nodes shared by scenes twice.
+        let scene = gltf.default_scene().or_else(|| gltf.scenes().next());
 
         let mut model = Model {
             meshes: vec![],
             textures: vec![],
+            nodes: gltf
+                .nodes()
+                .map(|node| {
+                    let (translation, rotation, scale) = node.transform().decomposed();
+                    ModelNode {
+                        name: node.name().map(str::to_string),
+                        transform: Transform {
+                            translation: Vec3::from(translation),
+                            rotation: Quat::from_array(rotation),
+                            scale: Vec3::from(scale),
+                        },
+                        children: node.children().map(|child| child.index()).collect(),
+                        meshes: vec![],
+                        morph_weights: vec![],
+                        light: node.light().map(|light| NodeLight::from_gltf(&light)),
+                    }
+                })
+                .collect(),
+            root_nodes: scene
+                .iter()
+                .flat_map(|scene| scene.nodes().map(|node| node.index()))
+                .collect(),
+            skins: gltf
+                .skins()
+                .map(|skin| {
+                    let joints: Vec<usize> = skin.joints().map(|joint| joint.index()).collect();
+                    let inverse_bind_matrices = skin
+                        .reader(|buffer| Some(&buffers[buffer.index()]))
+                        .read_inverse_bind_matrices()
+                        .map(|matrices| matrices.map(|m| Mat4::from_cols_array_2d(&m)).collect())
+                        .unwrap_or_else(|| vec![Mat4::IDENTITY; joints.len()]);
+                    Skin {
+                        name: skin.name().map(str::to_string),
+                        joints,
+                        inverse_bind_matrices,
+                    }
+                })
+                .collect(),
+            animations: gltf
+                .animations()
+                .map(|animation| AnimationClip::from_gltf(&animation, &buffers))
+                .collect(),
+            cameras: vec![],
+            variants: gltf
+                .variants()
+                .map(|variants| variants.map(|variant| variant.name().to_string()).collect())
+                .unwrap_or_default(),
+            variant_materials: vec![],
+            path: path.to_string(),
         };
 
-        for image in &mut images {
+        for (image_index, image) in images.iter_mut().enumerate() {
             // Convert images from rgb8 to rgba8
             if image.format == gltf::image::Format::R8G8B8 {
                 let dynamic_image = image::DynamicImage::ImageRgb8(
@@ -41,7 +219,7 @@ impl Model {
                         image.height,
                         std::mem::take(&mut image.pixels),
                     )
-                    .unwrap(),
+                    .ok_or(ModelError::InvalidImage(image_index))?,
                 );
 
                 let rgba8_image = dynamic_image.to_rgba8();
@@ -77,20 +255,96 @@ impl Model {
             model.textures.push(texture);
         }
 
-        for scene in gltf.scenes() {
-            for node in scene.nodes() {
-                Model::load_node(
-                    &gltf,
+        for node in scene.iter().flat_map(|scene| scene.nodes()) {
+            Model::load_node(
+                &gltf,
+                &node,
+                &mut model,
+                &buffers,
+                Mat4::IDENTITY,
+                path_buf.clone(),
+            )?;
+        }
+
+        let rest_transforms = model.rest_transforms();
+        model.cameras = gltf
+            .nodes()
+            .filter_map(|node| {
+                let camera = node.camera()?;
+                Some(CameraPreset::from_gltf(
                     &node,
-                    &mut model,
-                    &buffers,
-                    Mat4::IDENTITY,
-                    path_buf.clone(),
-                );
+                    &camera,
+                    rest_transforms[node.index()],
+                ))
+            })
+            .collect();
+
+        Ok(model)
+    }
+
+    /// Axis aligned bounding box `(min, max)` of all vertices in model space, `None` if the model
+    /// has no vertices.
+    pub fn bounds(&self) -> Option<(Vec3, Vec3)> {
+        let mut bounds: Option<(Vec3, Vec3)> = None;
+
+        for mesh in &self.meshes {
+            for vertex in &mesh.primitive.vertices {
+                let position = mesh
+                    .world
+                    .transform_point3(Vec4::from(vertex.position).truncate());
+                bounds = Some(match bounds {
+                    Some((min, max)) => (min.min(position), max.max(position)),
+                    None => (position, position),
+                });
             }
         }
 
-        model
+        bounds
+    }
+
+    /// Index into [`Model::animations`] of the first animation named `name`
+    pub fn find_animation(&self, name: &str) -> Option<usize> {
+        self.animations
+            .iter()
+            .position(|animation| animation.name.as_deref() == Some(name))
+    }
+
+    /// Index into [`Model::variants`] of the variant named `name`
+    pub fn find_variant(&self, name: &str) -> Option<usize> {
+        self.variants.iter().position(|variant| variant == name)
+    }
+
+    /// Material of a [`MeshDraw`], the materials of the meshes are followed by
+    /// [`Model::variant_materials`].
+    pub fn material(&self, index: usize) -> &GltfMaterialCPU {
+        match index.checked_sub(self.meshes.len()) {
+            Some(variant_index) => &self.variant_materials[variant_index],
+            None => &self.meshes[index].material,
+        }
+    }
+
+    /// Index into [`Model::cameras`] of the first camera named `name`
+    pub fn find_camera(&self, name: &str) -> Option<usize> {
+        self.cameras
+            .iter()
+            .position(|camera| camera.name.as_deref() == Some(name))
+    }
+
+    /// Model space transform of every node in the rest pose of the file
+    pub fn rest_transforms(&self) -> Vec<Mat4> {
+        fn visit(model: &Model, node: usize, parent: Mat4, transforms: &mut [Mat4]) {
+            let transform = parent * model.nodes[node].transform.model_matrix();
+            transforms[node] = transform;
+            for &child in &model.nodes[node].children {
+                visit(model, child, transform, transforms);
+            }
+        }
+
+        let mut transforms = vec![Mat4::IDENTITY; self.nodes.len()];
+        for &root in &self.root_nodes {
+            visit(self, root, Mat4::IDENTITY, &mut transforms);
+        }
+        transforms
     }
 
     pub fn load_node(
@@ -100,22 +354,67 @@ impl Model {
         buffers: &[gltf::buffer::Data],
         parent_transform: Mat4,
         path: std::path::PathBuf,
-    ) {
+    ) -> Result<(), ModelError> {
         let node_transform =
             parent_transform * Mat4::from_cols_array_2d(&node.transform().matrix());
 
         for child in node.children() {
-            Model::load_node(gltf, &child, model, buffers, node_transform, path.clone());
+            Model::load_node(gltf, &child, model, buffers, node_transform, path.clone())?;
         }
 
         if let Some(mesh) = node.mesh() {
+            // A node used by several scenes is loaded once per use, the node entity draws the
+            // meshes of the first.
+            let record_meshes = model.nodes[node.index()].meshes.is_empty();
+
+            let mesh_name = || mesh.name().unwrap_or_default().to_string();
+            let missing = |attribute| ModelError::MissingAttribute {
+                mesh: mesh_name(),
+                attribute,
+            };
+            // Weights of the node override the ones of the mesh.
+            let default_morph_weights = node.weights().or(mesh.weights()).unwrap_or_default();
+
             for primitive in mesh.primitives() {
                 // ================================= Loading vertices =================================
                 let reader = primitive.reader(|i| Some(&buffers[i.index()]));
 
-                let indices: Vec<_> = reader.read_indices().unwrap().into_u32().collect();
-                let positions: Vec<_> = reader.read_positions().unwrap().map(Vec3::from).collect();
-                let normals: Vec<_> = reader.read_normals().unwrap().map(Vec3::from).collect();
+                let indices: Vec<_> = reader
+                    .read_indices()
+                    .ok_or_else(|| missing("indices"))?
+                    .into_u32()
+                    .collect();
+                let positions: Vec<_> = reader
+                    .read_positions()
+                    .ok_or_else(|| missing("positions"))?
+                    .map(Vec3::from)
+                    .collect();
+                // Every attribute has one element per position, the vertices are indexed by them.
+                let check_count = |attribute, count| {
+                    if count == positions.len() {
+                        Ok(())
+                    } else {
+                        Err(ModelError::AttributeCountMismatch {
+                            mesh: mesh_name(),
+                            attribute,
+                            count,
+                            expected: positions.len(),
+                        })
+                    }
+                };
+                if let Some(&index) = indices.iter().find(|&&i| i as usize >= positions.len()) {
+                    return Err(ModelError::InvalidIndex {
+                        mesh: mesh_name(),
+                        index,
+                        count: positions.len(),
+                    });
+                }
+                let normals: Vec<_> = reader
+                    .read_normals()
+                    .ok_or_else(|| missing("normals"))?
+                    .map(Vec3::from)
+                    .collect();
+                check_count("normals", normals.len())?;
                 let tex_coords0 = if let Some(tex_coords) = reader.read_tex_coords(0) {
                     tex_coords.into_f32().map(Vec2::from).collect()
                 } else {
@@ -139,6 +438,74 @@ impl Model {
                 } else {
                     vec![Vec4::new(1.0, 1.0, 1.0, 1.0); positions.len()]
                 };
+                check_count("uv set 0", tex_coords0.len())?;
+                check_count("uv set 1", tex_coords1.len())?;
+                check_count("tangents", tangents.len())?;
+                check_count("colors", colors.len())?;
+
+                // Skinned primitives are only deformed if the node has a skin.
+                let skin_vertices: Option<Vec<SkinVertex>> = node
+                    .skin()
+                    .and(reader.read_joints(0))
+                    .zip(reader.read_weights(0))
+                    .map(|(joints, weights)| {
+                        joints
+                            .into_u16()
+                            .zip(weights.into_f32())
+                            .map(|(joints, weights)| SkinVertex {
+                                joints: joints.map(u32::from),
+                                weights,
+                            })
+                            .collect()
+                    });
+                // Joints index the joint matrices in the skinning shader.
+                if let Some(skin_vertices) = &skin_vertices {
+                    check_count("joints and weights", skin_vertices.len())?;
+                    let joint_count = node.skin().map_or(0, |skin| skin.joints().count());
+                    let invalid_joint = skin_vertices
+                        .iter()
+                        .flat_map(|vertex| vertex.joints)
+                        .find(|&joint| joint as usize >= joint_count);
+                    if let Some(joint) = invalid_joint {
+                        return Err(ModelError::InvalidJoint {
+                            mesh: mesh_name(),
+                            joint,
+                            count: joint_count,
+                        });
+                    }
+                }
+
+                // Offsets of every vertex for each target in turn, missing attributes stay zero.
+                let mut morph_targets: Vec<MorphVertex> = vec![];
+                for (position_offsets, normal_offsets, tangent_offsets) in
+                    reader.read_morph_targets()
+                {
+                    let mut target = vec![MorphVertex::default(); positions.len()];
+                    if let Some(offsets) = position_offsets {
+                        let offsets: Vec<[f32; 3]> = offsets.collect();
+                        check_count("morph target positions", offsets.len())?;
+                        for (vertex, offset) in target.iter_mut().zip(offsets) {
+                            vertex.position = Vec3::from(offset).extend(0.0).into();
+                        }
+                    }
+                    if let Some(offsets) = normal_offsets {
+                        let offsets: Vec<[f32; 3]> = offsets.collect();
+                        check_count("morph target normals", offsets.len())?;
+                        for (vertex, offset) in target.iter_mut().zip(offsets) {
+                            vertex.normal = Vec3::from(offset).extend(0.0).into();
+                        }
+                    }
+                    if let Some(offsets) = tangent_offsets {
+                        let offsets: Vec<[f32; 3]> = offsets.collect();
+                        check_count("morph target tangents", offsets.len())?;
+                        for (vertex, offset) in target.iter_mut().zip(offsets) {
+                            vertex.tangent = Vec3::from(offset).extend(0.0).into();
+                        }
+                    }
+                    morph_targets.extend(target);
+                }
+                let mut morph_weights = default_morph_weights.to_vec();
+                morph_weights.resize(morph_targets.len() / positions.len().max(1), 0.0);
 
                 let mut vertices: Vec<StaticVertex> = vec![];
 
@@ -156,115 +523,422 @@ impl Model {
                 // ================================= End Loading vertices =================================
                 // ================================= Start Loading materials =================================
 
-                let material = primitive.material();
-                let pbr = material.pbr_metallic_roughness();
-
-                let get_texture_index = |texture_info: Option<gltf::texture::Info>| {
-                    texture_info
-                        .and_then(|tex| {
-                            // 先获取texture索引
-                            let texture_idx = tex.texture().index();
-                            // 再通过texture获取对应的image索引
-                            gltf.textures()
-                                .nth(texture_idx)
-                                .and_then(|t| Some(t.source().index()))
-                        })
-                        .map(|image_idx| image_idx as u32)
-                        .unwrap_or(DEFAULT_TEXTURE_MAP)
-                };
-
-                let diffuse_index = get_texture_index(pbr.base_color_texture());
-                let metallic_roughness_index = get_texture_index(pbr.metallic_roughness_texture());
-                let emissive_index = get_texture_index(material.emissive_texture());
-
-                let normal_index = material
-                    .normal_texture()
-                    .and_then(|tex| {
-                        // 先获取texture索引
-                        let texture_idx = tex.texture().index();
-                        // 再通过texture获取对应的image索引
-                        gltf.textures()
-                            .nth(texture_idx)
-                            .and_then(|t| Some(t.source().index()))
-                    })
-                    .map(|image_idx| image_idx as u32)
-                    .unwrap_or(DEFAULT_TEXTURE_MAP);
-                let occlusion_index = material
-                    .occlusion_texture()
-                    .and_then(|tex| {
-                        // 先获取texture索引
-                        let texture_idx = tex.texture().index();
-                        // 再通过texture获取对应的image索引
-                        gltf.textures()
-                            .nth(texture_idx)
-                            .and_then(|t| Some(t.source().index()))
-                    })
-                    .map(|image_idx| image_idx as u32)
-                    .unwrap_or(DEFAULT_TEXTURE_MAP);
-
-                let base_color_factor = material.pbr_metallic_roughness().base_color_factor();
-                let metallic_factor = material.pbr_metallic_roughness().metallic_factor();
-                let roughness_factor = material.pbr_metallic_roughness().roughness_factor();
-                let emissive_factor: Vec3 = material.emissive_factor().into();
-
-                let mut alpha_cutoff = 0.0f32;
-                // 在primitive处理部分添加：
-                let alpha_mode = match material.alpha_mode() {
-                    gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
-                    gltf::material::AlphaMode::Mask => {
-                        alpha_cutoff = material.alpha_cutoff().unwrap_or(0.5);
-                        AlphaMode::Mask
+                let mesh_material = Model::load_material(gltf, &primitive.material());
+
+                // KHR_materials_variants, variants without a mapping keep the mesh material.
+                let mut variants = vec![None; model.variants.len()];
+                for mapping in primitive.mappings() {
+                    let material_index = model.variant_materials.len();
+                    model
+                        .variant_materials
+                        .push(Model::load_material(gltf, &mapping.material()));
+                    for &variant in mapping.variants() {
+                        if let Some(slot) = variants.get_mut(variant as usize) {
+                            *slot = Some(material_index);
+                        }
                     }
-                    gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+                }
+
+                if record_meshes {
+                    model.nodes[node.index()].meshes.push(model.meshes.len());
+                }
+                let skin = skin_vertices
+                    .as_ref()
+                    .and(node.skin())
+                    .map(|skin| skin.index());
+                if record_meshes
+                    && morph_weights.len() > model.nodes[node.index()].morph_weights.len()
+                {
+                    model.nodes[node.index()].morph_weights = morph_weights.clone();
+                }
+                let primitive = if skin_vertices.is_some() || !morph_targets.is_empty() {
+                    MeshPrimitive::deformed(indices, vertices, skin_vertices, morph_targets)
+                } else {
+                    MeshPrimitive::new(indices, vertices)
                 };
 
-                // 在load_node的primitive处理部分补充：
-                let base_color_tex_info = pbr.base_color_texture();
-                let base_color_uv_set = base_color_tex_info.map(|t| t.tex_coord()).unwrap_or(0);
+                model.meshes.push(Mesh {
+                    primitive,
+                    material: mesh_material,
+                    world: node_transform,
+                    skin,
+                    morph_weights,
+                    variants,
+                });
+            }
+        }
 
-                let normal_tex_info = material.normal_texture();
-                let normal_uv_set = normal_tex_info.map(|t| t.tex_coord()).unwrap_or(0);
+        Ok(())
+    }
 
-                let metallic_roughness_tex_info = pbr.metallic_roughness_texture();
-                let metallic_roughness_uv_set = metallic_roughness_tex_info
-                    .map(|t| t.tex_coord())
-                    .unwrap_or(0);
+    /// Converts a glTF material and its extensions. Texture indices are the ones of the images
+    /// of the file.
+    fn load_material(gltf: &gltf::Document, material: &gltf::Material) -> GltfMaterialCPU {
+        let pbr = material.pbr_metallic_roughness();
+
+        let get_texture_index = |texture_info: Option<gltf::texture::Info>| {
+            texture_info
+                .and_then(|tex| {
+                    // 先获取texture索引
+                    let texture_idx = tex.texture().index();
+                    // 再通过texture获取对应的image索引
+                    gltf.textures()
+                        .nth(texture_idx)
+                        .and_then(|t| Some(t.source().index()))
+                })
+                .map(|image_idx| image_idx as u32)
+                .unwrap_or(DEFAULT_TEXTURE_MAP)
+        };
 
-                let occlusion_tex_info = material.occlusion_texture();
-                let occlusion_uv_set = occlusion_tex_info.map(|t| t.tex_coord()).unwrap_or(0);
+        // Factor and texture of an extension only available as raw JSON
+        let json_factor = |extension: &gltf::json::Value, key: &str, default: f32| {
+            extension
+                .get(key)
+                .and_then(|value| value.as_f64())
+                .map_or(default, |value| value as f32)
+        };
+        let json_vec2 = |value: &gltf::json::Value, key: &str, default: Vec2| {
+            let components: Vec<f32> = value
+                .get(key)
+                .and_then(|value| value.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|c| c.as_f64())
+                .map(|c| c as f32)
+                .collect();
+            match components[..] {
+                [x, y] => Vec2::new(x, y),
+                _ => default,
+            }
+        };
 
-                let emissive_tex_info = material.emissive_texture();
-                let emissive_uv_set = emissive_tex_info.map(|t| t.tex_coord()).unwrap_or(0);
+        // KHR_texture_transform of a texture. The gltf crate parses it for texture infos
+        // only, normal and occlusion textures and raw JSON infos carry it as JSON.
+        let info_transform = |info: Option<&gltf::texture::Info>| {
+            info.and_then(|info| info.texture_transform()).map_or_else(
+                TextureTransform::default,
+                |transform| TextureTransform {
+                    offset: transform.offset().into(),
+                    rotation: transform.rotation(),
+                    scale: transform.scale().into(),
+                    tex_coord: transform.tex_coord(),
+                },
+            )
+        };
+        let json_transform = |transform: Option<&gltf::json::Value>| {
+            transform.map_or_else(TextureTransform::default, |transform| TextureTransform {
+                offset: json_vec2(transform, "offset", Vec2::ZERO),
+                rotation: json_factor(transform, "rotation", 0.0),
+                scale: json_vec2(transform, "scale", Vec2::ONE),
+                tex_coord: transform
+                    .get("texCoord")
+                    .and_then(|uv| uv.as_u64())
+                    .map(|uv| uv as u32),
+            })
+        };
+        let json_texture = |extension: &gltf::json::Value, key: &str| {
+            let Some(info) = extension.get(key) else {
+                return (DEFAULT_TEXTURE_MAP, 0, TextureTransform::default());
+            };
+            let texture = info
+                .get("index")
+                .and_then(|index| index.as_u64())
+                .and_then(|index| gltf.textures().nth(index as usize))
+                .map_or(DEFAULT_TEXTURE_MAP, |t| t.source().index() as u32);
+            let uv = info.get("texCoord").and_then(|uv| uv.as_u64()).unwrap_or(0);
+            let transform = json_transform(
+                info.get("extensions")
+                    .and_then(|extensions| extensions.get("KHR_texture_transform")),
+            );
+            (texture, uv as u32, transform)
+        };
 
-                model.meshes.push(Mesh {
-                    primitive: MeshPrimitive::new(indices, vertices),
-                    material: GltfMaterialCPU {
-                        // Texture IDs
-                        base_color_texture: diffuse_index,
-                        normal_texture: normal_index,
-                        surface_properties_texture: metallic_roughness_index,
-                        occlusion_texture: occlusion_index,
-                        emissive_texture: emissive_index,
-                        // UV Sets
-                        base_color_uv: base_color_uv_set,
-                        normal_uv: normal_uv_set,
-                        surface_properties_uv: metallic_roughness_uv_set,
-                        occlusion_uv: occlusion_uv_set,
-                        emissive_uv: emissive_uv_set,
-                        // Alpha Mode
-                        alpha_mode,
-                        // Color Factors
-                        base_color_factor: Vec4::from(base_color_factor),
-                        ormn: Vec4::new(1.0, roughness_factor, metallic_factor, 1.0),
-                        emissive_factor_alpha_cutoff: (emissive_factor, alpha_cutoff).into(),
-                        // Raytracing properties
-                        material_type: MaterialType::MetallicRoughness,
-                        ..Default::default()
-                    },
-                    gpu_mat_index: 0,
-                    world: node_transform,
-                });
+        let diffuse_index = get_texture_index(pbr.base_color_texture());
+        let metallic_roughness_index = get_texture_index(pbr.metallic_roughness_texture());
+        let emissive_index = get_texture_index(material.emissive_texture());
+
+        let normal_index = material
+            .normal_texture()
+            .and_then(|tex| {
+                // 先获取texture索引
+                let texture_idx = tex.texture().index();
+                // 再通过texture获取对应的image索引
+                gltf.textures()
+                    .nth(texture_idx)
+                    .and_then(|t| Some(t.source().index()))
+            })
+            .map(|image_idx| image_idx as u32)
+            .unwrap_or(DEFAULT_TEXTURE_MAP);
+        let occlusion_index = material
+            .occlusion_texture()
+            .and_then(|tex| {
+                // 先获取texture索引
+                let texture_idx = tex.texture().index();
+                // 再通过texture获取对应的image索引
+                gltf.textures()
+                    .nth(texture_idx)
+                    .and_then(|t| Some(t.source().index()))
+            })
+            .map(|image_idx| image_idx as u32)
+            .unwrap_or(DEFAULT_TEXTURE_MAP);
+
+        let base_color_factor = material.pbr_metallic_roughness().base_color_factor();
+        let metallic_factor = material.pbr_metallic_roughness().metallic_factor();
+        let roughness_factor = material.pbr_metallic_roughness().roughness_factor();
+        let emissive_factor: Vec3 = material.emissive_factor().into();
+        // KHR_materials_emissive_strength scales the factor past 1.0.
+        let emissive_strength = material.emissive_strength().unwrap_or(1.0);
+        // KHR_materials_unlit
+        let material_type = if material.unlit() {
+            MaterialType::Unlit
+        } else {
+            MaterialType::MetallicRoughness
+        };
+
+        let mut alpha_cutoff = 0.0f32;
+        // 在primitive处理部分添加：
+        let alpha_mode = match material.alpha_mode() {
+            gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+            gltf::material::AlphaMode::Mask => {
+                alpha_cutoff = material.alpha_cutoff().unwrap_or(0.5);
+                AlphaMode::Mask
+            }
+            gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+        };
+
+        // 在load_node的primitive处理部分补充：
+        let base_color_tex_info = pbr.base_color_texture();
+        let base_color_uv_set = base_color_tex_info.map(|t| t.tex_coord()).unwrap_or(0);
+
+        let normal_tex_info = material.normal_texture();
+        let normal_uv_set = normal_tex_info.map(|t| t.tex_coord()).unwrap_or(0);
+
+        let metallic_roughness_tex_info = pbr.metallic_roughness_texture();
+        let metallic_roughness_uv_set = metallic_roughness_tex_info
+            .map(|t| t.tex_coord())
+            .unwrap_or(0);
+
+        let occlusion_tex_info = material.occlusion_texture();
+        let occlusion_uv_set = occlusion_tex_info.map(|t| t.tex_coord()).unwrap_or(0);
+
+        let emissive_tex_info = material.emissive_texture();
+        let emissive_uv_set = emissive_tex_info.map(|t| t.tex_coord()).unwrap_or(0);
+
+        // Material extensions, zero factors disable the layers.
+        let mut layers = GltfMaterialCPU {
+            sheen_factors: Vec4::ZERO,
+            clearcoat_transmission_thickness: Vec4::ZERO,
+            ..Default::default()
+        };
+        layers.texture_transforms[ETextureSlot::BaseColor as usize] =
+            info_transform(pbr.base_color_texture().as_ref());
+        layers.texture_transforms[ETextureSlot::Normal as usize] = json_transform(
+            material
+                .normal_texture()
+                .and_then(|t| t.extension_value("KHR_texture_transform").cloned())
+                .as_ref(),
+        );
+        layers.texture_transforms[ETextureSlot::SurfaceProperties as usize] =
+            info_transform(pbr.metallic_roughness_texture().as_ref());
+        layers.texture_transforms[ETextureSlot::Occlusion as usize] = json_transform(
+            material
+                .occlusion_texture()
+                .and_then(|t| t.extension_value("KHR_texture_transform").cloned())
+                .as_ref(),
+        );
+        layers.texture_transforms[ETextureSlot::Emissive as usize] =
+            info_transform(material.emissive_texture().as_ref());
+
+        if let Some(clearcoat) = material.clearcoat() {
+            layers.clearcoat_transmission_thickness.x = clearcoat.clearcoat_factor();
+            layers.clearcoat_transmission_thickness.y = clearcoat.clearcoat_roughness_factor();
+
+            let clearcoat_tex_info = clearcoat.clearcoat_texture();
+            layers.texture_transforms[ETextureSlot::Clearcoat as usize] =
+                info_transform(clearcoat_tex_info.as_ref());
+            layers.clearcoat_uv = clearcoat_tex_info
+                .as_ref()
+                .map(|t| t.tex_coord())
+                .unwrap_or(0);
+            layers.clearcoat_texture = get_texture_index(clearcoat_tex_info);
+
+            let clearcoat_roughness_tex_info = clearcoat.clearcoat_roughness_texture();
+            layers.texture_transforms[ETextureSlot::ClearcoatRoughness as usize] =
+                info_transform(clearcoat_roughness_tex_info.as_ref());
+            layers.clearcoat_roughness_uv = clearcoat_roughness_tex_info
+                .as_ref()
+                .map(|t| t.tex_coord())
+                .unwrap_or(0);
+            layers.clearcoat_roughness_texture = get_texture_index(clearcoat_roughness_tex_info);
+
+            if let Some(tex) = clearcoat.clearcoat_normal_texture() {
+                layers.clearcoat_normal_uv = tex.tex_coord();
+                layers.texture_transforms[ETextureSlot::ClearcoatNormal as usize] =
+                    json_transform(tex.extension_value("KHR_texture_transform"));
+                layers.clearcoat_normal_texture = tex.texture().source().index() as u32;
             }
         }
+        if let Some(sheen) = material.sheen() {
+            layers.sheen_factors =
+                Vec3::from(sheen.sheen_color_factor()).extend(sheen.sheen_roughness_factor());
+
+            let sheen_tex_info = sheen.sheen_color_texture();
+            layers.texture_transforms[ETextureSlot::Sheen as usize] =
+                info_transform(sheen_tex_info.as_ref());
+            layers.sheen_uv = sheen_tex_info.as_ref().map(|t| t.tex_coord()).unwrap_or(0);
+            layers.sheen_texture = get_texture_index(sheen_tex_info);
+
+            let sheen_roughness_tex_info = sheen.sheen_roughness_texture();
+            layers.texture_transforms[ETextureSlot::SheenRoughness as usize] =
+                info_transform(sheen_roughness_tex_info.as_ref());
+            layers.sheen_roughness_uv = sheen_roughness_tex_info
+                .as_ref()
+                .map(|t| t.tex_coord())
+                .unwrap_or(0);
+            layers.sheen_roughness_texture = get_texture_index(sheen_roughness_tex_info);
+        }
+
+        // KHR_materials_transmission and KHR_materials_volume
+        if let Some(transmission) = material.transmission() {
+            layers.clearcoat_transmission_thickness.z = transmission.transmission_factor();
+
+            let transmission_tex_info = transmission.transmission_texture();
+            layers.texture_transforms[ETextureSlot::Transmission as usize] =
+                info_transform(transmission_tex_info.as_ref());
+            layers.transmission_uv = transmission_tex_info
+                .as_ref()
+                .map(|t| t.tex_coord())
+                .unwrap_or(0);
+            layers.transmission_texture = get_texture_index(transmission_tex_info);
+        }
+        if let Some(volume) = material.volume() {
+            layers.clearcoat_transmission_thickness.w = volume.thickness_factor();
+            layers.attenuation =
+                Vec3::from(volume.attenuation_color()).extend(volume.attenuation_distance());
+
+            let thickness_tex_info = volume.thickness_texture();
+            layers.texture_transforms[ETextureSlot::Thickness as usize] =
+                info_transform(thickness_tex_info.as_ref());
+            layers.thickness_uv = thickness_tex_info
+                .as_ref()
+                .map(|t| t.tex_coord())
+                .unwrap_or(0);
+            layers.thickness_texture = get_texture_index(thickness_tex_info);
+        }
+
+        // KHR_materials_specular and KHR_materials_ior, the reflectance of dielectrics
+        if let Some(specular) = material.specular() {
+            layers.specular_factors =
+                Vec3::from(specular.specular_color_factor()).extend(specular.specular_factor());
+
+            let specular_tex_info = specular.specular_texture();
+            layers.texture_transforms[ETextureSlot::Specular as usize] =
+                info_transform(specular_tex_info.as_ref());
+            layers.specular_uv = specular_tex_info
+                .as_ref()
+                .map(|t| t.tex_coord())
+                .unwrap_or(0);
+            layers.specular_texture = get_texture_index(specular_tex_info);
+
+            let specular_color_tex_info = specular.specular_color_texture();
+            layers.texture_transforms[ETextureSlot::SpecularColor as usize] =
+                info_transform(specular_color_tex_info.as_ref());
+            layers.specular_color_uv = specular_color_tex_info
+                .as_ref()
+                .map(|t| t.tex_coord())
+                .unwrap_or(0);
+            layers.specular_color_texture = get_texture_index(specular_color_tex_info);
+        }
+        layers.ior = material.ior().unwrap_or(layers.ior);
+
+        // KHR_materials_iridescence and KHR_materials_anisotropy are not parsed by the gltf
+        // crate, they are read from the raw extension JSON. The thin-film thickness is given
+        // in nanometers.
+        if let Some(iridescence) = material.extension_value("KHR_materials_iridescence") {
+            layers.iridescence_factors = Vec4::new(
+                json_factor(iridescence, "iridescenceFactor", 0.0),
+                json_factor(iridescence, "iridescenceIor", 1.3),
+                json_factor(iridescence, "iridescenceThicknessMinimum", 100.0),
+                json_factor(iridescence, "iridescenceThicknessMaximum", 400.0),
+            );
+            (
+                layers.iridescence_texture,
+                layers.iridescence_uv,
+                layers.texture_transforms[ETextureSlot::Iridescence as usize],
+            ) = json_texture(iridescence, "iridescenceTexture");
+            (
+                layers.iridescence_thickness_texture,
+                layers.iridescence_thickness_uv,
+                layers.texture_transforms[ETextureSlot::IridescenceThickness as usize],
+            ) = json_texture(iridescence, "iridescenceThicknessTexture");
+        }
+        if let Some(anisotropy) = material.extension_value("KHR_materials_anisotropy") {
+            layers.anisotropy_factors.x = json_factor(anisotropy, "anisotropyStrength", 0.0);
+            layers.anisotropy_factors.y = json_factor(anisotropy, "anisotropyRotation", 0.0);
+            (
+                layers.anisotropy_texture,
+                layers.anisotropy_uv,
+                layers.texture_transforms[ETextureSlot::Anisotropy as usize],
+            ) = json_texture(anisotropy, "anisotropyTexture");
+        }
+
+        let mut mesh_material = GltfMaterialCPU {
+            // Texture IDs
+            base_color_texture: diffuse_index,
+            normal_texture: normal_index,
+            surface_properties_texture: metallic_roughness_index,
+            occlusion_texture: occlusion_index,
+            emissive_texture: emissive_index,
+            // UV Sets
+            base_color_uv: base_color_uv_set,
+            normal_uv: normal_uv_set,
+            surface_properties_uv: metallic_roughness_uv_set,
+            occlusion_uv: occlusion_uv_set,
+            emissive_uv: emissive_uv_set,
+            // Alpha Mode
+            alpha_mode,
+            // Color Factors
+            base_color_factor: Vec4::from(base_color_factor),
+            ormn: Vec4::new(1.0, roughness_factor, metallic_factor, 1.0),
+            emissive_factor_alpha_cutoff: (emissive_factor, alpha_cutoff).into(),
+            emissive_strength,
+            // Raytracing properties
+            material_type,
+            ..layers
+        };
+
+        // KHR_materials_pbrSpecularGlossiness, shaded natively. The diffuse and
+        // specular-glossiness textures take the slots of the base color and
+        // metallic-roughness ones.
+        if let Some(sg) = material
+            .pbr_specular_glossiness()
+            .filter(|_| !material.unlit())
+        {
+            mesh_material.material_type = MaterialType::SpecularGlossiness;
+            mesh_material.base_color_factor = Vec4::from(sg.diffuse_factor());
+            mesh_material.specular_glossiness =
+                Vec3::from(sg.specular_factor()).extend(sg.glossiness_factor());
+
+            let diffuse_tex_info = sg.diffuse_texture();
+            mesh_material.texture_transforms[ETextureSlot::BaseColor as usize] =
+                info_transform(diffuse_tex_info.as_ref());
+            mesh_material.base_color_uv = diffuse_tex_info
+                .as_ref()
+                .map(|t| t.tex_coord())
+                .unwrap_or(0);
+            mesh_material.base_color_texture = get_texture_index(diffuse_tex_info);
+
+            let specular_glossiness_tex_info = sg.specular_glossiness_texture();
+            mesh_material.texture_transforms[ETextureSlot::SurfaceProperties as usize] =
+                info_transform(specular_glossiness_tex_info.as_ref());
+            mesh_material.surface_properties_uv = specular_glossiness_tex_info
+                .as_ref()
+                .map(|t| t.tex_coord())
+                .unwrap_or(0);
+            mesh_material.surface_properties_texture =
+                get_texture_index(specular_glossiness_tex_info);
+        }
+
+        mesh_material
     }
 }
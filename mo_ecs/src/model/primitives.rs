@@ -35,9 +35,32 @@ impl Default for StaticVertex {
     }
 }
 
+/// Joints and weights of a skinned vertex, read by the skinning compute pass
+#[derive(BufferContents, Clone, Copy, Default)]
+#[repr(C)]
+pub struct SkinVertex {
+    /// Indices into [`Skin::joints`](crate::model::Skin::joints)
+    pub joints: [u32; 4],
+    pub weights: [f32; 4],
+}
+
+/// Position, normal and tangent offsets of a vertex for one morph target
+#[derive(BufferContents, Clone, Copy, Default)]
+#[repr(C)]
+pub struct MorphVertex {
+    pub position: [f32; 4],
+    pub normal: [f32; 4],
+    pub tangent: [f32; 4],
+}
+
 pub struct MeshPrimitive {
     pub vertex_buffer: Subbuffer<[StaticVertex]>,
     pub index_buffer: Subbuffer<[u32]>,
+    /// Joints and weights of every vertex, only for skinned primitives
+    pub skin_buffer: Option<Subbuffer<[SkinVertex]>>,
+    /// Offsets of every vertex for each morph target in turn, only for morphed primitives
+    pub morph_buffer: Option<Subbuffer<[MorphVertex]>>,
+    pub morph_target_count: usize,
     pub indices: Vec<u32>,
     pub vertices: Vec<StaticVertex>,
 }
@@ -45,18 +68,42 @@ pub struct MeshPrimitive {
 pub struct Mesh {
     pub primitive: MeshPrimitive,
     pub material: GltfMaterialCPU,
-    pub gpu_mat_index: u32,
     pub world: Mat4,
+    /// Index into [`Model::skins`](crate::model::Model::skins) if the mesh is skinned
+    pub skin: Option<usize>,
+    /// Default weights of the morph targets
+    pub morph_weights: Vec<f32>,
+    /// Index into [`Model::variant_materials`](crate::model::Model::variant_materials) of the
+    /// material for each variant, `None` to keep [`Mesh::material`]
+    pub variants: Vec<Option<usize>>,
 }
 
 impl MeshPrimitive {
     pub fn new(indices: Vec<u32>, vertices: Vec<StaticVertex>) -> Self {
+        Self::deformed(indices, vertices, None, Vec::new())
+    }
+
+    /// Creates a primitive deformed by a skin or morph targets. `morph_targets` holds the offsets
+    /// of every vertex for each target in turn. The vertices of deformed primitives are also
+    /// readable as a storage buffer.
+    pub fn deformed(
+        indices: Vec<u32>,
+        vertices: Vec<StaticVertex>,
+        skin_vertices: Option<Vec<SkinVertex>>,
+        morph_targets: Vec<MorphVertex>,
+    ) -> Self {
         let memory_allocator = VULKAN.memory_allocator().clone();
 
+        let morph_target_count = morph_targets.len() / vertices.len().max(1);
+        let vertex_usage = if skin_vertices.is_some() || morph_target_count > 0 {
+            BufferUsage::VERTEX_BUFFER | BufferUsage::STORAGE_BUFFER
+        } else {
+            BufferUsage::VERTEX_BUFFER
+        };
         let vertex_buffer = Buffer::from_iter(
             memory_allocator.clone(),
             BufferCreateInfo {
-                usage: BufferUsage::VERTEX_BUFFER,
+                usage: vertex_usage,
                 ..Default::default()
             },
             AllocationCreateInfo {
@@ -83,11 +130,90 @@ impl MeshPrimitive {
         )
         .unwrap();
 
+        let skin_buffer = skin_vertices.map(|skin_vertices| {
+            Buffer::from_iter(
+                memory_allocator.clone(),
+                BufferCreateInfo {
+                    usage: BufferUsage::STORAGE_BUFFER,
+                    ..Default::default()
+                },
+                AllocationCreateInfo {
+                    memory_type_filter: MemoryTypeFilter::PREFER_DEVICE
+                        | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
+                    ..Default::default()
+                },
+                skin_vertices,
+            )
+            .unwrap()
+        });
+
+        let morph_buffer = (morph_target_count > 0).then(|| {
+            Buffer::from_iter(
+                memory_allocator.clone(),
+                BufferCreateInfo {
+                    usage: BufferUsage::STORAGE_BUFFER,
+                    ..Default::default()
+                },
+                AllocationCreateInfo {
+                    memory_type_filter: MemoryTypeFilter::PREFER_DEVICE
+                        | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
+                    ..Default::default()
+                },
+                morph_targets,
+            )
+            .unwrap()
+        });
+
         MeshPrimitive {
             index_buffer,
             vertex_buffer,
+            skin_buffer,
+            morph_buffer,
+            morph_target_count,
             indices,
             vertices,
         }
     }
+
+    /// Axis aligned cube centered at the origin, each face has its own vertices and uvs
+    pub fn cube(half_extent: f32) -> Self {
+        // Normal and tangent of each face
+        let faces = [
+            (Vec3::X, Vec3::NEG_Z),
+            (Vec3::NEG_X, Vec3::Z),
+            (Vec3::Y, Vec3::X),
+            (Vec3::NEG_Y, Vec3::X),
+            (Vec3::Z, Vec3::X),
+            (Vec3::NEG_Z, Vec3::NEG_X),
+        ];
+        let corners = [
+            Vec2::new(-1.0, -1.0),
+            Vec2::new(1.0, -1.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(-1.0, 1.0),
+        ];
+
+        let mut vertices = Vec::with_capacity(faces.len() * corners.len());
+        let mut indices = Vec::with_capacity(faces.len() * 6);
+
+        for (normal, tangent) in faces {
+            let bitangent = normal.cross(tangent);
+            let first = vertices.len() as u32;
+
+            for corner in corners {
+                let position = (normal + tangent * corner.x + bitangent * corner.y) * half_extent;
+                vertices.push(StaticVertex {
+                    position: position.extend(0.0).into(),
+                    normal: normal.extend(0.0).into(),
+                    uv0: [(corner.x + 1.0) * 0.5, (1.0 - corner.y) * 0.5],
+                    tangent: tangent.extend(1.0).into(),
+                    ..Default::default()
+                });
+            }
+
+            indices.extend([first, first + 1, first + 2, first, first + 2, first + 3]);
+        }
+
+        Self::new(indices, vertices)
+    }
 }
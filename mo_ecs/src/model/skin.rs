@@ -0,0 +1,74 @@
+use crate::{
+    asset::Handle,
+    component::{GlobalTransform, SceneInstance, SceneNode},
+    model::{Model, StaticVertex},
+};
+use bevy_ecs::{prelude::*, world::EntityRef};
+use bevy_math::Mat4;
+use std::collections::HashMap;
+use vulkano::buffer::Subbuffer;
+
+/// A glTF skin, the joints deforming its meshes
+pub struct Skin {
+    pub name: Option<String>,
+    /// Node index of every joint
+    pub joints: Vec<usize>,
+    /// Transforms from model space to the space of each joint in the bind pose
+    pub inverse_bind_matrices: Vec<Mat4>,
+}
+
+impl Skin {
+    /// World space joint matrices of `skin` for the meshes drawn by `entity`
+    ///
+    /// Joints follow the node entities of a [`SceneNode`], so they can be moved or animated. For
+    /// an entity with a plain `Handle<Model>` the joints stay in the rest pose of the file.
+    pub fn joint_matrices(
+        &self,
+        world: &World,
+        entity: EntityRef,
+        model: &Model,
+    ) -> Option<Vec<Mat4>> {
+        if let Some(node) = entity.get::<SceneNode>() {
+            let instance = world.get::<SceneInstance>(node.root)?;
+            return self
+                .joints
+                .iter()
+                .zip(&self.inverse_bind_matrices)
+                .map(|(&joint, inverse_bind_matrix)| {
+                    let joint_entity = (*instance.nodes.get(joint)?)?;
+                    let global_transform = world.get::<GlobalTransform>(joint_entity)?;
+                    Some(global_transform.matrix() * *inverse_bind_matrix)
+                })
+                .collect();
+        }
+
+        entity.get::<Handle<Model>>()?;
+        let global_transform = entity.get::<GlobalTransform>()?.matrix();
+        let rest_transforms = model.rest_transforms();
+        self.joints
+            .iter()
+            .zip(&self.inverse_bind_matrices)
+            .map(|(&joint, inverse_bind_matrix)| {
+                Some(global_transform * *rest_transforms.get(joint)? * *inverse_bind_matrix)
+            })
+            .collect()
+    }
+}
+
+/// Vertices of the skinned and morphed meshes, written by the skinning pass before the geometry
+/// passes draw them. Skinned vertices are in world space, morphed ones in model space.
+#[derive(Resource, Default)]
+pub struct SkinnedVertices {
+    buffers: HashMap<(Entity, usize), Subbuffer<[StaticVertex]>>,
+}
+
+impl SkinnedVertices {
+    pub fn new(buffers: HashMap<(Entity, usize), Subbuffer<[StaticVertex]>>) -> Self {
+        Self { buffers }
+    }
+
+    /// Skinned vertices of mesh `mesh_index` drawn by `entity`
+    pub fn get(&self, entity: Entity, mesh_index: usize) -> Option<&Subbuffer<[StaticVertex]>> {
+        self.buffers.get(&(entity, mesh_index))
+    }
+}
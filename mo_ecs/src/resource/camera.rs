@@ -1,6 +1,8 @@
 use crate::{
     component::Transform,
+    model::CameraPreset,
     resource::{
+        console::{Console, CVAR_CAMERA_SPEED},
         input::{EInputButton, EInputState}, Input,
         Timer,
     },
@@ -126,6 +128,15 @@ impl Camera {
         }
     }
 
+    /// Creates new Camera instance at `position` looking at `target`
+    pub fn looking_at(position: Vec3, target: Vec3) -> Self {
+        // 相机沿本地 +Z 方向观察
+        let rotation = Transform::from_translation(position)
+            .looking_to(position - target, Vec3::Y)
+            .rotation;
+        Self::new(position, rotation)
+    }
+
     /// Returns view matrix with zero transition
     ///
     /// It is useful for sky boxes and domes
@@ -141,6 +152,12 @@ impl Camera {
         &self.transform
     }
 
+    /// Moves the camera, it looks along the local +Z axis of `transform`.
+    pub fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+        self.is_dirty = true;
+    }
+
     /// Returns calculated camera position
     pub fn position(&self) -> Vec3 {
         self.transform.translation
@@ -200,6 +217,23 @@ impl Camera {
         self.prev_view
     }
 
+    /// Replaces the projection settings, keeping the current aspect ratio
+    pub fn set_settings(&mut self, settings: CameraSettings) {
+        self.settings = settings;
+        self.resize([self.aspect, 1.0]);
+        self.is_dirty = true;
+    }
+
+    /// Moves the camera to a glTF camera and takes over its projection, `model_transform` places
+    /// the model in the world. The aspect ratio stays the one of the window.
+    pub fn set_preset(&mut self, preset: &CameraPreset, model_transform: &Transform) {
+        let transform = model_transform.mul_transform(preset.transform);
+        // glTF cameras look along -Z, this camera along +Z.
+        self.transform = Transform::from_translation(transform.translation)
+            .with_rotation(transform.rotation * Quat::from_rotation_y(PI));
+        self.set_settings(preset.settings);
+    }
+
     pub fn resize(&mut self, window_size: [f32; 2]) {
         self.aspect = window_size[0] / window_size[1];
 
@@ -248,13 +282,23 @@ impl Camera {
 
     fn fixed_height(height: f32, window_size: [f32; 2]) -> (f32, f32) {
         let half_height = height / 2.0;
-        let half_width = half_height * window_size[1] / window_size[0];
+        let half_width = half_height * window_size[0] / window_size[1];
         (half_width, half_height)
     }
 
     /// System controlling camera with mouse.
-    pub fn update_camera(mut camera: ResMut<Camera>, input: Res<Input>, frame: Res<Timer>) {
+    pub fn update_camera(
+        mut camera: ResMut<Camera>,
+        input: Res<Input>,
+        frame: Res<Timer>,
+        console: Option<Res<Console>>,
+    ) {
         let time_delta = frame.delta().as_secs_f32();
+        // 控制台打开时键盘输入交给控制台
+        let keyboard_enabled = !console.as_ref().is_some_and(|console| console.is_open());
+        let move_speed = console
+            .as_ref()
+            .map_or(MOVE_SPEED, |console| console.get_or(CVAR_CAMERA_SPEED, MOVE_SPEED));
 
         // 处理相机旋转
         if input
@@ -294,39 +338,39 @@ impl Camera {
 
         // 处理 WASD 按键移动相机
         let mut movement = Vec3::ZERO;
-        if input
+        if keyboard_enabled && input
             .button_state(EInputButton::Key(PhysicalKey::Code(KeyCode::KeyW)))
             .is_some_and(|state| state == EInputState::Activated)
         {
             movement += direction * time_delta;
         }
-        if input
+        if keyboard_enabled && input
             .button_state(EInputButton::Key(PhysicalKey::Code(KeyCode::KeyA)))
             .is_some_and(|state| state == EInputState::Activated)
         {
             movement -= right * time_delta;
         }
-        if input
+        if keyboard_enabled && input
             .button_state(EInputButton::Key(PhysicalKey::Code(KeyCode::KeyS)))
             .is_some_and(|state| state == EInputState::Activated)
         {
             movement -= direction * time_delta;
         }
-        if input
+        if keyboard_enabled && input
             .button_state(EInputButton::Key(PhysicalKey::Code(KeyCode::KeyD)))
             .is_some_and(|state| state == EInputState::Activated)
         {
             movement += right * time_delta;
         }
         // E 键上移
-        if input
+        if keyboard_enabled && input
             .button_state(EInputButton::Key(PhysicalKey::Code(KeyCode::KeyE)))
             .is_some_and(|state| state == EInputState::Activated)
         {
             movement += Vec3::Y * time_delta;
         }
         // Q 键下移
-        if input
+        if keyboard_enabled && input
             .button_state(EInputButton::Key(PhysicalKey::Code(KeyCode::KeyQ)))
             .is_some_and(|state| state == EInputState::Activated)
         {
@@ -334,7 +378,7 @@ impl Camera {
         }
 
         if movement.length() > 0.0 {
-            movement = movement.normalize() * MOVE_SPEED;
+            movement = movement.normalize() * move_speed;
             camera.transform.translation += movement;
             camera.is_dirty = true;
         }
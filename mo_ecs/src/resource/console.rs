@@ -0,0 +1,489 @@
+//! Developer console and runtime variables (cvars)
+use crate::resource::{
+    input::{EInputEvent, FKeyEvent},
+    Input,
+};
+use bevy_ecs::prelude::*;
+use std::{collections::BTreeMap, fmt, path::Path};
+use thiserror::Error;
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+/// Enables or disables the SSAO pass.
+pub const CVAR_SSAO_ENABLED: &str = "r.ssao.enabled";
+/// Depth bias used when comparing against the shadow map.
+pub const CVAR_SHADOW_BIAS: &str = "r.shadow.bias";
+/// Default value of [`CVAR_SHADOW_BIAS`].
+pub const DEFAULT_SHADOW_BIAS: f32 = 0.000005;
+/// Tone mapping operator, 0 = Unreal, 1 = Reinhard, 2 = Uncharted 2.
+pub const CVAR_TONEMAP: &str = "r.tonemap";
+/// Camera movement speed per frame.
+pub const CVAR_CAMERA_SPEED: &str = "cam.speed";
+
+/// Script executed by [`Console::run_startup_script`], relative to the working directory.
+pub const STARTUP_SCRIPT: &str = "console.cfg";
+
+/// Maximum number of lines kept in the console history.
+const MAX_HISTORY: usize = 64;
+
+/// Maximum number of scripts executing each other, stops scripts which exec themselves.
+const MAX_SCRIPT_DEPTH: usize = 8;
+
+/// Value of a console variable.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CVarValue {
+    Bool(bool),
+    Int(i32),
+    Float(f32),
+    String(String),
+}
+
+impl CVarValue {
+    /// Parses `text` into a value of the same type as `self`.
+    fn parse_same_type(&self, text: &str) -> Option<CVarValue> {
+        match self {
+            CVarValue::Bool(_) => match text {
+                "1" | "true" | "on" => Some(CVarValue::Bool(true)),
+                "0" | "false" | "off" => Some(CVarValue::Bool(false)),
+                _ => None,
+            },
+            CVarValue::Int(_) => text.parse().ok().map(CVarValue::Int),
+            CVarValue::Float(_) => text.parse().ok().map(CVarValue::Float),
+            CVarValue::String(_) => Some(CVarValue::String(text.to_string())),
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            CVarValue::Bool(_) => "bool",
+            CVarValue::Int(_) => "int",
+            CVarValue::Float(_) => "float",
+            CVarValue::String(_) => "string",
+        }
+    }
+}
+
+impl fmt::Display for CVarValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CVarValue::Bool(value) => write!(f, "{}", *value as i32),
+            CVarValue::Int(value) => write!(f, "{value}"),
+            CVarValue::Float(value) => write!(f, "{value}"),
+            CVarValue::String(value) => write!(f, "\"{value}\""),
+        }
+    }
+}
+
+/// Rust types that can be stored in a console variable.
+pub trait CVarType: Sized {
+    fn from_value(value: &CVarValue) -> Option<Self>;
+    fn into_value(self) -> CVarValue;
+}
+
+impl CVarType for bool {
+    fn from_value(value: &CVarValue) -> Option<Self> {
+        match value {
+            CVarValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> CVarValue {
+        CVarValue::Bool(self)
+    }
+}
+
+impl CVarType for i32 {
+    fn from_value(value: &CVarValue) -> Option<Self> {
+        match value {
+            CVarValue::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> CVarValue {
+        CVarValue::Int(self)
+    }
+}
+
+impl CVarType for f32 {
+    fn from_value(value: &CVarValue) -> Option<Self> {
+        match value {
+            CVarValue::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> CVarValue {
+        CVarValue::Float(self)
+    }
+}
+
+impl CVarType for String {
+    fn from_value(value: &CVarValue) -> Option<Self> {
+        match value {
+            CVarValue::String(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> CVarValue {
+        CVarValue::String(self)
+    }
+}
+
+/// A named console variable.
+#[derive(Debug, Clone)]
+pub struct CVar {
+    pub value: CVarValue,
+    pub default: CVarValue,
+    pub description: String,
+}
+
+#[derive(Debug, Error)]
+pub enum ConsoleError {
+    #[error("unknown variable or command {0:?}")]
+    UnknownVariable(String),
+    #[error("cannot parse {value:?} as {expected} for {name:?}")]
+    InvalidValue {
+        name: String,
+        value: String,
+        expected: &'static str,
+    },
+    #[error("variable {name:?} is a {actual}, not a {expected}")]
+    TypeMismatch {
+        name: String,
+        actual: &'static str,
+        expected: &'static str,
+    },
+    #[error("usage: {0}")]
+    Usage(&'static str),
+    #[error("failed to read script: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("scripts nested deeper than {} levels", MAX_SCRIPT_DEPTH)]
+    ScriptDepthExceeded,
+}
+
+/// Developer console service
+///
+/// Holds named, typed runtime variables which systems and render passes read every frame. Values
+/// are changed through text commands, typed into the console (toggled with the backquote key) or
+/// executed from a startup script.
+///
+/// Supported commands:
+/// * `<name>` prints a variable
+/// * `<name> <value>` or `set <name> <value>` changes a variable
+/// * `toggle <name>` flips a bool variable
+/// * `reset <name>` restores the default value
+/// * `list [prefix]` prints all variables, optionally filtered by prefix
+/// * `exec <path>` runs a script
+#[derive(Resource)]
+pub struct Console {
+    cvars: BTreeMap<String, CVar>,
+    is_open: bool,
+    input_line: String,
+    history: Vec<String>,
+    /// Number of scripts currently executing
+    script_depth: usize,
+}
+
+impl Console {
+    /// Constructs a console with the engine variables registered
+    pub fn new() -> Self {
+        let mut console = Self {
+            cvars: BTreeMap::new(),
+            is_open: false,
+            input_line: String::new(),
+            history: Vec::new(),
+            script_depth: 0,
+        };
+
+        console.register(
+            CVAR_SSAO_ENABLED,
+            true,
+            "Enables screen space ambient occlusion",
+        );
+        console.register(
+            CVAR_SHADOW_BIAS,
+            DEFAULT_SHADOW_BIAS,
+            "Shadow map depth comparison bias",
+        );
+        console.register(
+            CVAR_TONEMAP,
+            0i32,
+            "Tone mapping operator: 0 = Unreal, 1 = Reinhard, 2 = Uncharted 2",
+        );
+        console.register(
+            CVAR_CAMERA_SPEED,
+            0.01f32,
+            "Camera movement speed per frame",
+        );
+
+        console
+    }
+
+    /// Registers a variable. If it already exists, only its description is updated and the current
+    /// value is kept.
+    pub fn register<T: CVarType>(&mut self, name: &str, default: T, description: &str) {
+        let default = default.into_value();
+        if let Some(cvar) = self.cvars.get_mut(name) {
+            cvar.description = description.to_string();
+            return;
+        }
+
+        self.cvars.insert(
+            name.to_string(),
+            CVar {
+                value: default.clone(),
+                default,
+                description: description.to_string(),
+            },
+        );
+    }
+
+    /// Returns the value of a variable, or `None` when it does not exist or has another type
+    pub fn get<T: CVarType>(&self, name: &str) -> Option<T> {
+        self.cvars
+            .get(name)
+            .and_then(|cvar| T::from_value(&cvar.value))
+    }
+
+    /// Returns the value of a variable, or `default` when it does not exist or has another type
+    pub fn get_or<T: CVarType>(&self, name: &str, default: T) -> T {
+        self.get(name).unwrap_or(default)
+    }
+
+    /// Sets the value of an existing variable
+    pub fn set<T: CVarType>(&mut self, name: &str, value: T) -> Result<(), ConsoleError> {
+        let value = value.into_value();
+        let cvar = self
+            .cvars
+            .get_mut(name)
+            .ok_or_else(|| ConsoleError::UnknownVariable(name.to_string()))?;
+
+        if std::mem::discriminant(&cvar.value) != std::mem::discriminant(&value) {
+            return Err(ConsoleError::TypeMismatch {
+                name: name.to_string(),
+                actual: cvar.value.type_name(),
+                expected: value.type_name(),
+            });
+        }
+
+        cvar.value = value;
+        Ok(())
+    }
+
+    /// Returns a registered variable
+    pub fn cvar(&self, name: &str) -> Option<&CVar> {
+        self.cvars.get(name)
+    }
+
+    /// Iterates over all variables sorted by name
+    pub fn cvars(&self) -> impl Iterator<Item = (&str, &CVar)> {
+        self.cvars.iter().map(|(name, cvar)| (name.as_str(), cvar))
+    }
+
+    /// Returns true while the console captures keyboard input
+    pub fn is_open(&self) -> bool {
+        self.is_open
+    }
+
+    /// Opens or closes the console
+    pub fn set_open(&mut self, open: bool) {
+        self.is_open = open;
+        self.input_line.clear();
+    }
+
+    /// Text typed into the console but not yet executed
+    pub fn input_line(&self) -> &str {
+        &self.input_line
+    }
+
+    /// Executes a single command line and returns its output
+    pub fn execute(&mut self, line: &str) -> Result<String, ConsoleError> {
+        let mut tokens = line.split_whitespace();
+        let Some(command) = tokens.next() else {
+            return Ok(String::new());
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        match command {
+            "set" => match args.as_slice() {
+                [name, value @ ..] if !value.is_empty() => {
+                    self.set_from_str(name, &value.join(" "))
+                }
+                _ => Err(ConsoleError::Usage("set <name> <value>")),
+            },
+            "toggle" => match args.as_slice() {
+                [name] => {
+                    let value: bool =
+                        self.get(name).ok_or_else(|| match self.cvars.get(*name) {
+                            Some(cvar) => ConsoleError::TypeMismatch {
+                                name: name.to_string(),
+                                actual: cvar.value.type_name(),
+                                expected: "bool",
+                            },
+                            None => ConsoleError::UnknownVariable(name.to_string()),
+                        })?;
+                    self.set(name, !value)?;
+                    self.describe(name)
+                }
+                _ => Err(ConsoleError::Usage("toggle <name>")),
+            },
+            "reset" => match args.as_slice() {
+                [name] => {
+                    let cvar = self
+                        .cvars
+                        .get_mut(*name)
+                        .ok_or_else(|| ConsoleError::UnknownVariable(name.to_string()))?;
+                    cvar.value = cvar.default.clone();
+                    self.describe(name)
+                }
+                _ => Err(ConsoleError::Usage("reset <name>")),
+            },
+            "list" => {
+                let prefix = args.first().copied().unwrap_or("");
+                Ok(self
+                    .cvars
+                    .iter()
+                    .filter(|(name, _)| name.starts_with(prefix))
+                    .map(|(name, cvar)| format!("{name} = {} ({})", cvar.value, cvar.description))
+                    .collect::<Vec<_>>()
+                    .join("\n"))
+            }
+            "exec" => match args.as_slice() {
+                [path] => self.exec_script(path).map(|_| String::new()),
+                _ => Err(ConsoleError::Usage("exec <path>")),
+            },
+            name if args.is_empty() => self.describe(name),
+            name => self.set_from_str(name, &args.join(" ")),
+        }
+    }
+
+    /// Executes every line of a script file. Empty lines and lines starting with `#` or `//` are
+    /// skipped. Errors are logged and do not stop the script.
+    pub fn exec_script(&mut self, path: impl AsRef<Path>) -> Result<(), ConsoleError> {
+        if self.script_depth >= MAX_SCRIPT_DEPTH {
+            return Err(ConsoleError::ScriptDepthExceeded);
+        }
+        let script = std::fs::read_to_string(path.as_ref())?;
+
+        self.script_depth += 1;
+        for line in script.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+                continue;
+            }
+            if let Err(err) = self.execute(line) {
+                tracing::warn!(
+                    "Console - {}: \"{}\" failed: {}",
+                    path.as_ref().display(),
+                    line,
+                    err
+                );
+            }
+        }
+        self.script_depth -= 1;
+
+        tracing::info!("Console - Script {} executed.", path.as_ref().display());
+        Ok(())
+    }
+
+    fn set_from_str(&mut self, name: &str, text: &str) -> Result<String, ConsoleError> {
+        let cvar = self
+            .cvars
+            .get_mut(name)
+            .ok_or_else(|| ConsoleError::UnknownVariable(name.to_string()))?;
+        let text = text.trim_matches('"');
+
+        cvar.value =
+            cvar.value
+                .parse_same_type(text)
+                .ok_or_else(|| ConsoleError::InvalidValue {
+                    name: name.to_string(),
+                    value: text.to_string(),
+                    expected: cvar.value.type_name(),
+                })?;
+
+        self.describe(name)
+    }
+
+    fn describe(&self, name: &str) -> Result<String, ConsoleError> {
+        self.cvars
+            .get(name)
+            .map(|cvar| format!("{name} = {}", cvar.value))
+            .ok_or_else(|| ConsoleError::UnknownVariable(name.to_string()))
+    }
+
+    fn submit_input_line(&mut self) {
+        let line = std::mem::take(&mut self.input_line);
+        if line.trim().is_empty() {
+            return;
+        }
+
+        tracing::info!("Console - > {}", line);
+        match self.execute(&line) {
+            Ok(output) if !output.is_empty() => tracing::info!("Console - {}", output),
+            Ok(_) => {}
+            Err(err) => tracing::warn!("Console - {}", err),
+        }
+
+        self.history.push(line);
+        if self.history.len() > MAX_HISTORY {
+            self.history.remove(0);
+        }
+    }
+
+    /// This system executes [`STARTUP_SCRIPT`] if it exists. It should be added to the startup
+    /// schedule.
+    pub fn run_startup_script(mut console: ResMut<Console>) {
+        if !Path::new(STARTUP_SCRIPT).exists() {
+            return;
+        }
+        if let Err(err) = console.exec_script(STARTUP_SCRIPT) {
+            tracing::warn!("Console - {}", err);
+        }
+    }
+
+    /// This system feeds the keyboard events of the frame into the console. It should be added to
+    /// the runtime schedule.
+    pub fn update_console(mut console: ResMut<Console>, input: Res<Input>) {
+        for event in &input.events {
+            match event {
+                EInputEvent::Key(FKeyEvent {
+                    key_code: PhysicalKey::Code(key_code),
+                    pressed: true,
+                    ..
+                }) => match key_code {
+                    KeyCode::Backquote => {
+                        let open = !console.is_open;
+                        console.set_open(open);
+                    }
+                    KeyCode::Escape if console.is_open => console.set_open(false),
+                    KeyCode::Enter | KeyCode::NumpadEnter if console.is_open => {
+                        console.submit_input_line();
+                    }
+                    KeyCode::Backspace if console.is_open => {
+                        console.input_line.pop();
+                    }
+                    KeyCode::ArrowUp if console.is_open => {
+                        if let Some(last) = console.history.last().cloned() {
+                            console.input_line = last;
+                        }
+                    }
+                    _ => {}
+                },
+                EInputEvent::Text(text) if console.is_open => {
+                    console
+                        .input_line
+                        .extend(text.chars().filter(|chr| *chr != '`'));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Default for Console {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,435 @@
+//! Immediate-mode debug UI
+use crate::{
+    asset::Handle,
+    color::{EColorSpace, FColor},
+    component::{DirectionalLight, MorphWeights, Name, SceneRoot, Transform},
+    model::Model,
+    resource::{
+        camera::CameraSettings,
+        console::{
+            CVAR_CAMERA_SPEED, CVAR_SHADOW_BIAS, CVAR_SSAO_ENABLED, CVAR_TONEMAP,
+            DEFAULT_SHADOW_BIAS,
+        },
+        input::{EInputEvent, FKeyEvent},
+        Camera, Console, Input,
+    },
+};
+use bevy_ecs::prelude::*;
+use bevy_math::{EulerRot, Quat};
+use egui::{
+    epaint::ImageDelta, ClippedPrimitive, Context, FullOutput, PlatformOutput, RawInput,
+    TextureId, TextureOptions, TexturesDelta,
+};
+use std::sync::Mutex;
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+/// Key toggling the visibility of the debug UI
+pub const TOGGLE_KEY: KeyCode = KeyCode::F1;
+
+/// Immediate-mode debug UI service
+///
+/// The application feeds window events into the [`egui::Context`] and starts a UI frame before
+/// the runtime systems, so any system can draw panels with [`DebugUi::context`]. After the
+/// runtime systems the frame is tessellated and drawn by the UI render pass on top of the
+/// shaded image.
+#[derive(Resource)]
+pub struct DebugUi {
+    context: Context,
+    visible: bool,
+    primitives: Vec<ClippedPrimitive>,
+    pixels_per_point: f32,
+    /// Texture changes not uploaded by the render pass yet
+    textures_delta: Mutex<TexturesDelta>,
+    /// The font atlas exists once the first frame finished
+    fonts_ready: bool,
+}
+
+impl DebugUi {
+    pub fn new() -> Self {
+        Self {
+            context: Context::default(),
+            visible: true,
+            primitives: Vec::new(),
+            pixels_per_point: 1.0,
+            textures_delta: Mutex::new(TexturesDelta::default()),
+            fonts_ready: false,
+        }
+    }
+
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    /// Returns true if a UI widget uses the keyboard, e.g. a focused text field
+    pub fn wants_keyboard_input(&self) -> bool {
+        self.visible && self.context.wants_keyboard_input()
+    }
+
+    /// Returns true if the pointer is over a panel or dragging a widget
+    pub fn wants_pointer_input(&self) -> bool {
+        self.visible && self.context.wants_pointer_input()
+    }
+
+    /// Called by the application before the runtime systems
+    pub fn begin_frame(&mut self, raw_input: RawInput) {
+        self.context.begin_pass(raw_input);
+    }
+
+    /// Called by the application after the runtime systems. Returns the output for the windowing
+    /// backend (cursor icon, clipboard, IME).
+    pub fn end_frame(&mut self) -> PlatformOutput {
+        let FullOutput {
+            platform_output,
+            textures_delta,
+            shapes,
+            pixels_per_point,
+            ..
+        } = self.context.end_pass();
+
+        let shapes = if self.visible { shapes } else { Vec::new() };
+        self.primitives = self.context.tessellate(shapes, pixels_per_point);
+        self.pixels_per_point = pixels_per_point;
+        // Accumulate, a skipped frame must not lose the font atlas upload.
+        self.textures_delta
+            .lock()
+            .unwrap()
+            .append(textures_delta);
+        self.fonts_ready = true;
+
+        platform_output
+    }
+
+    /// Triangles of the last finished frame, in points
+    pub fn primitives(&self) -> &[ClippedPrimitive] {
+        &self.primitives
+    }
+
+    pub fn pixels_per_point(&self) -> f32 {
+        self.pixels_per_point
+    }
+
+    /// Takes the texture changes since the last call. Used by the render pass.
+    pub fn take_textures_delta(&self) -> TexturesDelta {
+        std::mem::take(&mut *self.textures_delta.lock().unwrap())
+    }
+
+    /// Queues a full upload of the font atlas. Called by the application when the render pass was
+    /// recreated and lost its textures.
+    pub fn reset_textures(&self) {
+        if !self.fonts_ready {
+            return;
+        }
+
+        let font_image = self.context.fonts(|fonts| fonts.image());
+        let mut textures_delta = self.textures_delta.lock().unwrap();
+        textures_delta
+            .set
+            .retain(|(id, _)| *id != TextureId::default());
+        textures_delta.set.insert(
+            0,
+            (
+                TextureId::default(),
+                ImageDelta::full(font_image, TextureOptions::LINEAR),
+            ),
+        );
+    }
+
+    /// This system toggles the UI with [`TOGGLE_KEY`]. It should be added to the runtime schedule.
+    pub fn toggle_visibility(mut debug_ui: ResMut<DebugUi>, input: Res<Input>) {
+        for event in &input.events {
+            if let EInputEvent::Key(FKeyEvent {
+                key_code: PhysicalKey::Code(TOGGLE_KEY),
+                pressed: true,
+                ..
+            }) = event
+            {
+                let visible = !debug_ui.visible;
+                debug_ui.set_visible(visible);
+            }
+        }
+    }
+
+    /// This system draws the inspector panel: entity list, transform and light editing, camera
+    /// settings and render pass toggles. It should be added to the runtime schedule.
+    pub fn inspector(
+        debug_ui: Res<DebugUi>,
+        mut selected: Local<Option<Entity>>,
+        mut camera: Option<ResMut<Camera>>,
+        mut console: Option<ResMut<Console>>,
+        mut entities: Query<InspectedQuery>,
+        labels: Query<EntityLabelQuery>,
+    ) {
+        if !debug_ui.visible {
+            return;
+        }
+
+        egui::SidePanel::left("inspector")
+            .default_width(260.0)
+            .show(&debug_ui.context, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::CollapsingHeader::new("Entities")
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            for item in labels.iter() {
+                                let entity = item.0;
+                                let label = entity_label(item);
+                                if ui
+                                    .selectable_label(*selected == Some(entity), label)
+                                    .clicked()
+                                {
+                                    *selected = Some(entity);
+                                }
+                            }
+                        });
+
+                    if let Some((_, transform, light, morph_weights)) =
+                        selected.and_then(|entity| entities.get_mut(entity).ok())
+                    {
+                        if let Some(mut transform) = transform {
+                            egui::CollapsingHeader::new("Transform")
+                                .default_open(true)
+                                .show(ui, |ui| transform_ui(ui, &mut transform));
+                        }
+                        if let Some(mut light) = light {
+                            egui::CollapsingHeader::new("Directional Light")
+                                .default_open(true)
+                                .show(ui, |ui| light_ui(ui, &mut light));
+                        }
+                        if let Some(mut morph_weights) = morph_weights {
+                            egui::CollapsingHeader::new("Morph Weights")
+                                .default_open(true)
+                                .show(ui, |ui| morph_weights_ui(ui, &mut morph_weights));
+                        }
+                    }
+
+                    if let Some(camera) = camera.as_mut() {
+                        egui::CollapsingHeader::new("Camera").show(ui, |ui| {
+                            camera_ui(ui, camera, console.as_deref_mut())
+                        });
+                    }
+
+                    if let Some(console) = console.as_mut() {
+                        egui::CollapsingHeader::new("Render Passes")
+                            .show(ui, |ui| passes_ui(ui, console));
+                    }
+                });
+            });
+    }
+}
+
+impl Default for DebugUi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+type InspectedQuery = (
+    Entity,
+    Option<&'static mut Transform>,
+    Option<&'static mut DirectionalLight>,
+    Option<&'static mut MorphWeights>,
+);
+
+type EntityLabelQuery = (
+    Entity,
+    Option<&'static Name>,
+    Option<&'static Handle<Model>>,
+    Option<&'static SceneRoot>,
+    Has<DirectionalLight>,
+);
+
+fn entity_label(
+    (entity, name, model, scene, is_light): (
+        Entity,
+        Option<&Name>,
+        Option<&Handle<Model>>,
+        Option<&SceneRoot>,
+        bool,
+    ),
+) -> String {
+    let file_name = |model: &Handle<Model>| {
+        let path = model.path();
+        path.rsplit(['/', '\\']).next().unwrap_or(path).to_string()
+    };
+
+    if let Some(name) = name {
+        format!("{entity} {name}")
+    } else if let Some(model) = model {
+        format!("{entity} Model ({})", file_name(model))
+    } else if let Some(scene) = scene {
+        format!("{entity} Scene ({})", file_name(&scene.0))
+    } else if is_light {
+        format!("{entity} Directional Light")
+    } else {
+        format!("{entity}")
+    }
+}
+
+fn drag_vec3(ui: &mut egui::Ui, label: &str, value: &mut [f32; 3], speed: f32) -> bool {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        let mut changed = false;
+        for (component, prefix) in value.iter_mut().zip(["x: ", "y: ", "z: "]) {
+            changed |= ui
+                .add(egui::DragValue::new(component).speed(speed).prefix(prefix))
+                .changed();
+        }
+        changed
+    })
+    .inner
+}
+
+fn transform_ui(ui: &mut egui::Ui, transform: &mut Mut<Transform>) {
+    let mut translation = transform.translation.to_array();
+    if drag_vec3(ui, "Translation", &mut translation, 0.01) {
+        transform.translation = translation.into();
+    }
+
+    let (yaw, pitch, roll) = transform.rotation.to_euler(EulerRot::YXZ);
+    let mut rotation = [pitch.to_degrees(), yaw.to_degrees(), roll.to_degrees()];
+    if drag_vec3(ui, "Rotation", &mut rotation, 0.5) {
+        transform.rotation = Quat::from_euler(
+            EulerRot::YXZ,
+            rotation[1].to_radians(),
+            rotation[0].to_radians(),
+            rotation[2].to_radians(),
+        );
+    }
+
+    let mut scale = transform.scale.to_array();
+    if drag_vec3(ui, "Scale", &mut scale, 0.01) {
+        transform.scale = scale.into();
+    }
+}
+
+fn light_ui(ui: &mut egui::Ui, light: &mut Mut<DirectionalLight>) {
+    ui.horizontal(|ui| {
+        // The color picker edits linear RGB
+        let mut color: [f32; 3] = light.linear_color().into();
+        ui.label("Color");
+        if ui.color_edit_button_rgb(&mut color).changed() {
+            let alpha = light.color.a;
+            light.color = FColor::from(color)
+                .with_alpha(alpha)
+                .convert(EColorSpace::Linear, light.color_space);
+        }
+    });
+
+    let mut intensity = light.intensity;
+    if ui
+        .add(egui::Slider::new(&mut intensity, 0.0..=20.0).text("Intensity"))
+        .changed()
+    {
+        light.intensity = intensity;
+    }
+
+    let mut translation = light.transform.translation.to_array();
+    if drag_vec3(ui, "Position", &mut translation, 0.1) {
+        light.transform.translation = translation.into();
+    }
+
+    let mut is_shadow_caster = light.is_shadow_caster;
+    if ui.checkbox(&mut is_shadow_caster, "Shadow caster").changed() {
+        light.is_shadow_caster = is_shadow_caster;
+    }
+}
+
+fn morph_weights_ui(ui: &mut egui::Ui, morph_weights: &mut Mut<MorphWeights>) {
+    for index in 0..morph_weights.0.len() {
+        let mut weight = morph_weights.0[index];
+        if ui
+            .add(egui::Slider::new(&mut weight, 0.0..=1.0).text(format!("Target {index}")))
+            .changed()
+        {
+            morph_weights.0[index] = weight;
+        }
+    }
+}
+
+fn camera_ui(ui: &mut egui::Ui, camera: &mut Camera, console: Option<&mut Console>) {
+    let position = camera.position();
+    ui.label(format!(
+        "Position: {:.2} {:.2} {:.2}",
+        position.x, position.y, position.z
+    ));
+
+    let mut settings = camera.settings;
+    let changed = match &mut settings {
+        CameraSettings::Perspective { fov, near, far } => {
+            let mut fov_degrees = fov.to_degrees();
+            let mut changed = ui
+                .add(egui::Slider::new(&mut fov_degrees, 10.0..=120.0).text("FOV"))
+                .changed();
+            *fov = fov_degrees.to_radians();
+            changed |= ui
+                .add(egui::DragValue::new(near).speed(0.01).range(0.001..=*far).prefix("near: "))
+                .changed();
+            changed |= ui
+                .add(egui::DragValue::new(far).speed(1.0).range(*near..=f32::MAX).prefix("far: "))
+                .changed();
+            changed
+        }
+        CameraSettings::Orthographic { width, height, .. } => {
+            let mut changed = ui
+                .add(egui::DragValue::new(width).speed(0.1).range(0.01..=f32::MAX).prefix("width: "))
+                .changed();
+            changed |= ui
+                .add(egui::DragValue::new(height).speed(0.1).range(0.01..=f32::MAX).prefix("height: "))
+                .changed();
+            changed
+        }
+    };
+    if changed {
+        camera.set_settings(settings);
+    }
+
+    if let Some(console) = console {
+        let mut speed = console.get_or(CVAR_CAMERA_SPEED, 0.01f32);
+        if ui
+            .add(egui::Slider::new(&mut speed, 0.001..=1.0).logarithmic(true).text("Speed"))
+            .changed()
+        {
+            console.set(CVAR_CAMERA_SPEED, speed).ok();
+        }
+    }
+}
+
+fn passes_ui(ui: &mut egui::Ui, console: &mut Console) {
+    let mut ssao_enabled = console.get_or(CVAR_SSAO_ENABLED, true);
+    if ui.checkbox(&mut ssao_enabled, "SSAO").changed() {
+        console.set(CVAR_SSAO_ENABLED, ssao_enabled).ok();
+    }
+
+    let mut shadow_bias = console.get_or(CVAR_SHADOW_BIAS, DEFAULT_SHADOW_BIAS);
+    if ui
+        .add(
+            egui::Slider::new(&mut shadow_bias, 0.0..=0.001)
+                .logarithmic(true)
+                .text("Shadow bias"),
+        )
+        .changed()
+    {
+        console.set(CVAR_SHADOW_BIAS, shadow_bias).ok();
+    }
+
+    let mut tonemap = console.get_or(CVAR_TONEMAP, 0i32);
+    let names = ["Unreal", "Reinhard", "Uncharted 2"];
+    egui::ComboBox::from_label("Tonemap")
+        .selected_text(names.get(tonemap as usize).copied().unwrap_or("Unknown"))
+        .show_ui(ui, |ui| {
+            for (index, name) in names.iter().enumerate() {
+                ui.selectable_value(&mut tonemap, index as i32, *name);
+            }
+        });
+    if tonemap != console.get_or(CVAR_TONEMAP, 0i32) {
+        console.set(CVAR_TONEMAP, tonemap).ok();
+    }
+}
@@ -0,0 +1,153 @@
+//! Frame rate limiting and redraw scheduling
+use crate::{
+    asset::{AssetServer, Handle},
+    component::{
+        DirectionalLight, MaterialVariant, MorphWeights, PointLight, SpotLight, Transform,
+    },
+    model::Model,
+    resource::{Camera, Input},
+};
+use bevy_ecs::prelude::*;
+use std::time::{Duration, Instant};
+
+/// When the application renders new frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedrawMode {
+    /// Render frames back to back, limited only by the target frame rate
+    Continuous,
+    /// Render only when input, the camera or the scene changed, or a redraw was requested
+    OnDemand,
+}
+
+/// Frame pacing service
+///
+/// Read by the application every event loop iteration to decide whether and when the next frame
+/// is rendered. Systems can call [`FramePacing::request_redraw`] to force a frame in
+/// [`RedrawMode::OnDemand`].
+#[derive(Resource)]
+pub struct FramePacing {
+    pub mode: RedrawMode,
+    /// Frame rate limit while the window is focused, `None` for unlimited
+    pub target_fps: Option<f32>,
+    /// Frame rate limit while the window is not focused, `None` to use [`Self::target_fps`]
+    pub unfocused_fps: Option<f32>,
+    focused: bool,
+    redraw_requested: bool,
+    last_frame: Option<Instant>,
+}
+
+impl FramePacing {
+    /// Constructs an unlimited continuous pacing that drops to 10 FPS when unfocused
+    pub fn new() -> Self {
+        Self {
+            mode: RedrawMode::Continuous,
+            target_fps: None,
+            unfocused_fps: Some(10.0),
+            focused: true,
+            redraw_requested: true,
+            last_frame: None,
+        }
+    }
+
+    /// Constructs an on-demand pacing for editor-style tools
+    pub fn on_demand() -> Self {
+        Self {
+            mode: RedrawMode::OnDemand,
+            ..Self::new()
+        }
+    }
+
+    pub fn with_target_fps(mut self, fps: Option<f32>) -> Self {
+        self.target_fps = fps;
+        self
+    }
+
+    pub fn with_unfocused_fps(mut self, fps: Option<f32>) -> Self {
+        self.unfocused_fps = fps;
+        self
+    }
+
+    /// Requests a new frame in [`RedrawMode::OnDemand`]
+    pub fn request_redraw(&mut self) {
+        self.redraw_requested = true;
+    }
+
+    /// Returns true if a frame should be rendered, ignoring the frame rate limit
+    pub fn needs_redraw(&self) -> bool {
+        self.mode == RedrawMode::Continuous || self.redraw_requested
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Called by the application when the window gains or loses focus
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    /// Minimum duration between two frames for the current focus state
+    pub fn frame_interval(&self) -> Option<Duration> {
+        let fps = if self.focused {
+            self.target_fps
+        } else {
+            self.unfocused_fps.or(self.target_fps)
+        };
+
+        // Rates too small for a representable interval are treated as no limit.
+        fps.filter(|fps| *fps > 0.0)
+            .and_then(|fps| Duration::try_from_secs_f32(1.0 / fps).ok())
+    }
+
+    /// Earliest instant the next frame may start, `None` if it may start right away
+    pub fn next_frame_at(&self) -> Option<Instant> {
+        Some(self.last_frame? + self.frame_interval()?)
+    }
+
+    /// Called by the application when a frame starts rendering
+    pub fn begin_frame(&mut self) {
+        self.last_frame = Some(Instant::now());
+        self.redraw_requested = false;
+    }
+
+    /// This system requests the next frame while buttons are held, the camera moved, the scene
+    /// changed or assets are loading. It is run by the application after the runtime systems.
+    pub fn detect_changes(
+        mut pacing: ResMut<FramePacing>,
+        input: Option<Res<Input>>,
+        camera: Option<Res<Camera>>,
+        asset_server: Option<Res<AssetServer>>,
+        changed: Query<
+            (),
+            Or<(
+                Changed<Transform>,
+                Changed<Handle<Model>>,
+                Changed<DirectionalLight>,
+                Changed<PointLight>,
+                Changed<SpotLight>,
+                Changed<MorphWeights>,
+                Changed<MaterialVariant>,
+            )>,
+        >,
+    ) {
+        if pacing.mode != RedrawMode::OnDemand || pacing.redraw_requested {
+            return;
+        }
+
+        let input_active = input.is_some_and(|input| input.any_button_held());
+        let camera_moved = camera.is_some_and(|camera| camera.view() != camera.prev_view());
+        // Keep polling while loading, finished loads change the server.
+        let assets_changed = asset_server
+            .is_some_and(|asset_server| asset_server.is_loading() || asset_server.is_changed());
+
+        if input_active || camera_moved || assets_changed || !changed.is_empty() {
+            pacing.request_redraw();
+        }
+    }
+}
+
+impl Default for FramePacing {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -8,6 +8,8 @@ pub struct GlobalSamplers {
     pub clamp: Arc<Sampler>,
     pub wrap: Arc<Sampler>,
     pub mirror: Arc<Sampler>,
+    /// Clamped without filtering, for integer textures
+    pub nearest: Arc<Sampler>,
 }
 
 impl Default for GlobalSamplers {
@@ -45,10 +47,22 @@ impl Default for GlobalSamplers {
         )
         .unwrap();
 
+        let nearest = Sampler::new(
+            VULKAN.device().clone(),
+            SamplerCreateInfo {
+                mag_filter: Filter::Nearest,
+                min_filter: Filter::Nearest,
+                address_mode: [SamplerAddressMode::ClampToEdge; 3],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
         Self {
             clamp,
             wrap,
             mirror,
+            nearest,
         }
     }
 }
@@ -1,6 +1,6 @@
 use bevy_ecs::system::Resource;
 use mo_vk::{Texture, TextureCreateInfo, VULKAN};
-use std::sync::Arc;
+use std::{path::Path, sync::Arc};
 use vulkano::image::max_mip_levels;
 use vulkano::{
     command_buffer::{AutoCommandBufferBuilder, CommandBufferUsage},
@@ -28,8 +28,11 @@ use vulkano::{
     sync::GpuFuture,
 };
 
-#[derive(Resource)]
+/// Cloning is cheap, the textures are shared.
+#[derive(Resource, Clone)]
 pub struct IBLResource {
+    /// Name of the environment map in `resources/tex/env`, or a path to an `.hdr`/`.exr` file
+    pub name: String,
     // IBL related textures
     pub cubemap_src: Arc<Texture>,
     pub environment_map: Arc<Texture>,
@@ -41,7 +44,18 @@ pub struct IBLResource {
 const IBL_IMAGE_WIDTH_HEIGHT: u32 = 512;
 
 impl IBLResource {
+    /// Prepares the IBL textures of an environment map. `name` is either the file stem of a map in
+    /// `resources/tex/env` or a path to an `.hdr`/`.exr` file.
     pub fn new(name: &str) -> Self {
+        match Self::try_new(name) {
+            Ok(ibl) => ibl,
+            Err(err) => panic!("Loading environment map {} failed with error: {}", name, err),
+        }
+    }
+
+    /// Same as [`IBLResource::new`], but returns the error if the environment map can not be
+    /// loaded.
+    pub fn try_new(name: &str) -> Result<Self, image::ImageError> {
         let equirect_info = TextureCreateInfo::default_hdr();
         let irrad_maps_info = TextureCreateInfo {
             extent: [IBL_IMAGE_WIDTH_HEIGHT, IBL_IMAGE_WIDTH_HEIGHT, 1],
@@ -63,9 +77,16 @@ impl IBLResource {
             ..Default::default()
         };
 
-        let path = format!("env/{name}.hdr");
+        let path = if Self::is_environment_file(name) {
+            // Absolute, so it is not resolved relative to `resources/tex`
+            std::path::absolute(name)
+                .map(|path| path.display().to_string())
+                .unwrap_or_else(|_| name.to_string())
+        } else {
+            format!("env/{name}.hdr")
+        };
 
-        let equirect_map = Arc::new(Texture::load_from_file(path.as_str(), &equirect_info));
+        let equirect_map = Arc::new(Texture::try_load_from_file(path.as_str(), &equirect_info)?);
 
         let environment_map = Arc::new(Texture::new_cubemap(irrad_maps_info.clone()));
         let irradiance_map = Arc::new(Texture::new_cubemap(irrad_maps_info.clone()));
@@ -89,13 +110,21 @@ impl IBLResource {
 
         tracing::info!("ECS - IBL Textures resources successfully prepared.");
 
-        Self {
+        Ok(Self {
+            name: name.to_string(),
             cubemap_src: equirect_map,
             environment_map,
             irradiance_map,
             specular_map,
             brdf_lut,
-        }
+        })
+    }
+
+    /// Returns true if `path` has an environment map file extension
+    pub fn is_environment_file(path: impl AsRef<Path>) -> bool {
+        path.as_ref().extension().is_some_and(|extension| {
+            extension.eq_ignore_ascii_case("hdr") || extension.eq_ignore_ascii_case("exr")
+        })
     }
 }
 
@@ -2,16 +2,17 @@
 use bevy_ecs::prelude::Resource;
 use bevy_math::Vec2;
 use foldhash::{HashMap, HashMapExt};
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use winit::event::{ElementState, KeyEvent, Modifiers, MouseButton, MouseScrollDelta, WindowEvent};
-use winit::keyboard::PhysicalKey;
+use winit::keyboard::{ModifiersState, PhysicalKey};
 
 /// Action requirements trait
 pub trait TIntoAction: Copy + Eq + std::hash::Hash + std::fmt::Debug {}
 impl<T: Copy + Eq + Send + Sync + std::hash::Hash + std::fmt::Debug> TIntoAction for T {}
 
 /// Input button abstraction
-#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)] // TODO: add support for serialization
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub enum EInputButton {
     /// Key by the code
     Key(PhysicalKey),
@@ -26,7 +27,7 @@ pub enum EInputButton {
 }
 
 /// State of a button
-#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub enum EInputState {
     /// Button was activated (pressed)
     Activated,
@@ -59,6 +60,29 @@ pub struct FKeyEvent {
     pub modifiers: Modifiers,
 }
 
+/// Snapshot of the [`Input`] state for a single frame
+///
+/// Used by [`crate::resource::InputRecorder`] to record and replay input deterministically.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FInputFrame {
+    /// Frame delta time in seconds, as reported by [`crate::resource::Timer`]
+    pub delta: f64,
+    /// Button states with the modifiers active when they changed
+    pub states: Vec<(EInputButton, EInputState, ModifiersState)>,
+    /// Active modifiers
+    pub modifiers: ModifiersState,
+    /// Mouse scroll delta
+    pub mouse_scroll: f32,
+    /// Mouse horizontal scroll delta
+    pub mouse_horizontal_scroll: f32,
+    /// Mouse position in pixel coordinates
+    pub mouse_position: Option<[f32; 2]>,
+    /// Mouse motion delta
+    pub mouse_delta: [f32; 2],
+    /// Whether the mouse was moved
+    pub mouse_moved: bool,
+}
+
 /// Sample input Actions enumeration
 #[derive(Hash, Eq, PartialEq, Clone, Copy, Debug)]
 pub enum EInputAction {
@@ -188,6 +212,13 @@ impl Input {
         self.states.get(&button).map(|(state, _modifiers)| *state)
     }
 
+    /// Checks if any button is currently pressed or hold
+    pub fn any_button_held(&self) -> bool {
+        self.states
+            .values()
+            .any(|(state, _modifiers)| *state != EInputState::Deactivated)
+    }
+
     /// Checks if mapped action button is pressed
     pub fn is_action_activated<T>(&self, action: T) -> bool
     where
@@ -292,6 +323,45 @@ impl Input {
         Vec2::new(x, y)
     }
 
+    /// Captures the current state into a [`FInputFrame`]. Frame delta is left at zero.
+    pub fn capture_frame(&self) -> FInputFrame {
+        FInputFrame {
+            delta: 0.0,
+            states: self
+                .states
+                .iter()
+                .map(|(button, (state, modifiers))| (*button, *state, modifiers.state()))
+                .collect(),
+            modifiers: self.modifiers.state(),
+            mouse_scroll: self.mouse_scroll_delta,
+            mouse_horizontal_scroll: self.mouse_horizontal_scroll_delta,
+            mouse_position: self.mouse_position.map(|p| p.into()),
+            mouse_delta: self.mouse_delta.into(),
+            mouse_moved: self.mouse_moved,
+        }
+    }
+
+    /// Overrides the current state with a previously captured [`FInputFrame`]
+    pub fn apply_frame(&mut self, frame: &FInputFrame) {
+        self.states.clear();
+        for (button, state, modifiers) in &frame.states {
+            self.states
+                .insert(*button, (*state, Modifiers::from(*modifiers)));
+        }
+        self.modifiers = Modifiers::from(frame.modifiers);
+        self.mouse_scroll_delta = frame.mouse_scroll;
+        self.mouse_horizontal_scroll_delta = frame.mouse_horizontal_scroll;
+        self.mouse_position = frame.mouse_position.map(Vec2::from);
+        self.mouse_delta = Vec2::from(frame.mouse_delta);
+        self.mouse_moved = frame.mouse_moved;
+    }
+
+    /// Clears the events collected during the frame. Called by the application after the runtime
+    /// systems have run.
+    pub fn clear_events(&mut self) {
+        self.events.clear();
+    }
+
     /// This method must be called periodically to update states from events
     pub(crate) fn _reset(&mut self) {
         self.mouse_moved = false;
@@ -324,15 +394,6 @@ impl Input {
                 self.window_size = Vec2::new(size.width as f32, size.height as f32)
             }
             WindowEvent::ModifiersChanged(input) => self.modifiers = *input,
-            // WindowEvent::ReceivedCharacter(chr) => {
-            //     if is_printable(*chr) && !self.modifiers.ctrl() && !self.modifiers.logo() {
-            //         if let Some(EInputEvent::Text(text)) = self.events.last_mut() {
-            //             text.push(*chr);
-            //         } else {
-            //             self.events.push(EInputEvent::Text(chr.to_string()));
-            //         }
-            //     }
-            // }
             WindowEvent::HoveredFile(buffer) => self.on_hovered_file_event(buffer),
             WindowEvent::HoveredFileCancelled => self.on_hovered_file_canceled_event(),
             WindowEvent::DroppedFile(buffer) => self.on_dropped_file_event(buffer),
@@ -364,6 +425,14 @@ impl Input {
             modifiers,
             pressed: input.state == ElementState::Pressed,
         }));
+        if input.state == ElementState::Pressed
+            && !modifiers.state().control_key()
+            && !modifiers.state().super_key()
+        {
+            if let Some(text) = input.text.as_ref() {
+                self.on_text_event(text);
+            }
+        }
         self.on_button_state(
             EInputButton::Key(input.physical_key),
             input.state,
@@ -371,6 +440,16 @@ impl Input {
         );
     }
 
+    fn on_text_event(&mut self, text: &str) {
+        for chr in text.chars().filter(|chr| is_printable(*chr)) {
+            if let Some(EInputEvent::Text(text)) = self.events.last_mut() {
+                text.push(chr);
+            } else {
+                self.events.push(EInputEvent::Text(chr.to_string()));
+            }
+        }
+    }
+
     fn on_button_state(&mut self, btn: EInputButton, state: ElementState, modifiers: Modifiers) {
         match state {
             ElementState::Pressed => {
@@ -437,6 +516,15 @@ impl Input {
     }
 }
 
+#[inline]
+fn is_printable(chr: char) -> bool {
+    let is_in_private_use_area = ('\u{e000}'..='\u{f8ff}').contains(&chr)
+        || ('\u{f0000}'..='\u{ffffd}').contains(&chr)
+        || ('\u{100000}'..='\u{10fffd}').contains(&chr);
+
+    !is_in_private_use_area && !chr.is_ascii_control()
+}
+
 impl Default for Input {
     /// [`Input`] service constructor
     fn default() -> Self {
@@ -0,0 +1,196 @@
+//! Input recording and deterministic replay
+use crate::resource::{
+    input::{FInputFrame, Input},
+    FramePacing, Timer,
+};
+use bevy_ecs::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+    time::Duration,
+};
+
+/// A recorded sequence of input frames, serialized as JSON.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FInputRecording {
+    pub frames: Vec<FInputFrame>,
+}
+
+impl FInputRecording {
+    /// Loads a recording from a JSON file, frame deltas must be finite and not negative
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let recording: Self = serde_json::from_reader(reader)?;
+        if let Some((index, frame)) = recording
+            .frames
+            .iter()
+            .enumerate()
+            .find(|(_, frame)| Duration::try_from_secs_f64(frame.delta).is_err())
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("frame {index} has an invalid delta {}", frame.delta),
+            ));
+        }
+        Ok(recording)
+    }
+
+    /// Writes the recording to a JSON file
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        Ok(serde_json::to_writer(writer, self)?)
+    }
+}
+
+/// Current state of the [`InputRecorder`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ERecorderState {
+    /// Live input is passed through untouched
+    Idle,
+    /// Every frame of live input is appended to the recording
+    Recording,
+    /// Live input is replaced by the recorded frames
+    Replaying {
+        /// Index of the next frame to replay
+        frame: usize,
+        /// Timestep used instead of the recorded frame delta
+        fixed_delta: Option<Duration>,
+    },
+}
+
+/// Input recording service
+///
+/// Records the [`Input`] state and [`Timer`] delta of every frame and replays them later, so a
+/// camera path can be reproduced exactly. Register [`InputRecorder::replay_input`] before
+/// [`Timer::update_timer`] and [`InputRecorder::record_input`] after it, both before any system
+/// reading [`Input`].
+#[derive(Resource)]
+pub struct InputRecorder {
+    state: ERecorderState,
+    recording: FInputRecording,
+}
+
+impl InputRecorder {
+    /// Constructs an idle recorder
+    pub fn new() -> Self {
+        Self {
+            state: ERecorderState::Idle,
+            recording: FInputRecording::default(),
+        }
+    }
+
+    /// Returns the recorder state
+    pub fn state(&self) -> ERecorderState {
+        self.state
+    }
+
+    /// Returns the frames recorded or loaded so far
+    pub fn recording(&self) -> &FInputRecording {
+        &self.recording
+    }
+
+    /// Discards the current recording and starts recording live input
+    pub fn start_recording(&mut self) {
+        self.recording.frames.clear();
+        self.state = ERecorderState::Recording;
+        tracing::info!("Input - Input recording started.");
+    }
+
+    /// Stops recording and writes the recorded frames to `path`
+    pub fn stop_recording(&mut self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        self.state = ERecorderState::Idle;
+        self.recording.save(path.as_ref())?;
+        tracing::info!(
+            "Input - Input recording with {} frames saved to {}.",
+            self.recording.frames.len(),
+            path.as_ref().display()
+        );
+        Ok(())
+    }
+
+    /// Loads a recording from `path` and starts replaying it.
+    ///
+    /// Every replayed frame advances the [`Timer`] by `fixed_delta`, or by the recorded frame delta
+    /// when `None` is given.
+    pub fn start_replay(
+        &mut self,
+        path: impl AsRef<Path>,
+        fixed_delta: Option<Duration>,
+    ) -> std::io::Result<()> {
+        self.replay(FInputRecording::load(path)?, fixed_delta);
+        Ok(())
+    }
+
+    /// Starts replaying an in-memory recording
+    pub fn replay(&mut self, recording: FInputRecording, fixed_delta: Option<Duration>) {
+        tracing::info!(
+            "Input - Replaying input recording with {} frames.",
+            recording.frames.len()
+        );
+        self.recording = recording;
+        self.state = ERecorderState::Replaying {
+            frame: 0,
+            fixed_delta,
+        };
+    }
+
+    /// Returns true while a recording is being replayed
+    pub fn is_replaying(&self) -> bool {
+        matches!(self.state, ERecorderState::Replaying { .. })
+    }
+
+    /// This system overrides [`Input`] and the [`Timer`] timestep with the next recorded frame.
+    /// It should run before [`Timer::update_timer`]. Replays keep requesting frames, nothing else
+    /// does while the recorded input plays back.
+    pub fn replay_input(
+        mut recorder: ResMut<InputRecorder>,
+        mut input: ResMut<Input>,
+        mut timer: ResMut<Timer>,
+        pacing: Option<ResMut<FramePacing>>,
+    ) {
+        let ERecorderState::Replaying { frame, fixed_delta } = recorder.state else {
+            return;
+        };
+
+        let Some(input_frame) = recorder.recording.frames.get(frame) else {
+            recorder.state = ERecorderState::Idle;
+            timer.set_fixed_delta(None);
+            tracing::info!("Input - Input replay finished.");
+            return;
+        };
+
+        input.apply_frame(input_frame);
+        if let Some(mut pacing) = pacing {
+            pacing.request_redraw();
+        }
+        // Recordings passed to `replay` directly are not validated, invalid deltas do not advance.
+        timer.set_fixed_delta(Some(fixed_delta.unwrap_or_else(|| {
+            Duration::try_from_secs_f64(input_frame.delta).unwrap_or(Duration::ZERO)
+        })));
+
+        recorder.state = ERecorderState::Replaying {
+            frame: frame + 1,
+            fixed_delta,
+        };
+    }
+
+    /// This system appends the current [`Input`] state and [`Timer`] delta to the recording.
+    /// It should run after [`Timer::update_timer`].
+    pub fn record_input(mut recorder: ResMut<InputRecorder>, input: Res<Input>, timer: Res<Timer>) {
+        if recorder.state != ERecorderState::Recording {
+            return;
+        }
+
+        let mut frame = input.capture_frame();
+        frame.delta = timer.delta().as_secs_f64();
+        recorder.recording.frames.push(frame);
+    }
+}
+
+impl Default for InputRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
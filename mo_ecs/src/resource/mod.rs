@@ -1,13 +1,21 @@
 pub mod camera;
+pub mod console;
+pub mod debug_ui;
 pub mod default_tex;
+pub mod frame_pacing;
 pub mod global_samplers;
 pub mod ibl_resource;
 pub mod input;
+pub mod input_recorder;
 pub mod timer;
 
 pub use camera::Camera;
+pub use console::Console;
+pub use debug_ui::DebugUi;
 pub use default_tex::DefaultTextures;
+pub use frame_pacing::FramePacing;
 pub use global_samplers::GlobalSamplers;
 pub use ibl_resource::IBLResource;
 pub use input::Input;
+pub use input_recorder::InputRecorder;
 pub use timer::Timer;
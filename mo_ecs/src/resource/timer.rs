@@ -13,6 +13,7 @@ pub struct Timer {
     fps: Option<f32>,
     delta: Duration,
     time: Duration,
+    fixed_delta: Option<Duration>,
 }
 
 impl Timer {
@@ -26,21 +27,28 @@ impl Timer {
             fps: None,
             delta: Duration::from_secs(0),
             time: Duration::from_secs(0),
+            fixed_delta: None,
         }
     }
 
     pub fn next(&mut self) {
         let now = Instant::now();
-        if let Some(first) = self.first {
-            self.time = now - first;
+        if let Some(fixed_delta) = self.fixed_delta {
+            // With a fixed timestep the simulated time no longer follows the wall clock.
+            self.delta = fixed_delta;
+            self.time += fixed_delta;
         } else {
+            if let Some(first) = self.first {
+                self.time = now - first;
+            }
+            if let Some(current) = self.current {
+                self.delta = now - current;
+            }
+        }
+        if self.first.is_none() {
             self.first = Some(now);
             self.counter_start = now;
         }
-
-        if let Some(current) = self.current {
-            self.delta = now - current;
-        }
         self.current = Some(now);
 
         let duration = now - self.counter_start;
@@ -53,6 +61,23 @@ impl Timer {
         self.counter += 1;
     }
 
+    /// Makes every following frame advance by `delta` instead of the measured wall clock time.
+    /// Pass `None` to go back to real time. FPS is still measured from the wall clock.
+    pub fn set_fixed_delta(&mut self, delta: Option<Duration>) {
+        if self.fixed_delta.is_some() && delta.is_none() {
+            // Rebase the start instant so that `time` keeps going from the simulated value.
+            self.first = self
+                .current
+                .and_then(|current| current.checked_sub(self.time));
+        }
+        self.fixed_delta = delta;
+    }
+
+    /// Returns the fixed timestep, if one is set
+    pub fn fixed_delta(&self) -> Option<Duration> {
+        self.fixed_delta
+    }
+
     /// Returns [`Duration`] from application start
     pub fn time(&self) -> Duration {
         self.time
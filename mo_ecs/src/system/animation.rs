@@ -0,0 +1,151 @@
+use crate::{
+    asset::AssetServer,
+    component::{AnimationPlayer, MorphWeights, SceneInstance, SceneNode, SceneRoot, Transform},
+    model::{EAnimationProperty, Model},
+    resource::Timer,
+};
+use bevy_ecs::prelude::*;
+use bevy_math::{Quat, Vec3, Vec4};
+use std::collections::HashMap;
+
+/// Longest step of a frame in seconds. In on-demand redraw mode the first frame after an idle
+/// period reports the whole idle time, which would make the animation jump.
+const MAX_DELTA: f32 = 0.25;
+
+/// This system advances every [`AnimationPlayer`] by the [`Timer`] delta and poses the node
+/// entities of its [`SceneRoot`]. It should be added to the runtime schedule after
+/// [`Timer::update_timer`].
+pub fn play_animations(
+    mut commands: Commands,
+    timer: Res<Timer>,
+    asset_server: Res<AssetServer>,
+    mut players: Query<(&mut AnimationPlayer, &SceneRoot, &SceneInstance)>,
+    mut nodes: Query<(&mut Transform, Option<&mut MorphWeights>), With<SceneNode>>,
+) {
+    let delta = timer.delta_time().min(MAX_DELTA);
+
+    for (mut player, root, instance) in &mut players {
+        let Some(model) = asset_server.get(&root.0) else {
+            continue;
+        };
+
+        if player.is_playing() {
+            player.advance(delta, |clip| {
+                model
+                    .animations
+                    .get(clip)
+                    .map(|animation| animation.duration)
+            });
+        }
+        // Paused and finished players leave the nodes alone, so they can be moved by hand.
+        if !player.is_changed() {
+            continue;
+        }
+
+        for (node, pose) in sample_pose(&player, model) {
+            let Some(entity) = instance.nodes.get(node).copied().flatten() else {
+                continue;
+            };
+            let Ok((mut transform, weights)) = nodes.get_mut(entity) else {
+                continue;
+            };
+
+            let rest = &model.nodes[node].transform;
+            if let Some((translation, weight)) = pose.translation {
+                transform.translation =
+                    rest.translation.lerp(translation / weight, weight.min(1.0));
+            }
+            if let Some((rotation, weight)) = pose.rotation {
+                let rotation = Quat::from_vec4(rotation).normalize();
+                transform.rotation = rest.rotation.slerp(rotation, weight.min(1.0));
+            }
+            if let Some((scale, weight)) = pose.scale {
+                transform.scale = rest.scale.lerp(scale / weight, weight.min(1.0));
+            }
+
+            if let Some((mut morph_weights, weight)) = pose.weights {
+                let rest_weights = &model.nodes[node].morph_weights;
+                for (index, value) in morph_weights.iter_mut().enumerate() {
+                    let rest = rest_weights.get(index).copied().unwrap_or_default();
+                    *value = rest + (*value / weight - rest) * weight.min(1.0);
+                }
+                match weights {
+                    Some(mut weights) => weights.0 = morph_weights,
+                    None => {
+                        commands.entity(entity).insert(MorphWeights(morph_weights));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Weighted sums of the sampled values of a node and the sum of the weights
+#[derive(Default)]
+struct NodePose {
+    translation: Option<(Vec3, f32)>,
+    rotation: Option<(Vec4, f32)>,
+    scale: Option<(Vec3, f32)>,
+    weights: Option<(Vec<f32>, f32)>,
+}
+
+/// Blends the clips of `player` into a pose per animated node. A property animated by a clip
+/// fading in or out only has the weight of that clip, the rest pose makes up the difference.
+fn sample_pose(player: &AnimationPlayer, model: &Model) -> HashMap<usize, NodePose> {
+    let mut poses: HashMap<usize, NodePose> = HashMap::new();
+    let mut value = Vec::new();
+
+    for (clip, time, weight) in player.weighted_clips() {
+        let Some(animation) = model.animations.get(clip) else {
+            continue;
+        };
+        if weight <= 0.0 {
+            continue;
+        }
+
+        for channel in &animation.channels {
+            value.resize(channel.stride, 0.0);
+            channel.sample(time, &mut value);
+
+            let pose = poses.entry(channel.node).or_default();
+            match channel.property {
+                EAnimationProperty::Translation => {
+                    accumulate(&mut pose.translation, Vec3::from_slice(&value), weight);
+                }
+                EAnimationProperty::Scale => {
+                    accumulate(&mut pose.scale, Vec3::from_slice(&value), weight);
+                }
+                EAnimationProperty::Rotation => {
+                    let mut rotation = Vec4::from_slice(&value);
+                    // q and -q are the same rotation, blend along the shorter arc.
+                    if let Some((sum, _)) = pose.rotation {
+                        if sum.dot(rotation) < 0.0 {
+                            rotation = -rotation;
+                        }
+                    }
+                    accumulate(&mut pose.rotation, rotation, weight);
+                }
+                EAnimationProperty::MorphWeights => {
+                    let (sum, total) = pose
+                        .weights
+                        .get_or_insert_with(|| (vec![0.0; value.len()], 0.0));
+                    for (sum, value) in sum.iter_mut().zip(&value) {
+                        *sum += value * weight;
+                    }
+                    *total += weight;
+                }
+            }
+        }
+    }
+
+    poses
+}
+
+fn accumulate<T>(sum: &mut Option<(T, f32)>, value: T, weight: f32)
+where
+    T: Copy + Default + std::ops::Add<Output = T> + std::ops::Mul<f32, Output = T>,
+{
+    let (sum, total) = sum.get_or_insert((T::default(), 0.0));
+    *sum = *sum + value * weight;
+    *total += weight;
+}
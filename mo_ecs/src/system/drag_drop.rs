@@ -0,0 +1,82 @@
+use crate::{
+    asset::{AssetServer, ELoadState, Handle},
+    component::{SceneRoot, Transform},
+    model::Model,
+    resource::{IBLResource, Input},
+};
+use bevy_ecs::prelude::*;
+use std::path::Path;
+
+/// Returns true if `path` has a glTF model file extension
+pub fn is_model_file(path: impl AsRef<Path>) -> bool {
+    path.as_ref().extension().is_some_and(|extension| {
+        extension.eq_ignore_ascii_case("gltf") || extension.eq_ignore_ascii_case("glb")
+    })
+}
+
+/// This system loads the files dropped on the window in the background.
+///
+/// Models are spawned as [`SceneRoot`]s at the origin right away and rendered as placeholders
+/// until loaded, environment maps replace the [`IBLResource`] once loaded. Files which can not
+/// be loaded are logged and skipped, the roots of models which fail to load are despawned.
+pub fn load_dropped_files(
+    mut commands: Commands,
+    mut input: ResMut<Input>,
+    mut asset_server: ResMut<AssetServer>,
+    mut ibl: Option<ResMut<IBLResource>>,
+    mut pending_environments: Local<Vec<Handle<IBLResource>>>,
+    mut pending_models: Local<Vec<(Entity, Handle<Model>)>>,
+) {
+    pending_models.retain(|(root, model)| match asset_server.load_state(model) {
+        ELoadState::Loading => true,
+        ELoadState::Loaded => false,
+        ELoadState::Failed => {
+            if let Some(mut root) = commands.get_entity(*root) {
+                root.despawn();
+            }
+            false
+        }
+    });
+
+    // The last dropped environment map wins if several finish in the same frame.
+    pending_environments.retain(|environment| match asset_server.load_state(environment) {
+        ELoadState::Loading => true,
+        ELoadState::Loaded => {
+            if let (Some(ibl), Some(environment)) = (ibl.as_mut(), asset_server.get(environment)) {
+                **ibl = environment.clone();
+            }
+            false
+        }
+        ELoadState::Failed => false,
+    });
+
+    let Some(dropped_files) = input.dropped_files.take() else {
+        return;
+    };
+
+    for path in dropped_files {
+        // Absolute, so the loaders do not resolve it relative to `resources`
+        let path = std::path::absolute(&path).unwrap_or(path);
+        let name = path.to_string_lossy();
+
+        if is_model_file(&path) {
+            tracing::info!("ECS - Loading dropped model {name}");
+            let model = asset_server.load::<Model>(&name);
+            let root = commands
+                .spawn((Transform::IDENTITY, SceneRoot(model.clone())))
+                .id();
+            pending_models.push((root, model));
+        } else if IBLResource::is_environment_file(&path) {
+            if ibl.is_none() {
+                tracing::error!("ECS - Dropped environment map {name} ignored, no IBL resource.");
+                continue;
+            }
+            tracing::info!("ECS - Loading dropped environment map {name}");
+            pending_environments.push(asset_server.load::<IBLResource>(&name));
+        } else {
+            tracing::error!(
+                "ECS - Dropped file {name} is neither a glTF model nor an environment map."
+            );
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use crate::component::{
+    DirectionalLight, GlobalTransform, PointLight, SceneNode, SpotLight, Transform,
+};
+use bevy_ecs::prelude::*;
+
+type MovedNode = (With<SceneNode>, Changed<GlobalTransform>);
+
+/// This system moves the lights imported with a [`SceneNode`] to the world transform of their
+/// node, the renderer reads the transform of the light components. The application runs it after
+/// [`propagate_transforms`](crate::system::propagate_transforms).
+pub fn sync_scene_lights(
+    mut directional_lights: Query<(&GlobalTransform, &mut DirectionalLight), MovedNode>,
+    mut point_lights: Query<(&GlobalTransform, &mut PointLight), MovedNode>,
+    mut spot_lights: Query<(&GlobalTransform, &mut SpotLight), MovedNode>,
+) {
+    for (global_transform, mut light) in &mut directional_lights {
+        light.transform = Transform::from_matrix(global_transform.0);
+    }
+    for (global_transform, mut light) in &mut point_lights {
+        light.transform = Transform::from_matrix(global_transform.0);
+    }
+    for (global_transform, mut light) in &mut spot_lights {
+        light.transform = Transform::from_matrix(global_transform.0);
+    }
+}
@@ -0,0 +1,19 @@
+use crate::component::{MaterialVariant, SceneInstance};
+use bevy_ecs::prelude::*;
+
+/// This system copies the [`MaterialVariant`] of every scene root to its node entities, which
+/// draw the meshes. The application runs it after
+/// [`spawn_scene_nodes`](crate::system::spawn_scene_nodes).
+pub fn sync_material_variants(
+    mut commands: Commands,
+    roots: Query<
+        (&MaterialVariant, &SceneInstance),
+        Or<(Changed<MaterialVariant>, Added<SceneInstance>)>,
+    >,
+) {
+    for (variant, instance) in &roots {
+        for &entity in instance.nodes.iter().flatten() {
+            commands.entity(entity).insert(*variant);
+        }
+    }
+}
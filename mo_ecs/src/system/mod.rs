@@ -0,0 +1,13 @@
+pub mod animation;
+pub mod drag_drop;
+pub mod light;
+pub mod material_variant;
+pub mod scene;
+pub mod transform_propagation;
+
+pub use animation::play_animations;
+pub use drag_drop::load_dropped_files;
+pub use light::sync_scene_lights;
+pub use material_variant::sync_material_variants;
+pub use scene::spawn_scene_nodes;
+pub use transform_propagation::propagate_transforms;
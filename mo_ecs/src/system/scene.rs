@@ -0,0 +1,124 @@
+use crate::{
+    asset::{AssetServer, Handle},
+    color::EColorSpace,
+    component::{
+        Children, DirectionalLight, MorphWeights, Name, Parent, PointLight, SceneInstance,
+        SceneNode, SceneRoot, SpotLight,
+    },
+    model::{ENodeLightKind, Model},
+};
+use bevy_ecs::prelude::*;
+
+/// This system spawns the node entities of every [`SceneRoot`] whose model finished loading.
+/// The application runs it at the end of each frame.
+pub fn spawn_scene_nodes(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    roots: Query<(Entity, &SceneRoot), Without<SceneInstance>>,
+) {
+    for (entity, root) in &roots {
+        let Some(model) = asset_server.get(&root.0) else {
+            continue;
+        };
+
+        let mut spawner = NodeSpawner {
+            commands: &mut commands,
+            handle: &root.0,
+            model,
+            root: entity,
+            instance: SceneInstance {
+                nodes: vec![None; model.nodes.len()],
+            },
+        };
+        let children = model
+            .root_nodes
+            .iter()
+            .map(|&node| spawner.spawn(node, entity))
+            .collect();
+        let instance = spawner.instance;
+        commands.entity(entity).insert((Children(children), instance));
+
+        tracing::info!("ECS - Spawned the nodes of {}.", root.0.path());
+    }
+}
+
+struct NodeSpawner<'a, 'w, 's> {
+    commands: &'a mut Commands<'w, 's>,
+    handle: &'a Handle<Model>,
+    model: &'a Model,
+    root: Entity,
+    instance: SceneInstance,
+}
+
+impl NodeSpawner<'_, '_, '_> {
+    fn spawn(&mut self, index: usize, parent: Entity) -> Entity {
+        let model = self.model;
+        let node = &model.nodes[index];
+        let name = node.name.clone().unwrap_or_else(|| format!("Node {index}"));
+
+        let entity = self
+            .commands
+            .spawn((
+                Name(name),
+                node.transform,
+                Parent(parent),
+                SceneNode {
+                    model: self.handle.clone(),
+                    node: index,
+                    root: self.root,
+                },
+            ))
+            .id();
+        self.instance.nodes[index] = Some(entity);
+
+        let children: Vec<Entity> = node
+            .children
+            .iter()
+            .map(|&child| self.spawn(child, entity))
+            .collect();
+        if !children.is_empty() {
+            self.commands.entity(entity).insert(Children(children));
+        }
+        if !node.morph_weights.is_empty() {
+            self.commands
+                .entity(entity)
+                .insert(MorphWeights(node.morph_weights.clone()));
+        }
+        // The light follows the world transform of the node, see `sync_scene_lights`.
+        if let Some(light) = &node.light {
+            let mut entity = self.commands.entity(entity);
+            match light.kind {
+                ENodeLightKind::Directional => entity.insert(DirectionalLight::new(
+                    node.transform,
+                    light.color,
+                    EColorSpace::Linear,
+                    light.intensity,
+                    false,
+                )),
+                ENodeLightKind::Point => entity.insert(PointLight::new(
+                    node.transform,
+                    light.color,
+                    EColorSpace::Linear,
+                    light.intensity,
+                    light.range,
+                )),
+                ENodeLightKind::Spot {
+                    inner_cone_angle,
+                    outer_cone_angle,
+                } => entity.insert(SpotLight {
+                    range: light.range,
+                    ..SpotLight::new(
+                        node.transform,
+                        light.color,
+                        EColorSpace::Linear,
+                        light.intensity,
+                        inner_cone_angle,
+                        outer_cone_angle,
+                    )
+                }),
+            };
+        }
+
+        entity
+    }
+}
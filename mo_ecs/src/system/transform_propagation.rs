@@ -0,0 +1,51 @@
+use crate::component::{Children, GlobalTransform, Parent, Transform};
+use bevy_ecs::prelude::*;
+use bevy_math::Mat4;
+
+/// This system computes the [`GlobalTransform`] of every entity from its [`Transform`] and the
+/// transforms of its parents. The application runs it at the end of each frame.
+pub fn propagate_transforms(
+    roots: Query<(Entity, &Transform, Option<&Children>), Without<Parent>>,
+    nodes: Query<(&Transform, Option<&Children>), With<Parent>>,
+    mut global_transforms: Query<&mut GlobalTransform>,
+) {
+    for (entity, transform, children) in &roots {
+        let global_transform = transform.model_matrix();
+        set_global_transform(&mut global_transforms, entity, global_transform);
+
+        if let Some(children) = children {
+            propagate(&nodes, &mut global_transforms, children, global_transform);
+        }
+    }
+}
+
+fn propagate(
+    nodes: &Query<(&Transform, Option<&Children>), With<Parent>>,
+    global_transforms: &mut Query<&mut GlobalTransform>,
+    children: &Children,
+    parent_transform: Mat4,
+) {
+    for &child in &children.0 {
+        let Ok((transform, grandchildren)) = nodes.get(child) else {
+            continue;
+        };
+
+        let global_transform = parent_transform * transform.model_matrix();
+        set_global_transform(global_transforms, child, global_transform);
+
+        if let Some(grandchildren) = grandchildren {
+            propagate(nodes, global_transforms, grandchildren, global_transform);
+        }
+    }
+}
+
+fn set_global_transform(
+    global_transforms: &mut Query<&mut GlobalTransform>,
+    entity: Entity,
+    matrix: Mat4,
+) {
+    if let Ok(mut global_transform) = global_transforms.get_mut(entity) {
+        // Only mark changed when moved, so static scenes do not trigger redraws.
+        global_transform.set_if_neq(GlobalTransform(matrix));
+    }
+}
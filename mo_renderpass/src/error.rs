@@ -0,0 +1,55 @@
+use thiserror::Error;
+use vulkano::{
+    Validated, ValidationError, VulkanError,
+    buffer::AllocateBufferError,
+    command_buffer::CommandBufferExecError,
+    memory::allocator::MemoryAllocatorError,
+    sync::HostAccessError,
+};
+
+/// Errors returned while recording or submitting a frame
+#[derive(Debug, Error)]
+pub enum RenderError {
+    #[error("vulkan error: {0}")]
+    Vulkan(#[from] VulkanError),
+    #[error("validation error: {0}")]
+    Validation(#[from] Box<ValidationError>),
+    #[error("failed to allocate memory: {0}")]
+    MemoryAllocation(#[from] MemoryAllocatorError),
+    #[error("failed to allocate buffer: {0}")]
+    BufferAllocation(#[from] AllocateBufferError),
+    #[error("failed to access buffer from host: {0}")]
+    HostAccess(#[from] HostAccessError),
+    #[error("failed to execute command buffer: {0}")]
+    CommandBufferExec(#[from] CommandBufferExecError),
+}
+
+impl RenderError {
+    /// Returns true if the logical device was lost and every GPU resource must be recreated
+    pub fn is_device_lost(&self) -> bool {
+        matches!(self, RenderError::Vulkan(VulkanError::DeviceLost))
+    }
+
+    /// Returns true if the swapchain no longer matches the surface and must be recreated
+    pub fn is_out_of_date(&self) -> bool {
+        matches!(self, RenderError::Vulkan(VulkanError::OutOfDate))
+    }
+}
+
+impl From<Validated<VulkanError>> for RenderError {
+    fn from(err: Validated<VulkanError>) -> Self {
+        match err {
+            Validated::Error(err) => RenderError::Vulkan(err),
+            Validated::ValidationError(err) => RenderError::Validation(err),
+        }
+    }
+}
+
+impl From<Validated<AllocateBufferError>> for RenderError {
+    fn from(err: Validated<AllocateBufferError>) -> Self {
+        match err {
+            Validated::Error(err) => RenderError::BufferAllocation(err),
+            Validated::ValidationError(err) => RenderError::Validation(err),
+        }
+    }
+}
@@ -1,19 +1,28 @@
-use crate::RenderPassTrait;
+use crate::{RenderError, RenderPassTrait};
 use bevy_ecs::prelude::*;
+use bevy_math::Mat4;
 use mo_ecs::resource::GlobalSamplers;
 use mo_ecs::{
-    component::Transform,
-    model::{Model, StaticVertex, DEFAULT_TEXTURE_MAP},
+    asset::{AssetId, AssetServer, Handle},
+    component::{GlobalTransform, SceneNode},
+    model::{
+        ETextureSlot, GltfMaterialCPU, MaterialType, MeshPrimitive, Model, ModelInstance,
+        SkinnedVertices, StaticVertex, DEFAULT_TEXTURE_MAP,
+    },
     resource::{Camera, DefaultTextures},
 };
 use mo_vk::{Texture, TextureCreateInfo, VulkanoWindowRenderer, VULKAN};
-use std::{cell::RefCell, sync::Arc};
+use std::{
+    cell::RefCell,
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+};
 use vulkano::buffer::allocator::{SubbufferAllocator, SubbufferAllocatorCreateInfo};
 use vulkano::pipeline::graphics::color_blend::ColorComponents;
 use vulkano::pipeline::graphics::depth_stencil::CompareOp;
 use vulkano::pipeline::graphics::rasterization::CullMode;
 use vulkano::{
-    buffer::{Buffer, BufferCreateInfo, BufferUsage},
+    buffer::{Buffer, BufferCreateInfo, BufferUsage, Subbuffer},
     command_buffer::{AutoCommandBufferBuilder, PrimaryAutoCommandBuffer, RenderPassBeginInfo},
     descriptor_set::{
         allocator::StandardDescriptorSetAllocator, layout::{
@@ -51,9 +60,13 @@ pub struct GBufferTextures {
     pub position: Arc<Texture>,
     pub normal: Arc<Texture>,
     pub emissive: Arc<Texture>,
-    /// Occlusion, Roughness, Metallic, Material ID
+    /// Occlusion, Roughness, Metallic, Material ID ([`MaterialType`] / 255)
     pub pbr: Arc<Texture>,
     pub velocity: Arc<Texture>,
+    /// Packed clearcoat, sheen and specular layers, see `include/gbuffer.glsl`
+    pub material: Arc<Texture>,
+    /// Packed anisotropy and iridescence layers, see `include/gbuffer.glsl`
+    pub material_ext: Arc<Texture>,
     pub depth: Arc<Texture>,
 }
 
@@ -109,6 +122,22 @@ impl GBufferTextures {
         };
         let velocity = Arc::new(Texture::new(velocity_info));
 
+        let material_info = TextureCreateInfo {
+            format: vulkano::format::Format::R32G32B32A32_UINT,
+            extent: [size.0, size.1, 1],
+            usage: ImageUsage::COLOR_ATTACHMENT | ImageUsage::SAMPLED,
+            ..Default::default()
+        };
+        let material = Arc::new(Texture::new(material_info));
+
+        let material_ext_info = TextureCreateInfo {
+            format: vulkano::format::Format::R32G32_UINT,
+            extent: [size.0, size.1, 1],
+            usage: ImageUsage::COLOR_ATTACHMENT | ImageUsage::SAMPLED,
+            ..Default::default()
+        };
+        let material_ext = Arc::new(Texture::new(material_ext_info));
+
         let depth_info = TextureCreateInfo {
             format: vulkano::format::Format::D32_SFLOAT,
             extent: [size.0, size.1, 1],
@@ -124,6 +153,8 @@ impl GBufferTextures {
             emissive,
             pbr,
             velocity,
+            material,
+            material_ext,
             depth,
         }
     }
@@ -137,10 +168,26 @@ pub struct GBufferPass {
     texture_descriptor_set: Arc<DescriptorSet>,
     material_descriptor_set: Arc<DescriptorSet>,
     uniform_buffer: SubbufferAllocator,
+    descriptor_set_alloc: Arc<StandardDescriptorSetAllocator>,
+    sampler: Arc<Sampler>,
+    /// Index of the first material of each registered model, its meshes follow in order
+    material_offsets: HashMap<AssetId, u32>,
+    /// Drawn in place of models which are still loading
+    placeholder: MeshPrimitive,
 
     viewport: Viewport,
 }
 
+/// Material of the placeholder, textured with the [`DefaultTextures`] grid
+const PLACEHOLDER_MATERIAL_INDEX: u32 = 0;
+
+/// Bindless textures and GPU materials of every model in the world
+pub(crate) struct BindlessResources {
+    pub textures: Vec<(Arc<ImageView>, Arc<Sampler>)>,
+    pub gpu_materials: Vec<gbuffer_fs::GltfMaterialGPU>,
+    pub material_offsets: HashMap<AssetId, u32>,
+}
+
 impl GBufferPass {
     pub fn new(
         world: &RefCell<World>,
@@ -188,6 +235,18 @@ impl GBufferPass {
                     load_op: Clear,
                     store_op: Store,
                 },
+                material: {
+                    format: gbuffer_textures.material.image_view.format(),
+                    samples: 1,
+                    load_op: Clear,
+                    store_op: Store,
+                },
+                material_ext: {
+                    format: gbuffer_textures.material_ext.image_view.format(),
+                    samples: 1,
+                    load_op: Clear,
+                    store_op: Store,
+                },
                 depth: {
                     format: gbuffer_textures.depth.image_view.format(),
                     samples: 1,
@@ -196,183 +255,19 @@ impl GBufferPass {
                 }
             },
             pass: {
-                color: [base_color, position, normal, emissive, pbr, velocity],
+                color: [
+                    base_color, position, normal, emissive, pbr, velocity, material, material_ext
+                ],
                 depth_stencil: {depth},
             },
         )
         .unwrap();
 
-        let mut default_diffuse_map_index = 0u32;
-        let mut default_normal_map_index = 0u32;
-        let mut default_occlusion_map_index = 0u32;
-        let mut default_metallic_roughness_map_index = 0u32;
-        let mut default_black_map_index = 0u32;
-        let mut next_bindless_image_index = 0u32;
-        let mut textures: Vec<(Arc<ImageView>, Arc<Sampler>)> = Vec::new();
-
-        let mut gpu_materials: Vec<gbuffer_fs::GltfMaterialGPU> = Vec::new();
-
         let sampler = world.borrow().resource::<GlobalSamplers>().clamp.clone();
 
-        // Add default textures to the bindless descriptor set and update the indices for them.
-        add_default_textures(
-            &world.borrow(),
-            &mut default_diffuse_map_index,
-            &mut default_normal_map_index,
-            &mut default_occlusion_map_index,
-            &mut default_metallic_roughness_map_index,
-            &mut default_black_map_index,
-            &mut textures,
-            &sampler,
-            &mut next_bindless_image_index,
-        );
-
-        let mut query = world.borrow_mut().query::<(&Transform, &mut Model)>();
-        for (&_, model) in query.iter_mut(&mut world.borrow_mut()) {
-            add_model(
-                &mut gpu_materials,
-                model.into_inner(),
-                default_diffuse_map_index,
-                default_normal_map_index,
-                default_occlusion_map_index,
-                default_metallic_roughness_map_index,
-                default_black_map_index,
-                &mut textures,
-                &sampler,
-                &mut next_bindless_image_index,
-            );
-        }
+        let resources = collect_bindless_resources(&mut world.borrow_mut(), &sampler);
 
-        let pipeline = {
-            let vs = gbuffer_vs::load(VULKAN.device().clone())
-                .unwrap()
-                .entry_point("main")
-                .unwrap();
-            let fs = gbuffer_fs::load(VULKAN.device().clone())
-                .unwrap()
-                .entry_point("main")
-                .unwrap();
-
-            let vertex_input_state = [StaticVertex::per_vertex()].definition(&vs).unwrap();
-            let stages = [
-                PipelineShaderStageCreateInfo::new(vs.clone()),
-                PipelineShaderStageCreateInfo::new(fs.clone()),
-            ];
-
-            let pipeline_layout = PipelineLayout::new(
-                VULKAN.device().clone(),
-                PipelineLayoutCreateInfo {
-                    set_layouts: vec![
-                        // We separate the bindless resource arrays to different sets.
-                        // To avoid having more descriptors than max descriptor in one set.
-                        // Texture descriptor set. Number 0.
-                        DescriptorSetLayout::new(
-                            VULKAN.device().clone(),
-                            DescriptorSetLayoutCreateInfo {
-                                bindings: [(
-                                    0,
-                                    DescriptorSetLayoutBinding {
-                                        stages: ShaderStages::FRAGMENT,
-                                        descriptor_count: textures.len() as u32,
-                                        ..DescriptorSetLayoutBinding::descriptor_type(
-                                            DescriptorType::CombinedImageSampler,
-                                        )
-                                    },
-                                )]
-                                .into_iter()
-                                .collect(),
-                                ..Default::default()
-                            },
-                        )
-                        .unwrap(),
-                        // Material Descriptor set.  Number 1.
-                        DescriptorSetLayout::new(
-                            VULKAN.device().clone(),
-                            DescriptorSetLayoutCreateInfo {
-                                bindings: [(
-                                    0,
-                                    DescriptorSetLayoutBinding {
-                                        stages: ShaderStages::FRAGMENT,
-                                        descriptor_count: gpu_materials.len() as u32,
-                                        ..DescriptorSetLayoutBinding::descriptor_type(
-                                            DescriptorType::StorageBuffer,
-                                        )
-                                    },
-                                )]
-                                .into_iter()
-                                .collect(),
-                                ..Default::default()
-                            },
-                        )
-                        .unwrap(),
-                        // Uniform Descriptor set.  Number 2.
-                        DescriptorSetLayout::new(
-                            VULKAN.device().clone(),
-                            DescriptorSetLayoutCreateInfo {
-                                bindings: [(
-                                    0,
-                                    DescriptorSetLayoutBinding {
-                                        stages: ShaderStages::VERTEX,
-                                        descriptor_count: 1,
-                                        ..DescriptorSetLayoutBinding::descriptor_type(
-                                            DescriptorType::UniformBuffer,
-                                        )
-                                    },
-                                )]
-                                .into_iter()
-                                .collect(),
-                                ..Default::default()
-                            },
-                        )
-                        .unwrap(),
-                    ],
-                    push_constant_ranges: vec![PushConstantRange {
-                        stages: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
-                        offset: 0,
-                        size: size_of::<gbuffer_vs::PushConsts>() as u32,
-                    }],
-                    ..Default::default()
-                },
-            )
-            .unwrap();
-
-            let subpass = Subpass::from(render_pass.clone(), 0).unwrap();
-
-            GraphicsPipeline::new(
-                VULKAN.device().clone(),
-                None,
-                GraphicsPipelineCreateInfo {
-                    stages: stages.into_iter().collect(),
-                    vertex_input_state: Some(vertex_input_state),
-                    input_assembly_state: Some(InputAssemblyState::default()),
-                    viewport_state: Some(ViewportState::default()),
-                    rasterization_state: Some(RasterizationState {
-                        cull_mode: CullMode::Back,
-                        ..Default::default()
-                    }),
-                    multisample_state: Some(MultisampleState::default()),
-                    color_blend_state: Some(ColorBlendState::with_attachment_states(
-                        subpass.num_color_attachments(),
-                        ColorBlendAttachmentState {
-                            color_write_mask: ColorComponents::all(),
-                            ..Default::default()
-                        },
-                    )),
-                    depth_stencil_state: Some(DepthStencilState {
-                        depth: Some(DepthState {
-                            compare_op: CompareOp::LessOrEqual,
-                            write_enable: true,
-                            ..Default::default()
-                        }),
-                        ..Default::default()
-                    }),
-                    dynamic_state: [DynamicState::Viewport].into_iter().collect(),
-                    subpass: Some(subpass.into()),
-                    ..GraphicsPipelineCreateInfo::layout(pipeline_layout)
-                },
-            )
-            .unwrap()
-        };
+        let pipeline = create_pipeline(&render_pass, resources.textures.len() as u32);
 
         let frame_buffer = recreate_framebuffer(&gbuffer_textures, &render_pass);
 
@@ -382,47 +277,16 @@ impl GBufferPass {
             depth_range: 0.0..=1.0,
         };
 
-        let memory_allocator = VULKAN.memory_allocator().clone();
-
-        let gpu_materials_buffer = Buffer::from_iter(
-            memory_allocator.clone(),
-            BufferCreateInfo {
-                usage: BufferUsage::STORAGE_BUFFER,
-                ..Default::default()
-            },
-            AllocationCreateInfo {
-                memory_type_filter: MemoryTypeFilter::PREFER_DEVICE
-                    | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
-                ..Default::default()
-            },
-            gpu_materials,
-        )
-        .unwrap();
-
-        let texture_descriptor_writes =
-            WriteDescriptorSet::image_view_sampler_array(0, 0, textures);
-        let gpu_materials_writes = WriteDescriptorSet::buffer(0, gpu_materials_buffer);
-
-        let layout = &pipeline.layout().set_layouts()[0];
-        let texture_descriptor_set = DescriptorSet::new(
-            descriptor_set_alloc.clone(),
-            layout.clone(),
-            [texture_descriptor_writes],
-            [],
-        )
-        .unwrap();
-
-        let layout = &pipeline.layout().set_layouts()[1];
-        let material_descriptor_set = DescriptorSet::new(
-            descriptor_set_alloc.clone(),
-            layout.clone(),
-            [gpu_materials_writes],
-            [],
+        let (texture_descriptor_set, material_descriptor_set) = create_bindless_descriptor_sets(
+            &pipeline,
+            &descriptor_set_alloc,
+            resources.textures,
+            resources.gpu_materials,
         )
         .unwrap();
 
         let uniform_buffer_allocator = SubbufferAllocator::new(
-            memory_allocator.clone(),
+            VULKAN.memory_allocator().clone(),
             SubbufferAllocatorCreateInfo {
                 buffer_usage: BufferUsage::UNIFORM_BUFFER,
                 memory_type_filter: MemoryTypeFilter::PREFER_DEVICE
@@ -441,6 +305,10 @@ impl GBufferPass {
             texture_descriptor_set,
             material_descriptor_set,
             uniform_buffer: uniform_buffer_allocator,
+            descriptor_set_alloc,
+            sampler,
+            material_offsets: resources.material_offsets,
+            placeholder: MeshPrimitive::cube(0.5),
 
             viewport,
         }
@@ -448,13 +316,47 @@ impl GBufferPass {
 }
 
 impl RenderPassTrait for GBufferPass {
+    fn prepare(&mut self, world: &mut World) -> Result<(), RenderError> {
+        let models = loaded_models(world);
+        let unchanged = models.len() == self.material_offsets.len()
+            && models
+                .iter()
+                .all(|handle| self.material_offsets.contains_key(&handle.id()));
+        if unchanged {
+            return Ok(());
+        }
+
+        // The size of the bindless texture array is part of the pipeline layout, so models
+        // added or removed at runtime need a new pipeline.
+        let resources = collect_bindless_resources(world, &self.sampler);
+        let pipeline = create_pipeline(&self.gbuffer_renderpass, resources.textures.len() as u32);
+        let (texture_descriptor_set, material_descriptor_set) = create_bindless_descriptor_sets(
+            &pipeline,
+            &self.descriptor_set_alloc,
+            resources.textures,
+            resources.gpu_materials,
+        )?;
+
+        self.gbuffer_pipeline = pipeline;
+        self.texture_descriptor_set = texture_descriptor_set;
+        self.material_descriptor_set = material_descriptor_set;
+        self.material_offsets = resources.material_offsets;
+
+        tracing::info!(
+            "Render - GBuffer Pass registered materials of {} models.",
+            self.material_offsets.len()
+        );
+
+        Ok(())
+    }
+
     fn render(
         &mut self,
         _image_idx: u32,
         world: &World,
         descriptor_set_allocator: Arc<StandardDescriptorSetAllocator>,
         builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
-    ) {
+    ) -> Result<(), RenderError> {
         let camera = world.resource::<Camera>();
 
         let uniform_buffer_subbuffer = {
@@ -464,8 +366,8 @@ impl RenderPassTrait for GBufferPass {
                 prev_view: camera.prev_view().to_cols_array_2d(),
             };
 
-            let subbuffer = self.uniform_buffer.allocate_sized().unwrap();
-            *subbuffer.write().unwrap() = uniform_data;
+            let subbuffer = self.uniform_buffer.allocate_sized()?;
+            *subbuffer.write()? = uniform_data;
 
             subbuffer
         };
@@ -476,8 +378,7 @@ impl RenderPassTrait for GBufferPass {
             layout.clone(),
             [WriteDescriptorSet::buffer(0, uniform_buffer_subbuffer)],
             [],
-        )
-        .unwrap();
+        )?;
 
         builder
             .begin_render_pass(
@@ -489,18 +390,17 @@ impl RenderPassTrait for GBufferPass {
                         Some(ClearValue::Float([0.0, 0.0, 0.0, 1.0])),
                         Some(ClearValue::Float([1.0, 0.0, 0.0, 1.0])),
                         Some(ClearValue::Float([0.0, 0.0, 0.0, 1.0])),
+                        Some(ClearValue::Uint([0; 4])),
+                        Some(ClearValue::Uint([0; 4])),
                         Some(ClearValue::Depth(1.0)),
                     ],
                     render_pass: self.gbuffer_renderpass.clone(),
                     ..RenderPassBeginInfo::framebuffer(self.gbuffer_framebuffer.clone())
                 },
                 Default::default(),
-            )
-            .unwrap()
-            .bind_pipeline_graphics(self.gbuffer_pipeline.clone())
-            .unwrap()
-            .set_viewport(0, [self.viewport.clone()].into_iter().collect())
-            .unwrap()
+            )?
+            .bind_pipeline_graphics(self.gbuffer_pipeline.clone())?
+            .set_viewport(0, [self.viewport.clone()].into_iter().collect())?
             .bind_descriptor_sets(
                 PipelineBindPoint::Graphics,
                 self.gbuffer_pipeline.layout().clone(),
@@ -510,44 +410,52 @@ impl RenderPassTrait for GBufferPass {
                     self.material_descriptor_set.clone(),
                     descriptor_set,
                 ],
-            )
-            .unwrap();
+            )?;
+
+        let asset_server = world.resource::<AssetServer>();
+        let skinned_vertices = world.get_resource::<SkinnedVertices>();
 
         for entity in world.iter_entities().into_iter() {
-            if entity.get::<Model>().is_some() {
-                let transform = entity.get::<Transform>().unwrap();
-                let model = entity.get::<Model>().unwrap();
-
-                for mesh in &model.meshes {
-                    let world_matrix = transform.model_matrix() * mesh.world;
-                    let normal_matrix = world_matrix.clone().inverse().transpose();
-                    builder
-                        .push_constants(
-                            self.gbuffer_pipeline.layout().clone(),
-                            0,
-                            gbuffer_vs::PushConsts {
-                                world: world_matrix.to_cols_array_2d(),
-                                mat_index: mesh.gpu_mat_index.into(),
-                                normal_matrix: normal_matrix.to_cols_array_2d(),
-                                pad: [0, 0, 0],
-                            },
-                        )
-                        .unwrap()
-                        .bind_vertex_buffers(0, mesh.primitive.vertex_buffer.clone())
-                        .unwrap()
-                        .bind_index_buffer(mesh.primitive.index_buffer.clone())
-                        .unwrap();
-
-                    unsafe {
-                        builder
-                            .draw_indexed(mesh.primitive.index_buffer.len() as u32, 1, 0, 0, 0)
-                            .unwrap();
-                    }
+            if ModelInstance::is_loading(entity, asset_server) {
+                if let Some(global_transform) = entity.get::<GlobalTransform>() {
+                    self.draw_mesh(
+                        builder,
+                        &self.placeholder.vertex_buffer,
+                        &self.placeholder.index_buffer,
+                        global_transform.matrix(),
+                        PLACEHOLDER_MATERIAL_INDEX,
+                    )?;
                 }
+                continue;
+            }
+
+            // Skip models which failed to load or whose materials are not registered.
+            let Some(instance) = ModelInstance::from_entity(entity, asset_server, skinned_vertices)
+            else {
+                continue;
+            };
+            let Some(&material_offset) = self.material_offsets.get(&instance.handle.id()) else {
+                continue;
+            };
+
+            for mesh in &instance.meshes {
+                // Drawn by the transmission pass on top of the lit scene.
+                if instance.model.material(mesh.material).is_transmissive() {
+                    continue;
+                }
+                self.draw_mesh(
+                    builder,
+                    mesh.vertex_buffer,
+                    &instance.model.meshes[mesh.index].primitive.index_buffer,
+                    mesh.world,
+                    material_offset + mesh.material as u32,
+                )?;
             }
         }
 
-        builder.end_render_pass(Default::default()).unwrap();
+        builder.end_render_pass(Default::default())?;
+
+        Ok(())
     }
 
     fn on_swapchain_recreate(
@@ -566,6 +474,354 @@ impl RenderPassTrait for GBufferPass {
     }
 }
 
+impl GBufferPass {
+    fn draw_mesh(
+        &self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        vertex_buffer: &Subbuffer<[StaticVertex]>,
+        index_buffer: &Subbuffer<[u32]>,
+        world_matrix: Mat4,
+        mat_index: u32,
+    ) -> Result<(), RenderError> {
+        let normal_matrix = world_matrix.inverse().transpose();
+        builder
+            .push_constants(
+                self.gbuffer_pipeline.layout().clone(),
+                0,
+                gbuffer_vs::PushConsts {
+                    world: world_matrix.to_cols_array_2d(),
+                    mat_index,
+                    normal_matrix: normal_matrix.to_cols_array_2d(),
+                    pad: [0, 0, 0],
+                },
+            )?
+            .bind_vertex_buffers(0, vertex_buffer.clone())?
+            .bind_index_buffer(index_buffer.clone())?;
+
+        unsafe {
+            builder.draw_indexed(index_buffer.len() as u32, 1, 0, 0, 0)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Loaded models used by entities and scene nodes, each model once
+pub(crate) fn loaded_models(world: &mut World) -> Vec<Handle<Model>> {
+    let mut models = world.query_filtered::<&Handle<Model>, With<GlobalTransform>>();
+    let mut nodes = world.query_filtered::<&SceneNode, With<GlobalTransform>>();
+    let asset_server = world.resource::<AssetServer>();
+
+    let mut ids = BTreeSet::new();
+    models
+        .iter(world)
+        .chain(nodes.iter(world).map(|node| &node.model))
+        .filter(|handle| asset_server.get(*handle).is_some() && ids.insert(handle.id()))
+        .cloned()
+        .collect()
+}
+
+/// Registers the default textures and the textures and materials of every model used by the
+/// world.
+pub(crate) fn collect_bindless_resources(
+    world: &mut World,
+    sampler: &Arc<Sampler>,
+) -> BindlessResources {
+    let mut default_diffuse_map_index = 0u32;
+    let mut default_normal_map_index = 0u32;
+    let mut default_occlusion_map_index = 0u32;
+    let mut default_metallic_roughness_map_index = 0u32;
+    let mut default_black_map_index = 0u32;
+    let mut next_bindless_image_index = 0u32;
+    let mut textures: Vec<(Arc<ImageView>, Arc<Sampler>)> = Vec::new();
+
+    let mut gpu_materials: Vec<gbuffer_fs::GltfMaterialGPU> = Vec::new();
+    let mut material_offsets = HashMap::new();
+
+    // Add default textures to the bindless descriptor set and update the indices for them.
+    add_default_textures(
+        world,
+        &mut default_diffuse_map_index,
+        &mut default_normal_map_index,
+        &mut default_occlusion_map_index,
+        &mut default_metallic_roughness_map_index,
+        &mut default_black_map_index,
+        &mut textures,
+        sampler,
+        &mut next_bindless_image_index,
+    );
+
+    // The placeholder material comes first, so the storage buffer is never empty either.
+    let grid_map_index = add_bindless_texture(
+        &mut textures,
+        &world.resource::<DefaultTextures>().grid,
+        sampler,
+        &mut next_bindless_image_index,
+    );
+    add_material(
+        &mut gpu_materials,
+        gbuffer_fs::GltfMaterialGPU {
+            base_color_map: grid_map_index,
+            normal_map: default_normal_map_index,
+            metallic_roughness_map: default_metallic_roughness_map_index,
+            occlusion_map: default_occlusion_map_index,
+            emissive_map: default_black_map_index,
+            base_color_uv_set: 0,
+            normal_uv_set: 0,
+            metallic_roughness_uv_set: 0,
+            occlusion_uv_set: 0,
+            emissive_uv_set: 0,
+            material_type: MaterialType::MetallicRoughness as u32,
+            padding: 0,
+            base_color_factor: [1.0; 4],
+            emissive_factor: [0.0, 0.0, 0.0, 1.0],
+            metallic_factor: 0.0,
+            roughness_factor: 1.0,
+            alpha_mode: 0,
+            alpha_cutoff: 0.0,
+            raytrace_properties: [0.0, 0.0, 0.0, 0.0],
+            clearcoat_map: default_diffuse_map_index,
+            clearcoat_roughness_map: default_diffuse_map_index,
+            clearcoat_normal_map: default_normal_map_index,
+            sheen_color_map: default_diffuse_map_index,
+            sheen_roughness_map: default_diffuse_map_index,
+            clearcoat_uv_set: 0,
+            clearcoat_roughness_uv_set: 0,
+            clearcoat_normal_uv_set: 0,
+            sheen_color_uv_set: 0,
+            sheen_roughness_uv_set: 0,
+            padding2: [0.0; 2],
+            clearcoat_factors: [0.0; 4],
+            sheen_factors: [0.0; 4],
+            transmission_map: default_diffuse_map_index,
+            thickness_map: default_diffuse_map_index,
+            transmission_uv_set: 0,
+            thickness_uv_set: 0,
+            transmission_factors: [0.0, 0.0, 1.5, 0.0],
+            attenuation: [1.0; 4],
+            specular_map: default_diffuse_map_index,
+            specular_color_map: default_diffuse_map_index,
+            specular_uv_set: 0,
+            specular_color_uv_set: 0,
+            specular_factors: [1.0; 4],
+            iridescence_map: default_diffuse_map_index,
+            iridescence_thickness_map: default_diffuse_map_index,
+            anisotropy_map: default_diffuse_map_index,
+            iridescence_uv_set: 0,
+            iridescence_thickness_uv_set: 0,
+            anisotropy_uv_set: 0,
+            padding3: [0.0; 2],
+            iridescence_factors: [0.0, 1.3, 100.0, 400.0],
+            anisotropy_factors: [0.0; 4],
+            specular_glossiness_factors: [1.0; 4],
+            uv_transforms: uv_transforms(&GltfMaterialCPU::default()),
+        },
+    );
+
+    let models = loaded_models(world);
+    let asset_server = world.resource::<AssetServer>();
+    for handle in models {
+        let Some(model) = asset_server.get(&handle) else {
+            continue;
+        };
+        let material_offset = add_model(
+            &mut gpu_materials,
+            model,
+            default_diffuse_map_index,
+            default_normal_map_index,
+            default_occlusion_map_index,
+            default_metallic_roughness_map_index,
+            default_black_map_index,
+            &mut textures,
+            sampler,
+            &mut next_bindless_image_index,
+        );
+        material_offsets.insert(handle.id(), material_offset);
+    }
+
+    BindlessResources {
+        textures,
+        gpu_materials,
+        material_offsets,
+    }
+}
+
+fn create_pipeline(render_pass: &Arc<RenderPass>, texture_count: u32) -> Arc<GraphicsPipeline> {
+    let vs = gbuffer_vs::load(VULKAN.device().clone())
+        .unwrap()
+        .entry_point("main")
+        .unwrap();
+    let fs = gbuffer_fs::load(VULKAN.device().clone())
+        .unwrap()
+        .entry_point("main")
+        .unwrap();
+
+    let vertex_input_state = [StaticVertex::per_vertex()].definition(&vs).unwrap();
+    let stages = [
+        PipelineShaderStageCreateInfo::new(vs.clone()),
+        PipelineShaderStageCreateInfo::new(fs.clone()),
+    ];
+
+    let pipeline_layout = PipelineLayout::new(
+        VULKAN.device().clone(),
+        PipelineLayoutCreateInfo {
+            set_layouts: vec![
+                // We separate the bindless resource arrays to different sets.
+                // To avoid having more descriptors than max descriptor in one set.
+                // Texture descriptor set. Number 0.
+                DescriptorSetLayout::new(
+                    VULKAN.device().clone(),
+                    DescriptorSetLayoutCreateInfo {
+                        bindings: [(
+                            0,
+                            DescriptorSetLayoutBinding {
+                                stages: ShaderStages::FRAGMENT,
+                                descriptor_count: texture_count,
+                                ..DescriptorSetLayoutBinding::descriptor_type(
+                                    DescriptorType::CombinedImageSampler,
+                                )
+                            },
+                        )]
+                        .into_iter()
+                        .collect(),
+                        ..Default::default()
+                    },
+                )
+                .unwrap(),
+                // Material Descriptor set.  Number 1.
+                // A single storage buffer holding the array of all materials.
+                DescriptorSetLayout::new(
+                    VULKAN.device().clone(),
+                    DescriptorSetLayoutCreateInfo {
+                        bindings: [(
+                            0,
+                            DescriptorSetLayoutBinding {
+                                stages: ShaderStages::FRAGMENT,
+                                descriptor_count: 1,
+                                ..DescriptorSetLayoutBinding::descriptor_type(
+                                    DescriptorType::StorageBuffer,
+                                )
+                            },
+                        )]
+                        .into_iter()
+                        .collect(),
+                        ..Default::default()
+                    },
+                )
+                .unwrap(),
+                // Uniform Descriptor set.  Number 2.
+                DescriptorSetLayout::new(
+                    VULKAN.device().clone(),
+                    DescriptorSetLayoutCreateInfo {
+                        bindings: [(
+                            0,
+                            DescriptorSetLayoutBinding {
+                                stages: ShaderStages::VERTEX,
+                                descriptor_count: 1,
+                                ..DescriptorSetLayoutBinding::descriptor_type(
+                                    DescriptorType::UniformBuffer,
+                                )
+                            },
+                        )]
+                        .into_iter()
+                        .collect(),
+                        ..Default::default()
+                    },
+                )
+                .unwrap(),
+            ],
+            push_constant_ranges: vec![PushConstantRange {
+                stages: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+                offset: 0,
+                size: size_of::<gbuffer_vs::PushConsts>() as u32,
+            }],
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let subpass = Subpass::from(render_pass.clone(), 0).unwrap();
+
+    GraphicsPipeline::new(
+        VULKAN.device().clone(),
+        None,
+        GraphicsPipelineCreateInfo {
+            stages: stages.into_iter().collect(),
+            vertex_input_state: Some(vertex_input_state),
+            input_assembly_state: Some(InputAssemblyState::default()),
+            viewport_state: Some(ViewportState::default()),
+            rasterization_state: Some(RasterizationState {
+                cull_mode: CullMode::Back,
+                ..Default::default()
+            }),
+            multisample_state: Some(MultisampleState::default()),
+            color_blend_state: Some(ColorBlendState::with_attachment_states(
+                subpass.num_color_attachments(),
+                ColorBlendAttachmentState {
+                    color_write_mask: ColorComponents::all(),
+                    ..Default::default()
+                },
+            )),
+            depth_stencil_state: Some(DepthStencilState {
+                depth: Some(DepthState {
+                    compare_op: CompareOp::LessOrEqual,
+                    write_enable: true,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            dynamic_state: [DynamicState::Viewport].into_iter().collect(),
+            subpass: Some(subpass.into()),
+            ..GraphicsPipelineCreateInfo::layout(pipeline_layout)
+        },
+    )
+    .unwrap()
+}
+
+/// Creates the texture (set 0) and material (set 1) descriptor sets, shared by every pipeline
+/// reading the bindless materials
+pub(crate) fn create_bindless_descriptor_sets(
+    pipeline: &Arc<GraphicsPipeline>,
+    descriptor_set_alloc: &Arc<StandardDescriptorSetAllocator>,
+    textures: Vec<(Arc<ImageView>, Arc<Sampler>)>,
+    gpu_materials: Vec<gbuffer_fs::GltfMaterialGPU>,
+) -> Result<(Arc<DescriptorSet>, Arc<DescriptorSet>), RenderError> {
+    let gpu_materials_buffer = Buffer::from_iter(
+        VULKAN.memory_allocator().clone(),
+        BufferCreateInfo {
+            usage: BufferUsage::STORAGE_BUFFER,
+            ..Default::default()
+        },
+        AllocationCreateInfo {
+            memory_type_filter: MemoryTypeFilter::PREFER_DEVICE
+                | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
+            ..Default::default()
+        },
+        gpu_materials,
+    )?;
+
+    let texture_descriptor_writes = WriteDescriptorSet::image_view_sampler_array(0, 0, textures);
+    let gpu_materials_writes = WriteDescriptorSet::buffer(0, gpu_materials_buffer);
+
+    let layout = &pipeline.layout().set_layouts()[0];
+    let texture_descriptor_set = DescriptorSet::new(
+        descriptor_set_alloc.clone(),
+        layout.clone(),
+        [texture_descriptor_writes],
+        [],
+    )?;
+
+    let layout = &pipeline.layout().set_layouts()[1];
+    let material_descriptor_set = DescriptorSet::new(
+        descriptor_set_alloc.clone(),
+        layout.clone(),
+        [gpu_materials_writes],
+        [],
+    )?;
+
+    Ok((texture_descriptor_set, material_descriptor_set))
+}
+
 fn recreate_framebuffer(
     gbuffer_textures: &GBufferTextures,
     render_pass: &Arc<RenderPass>,
@@ -581,6 +837,8 @@ fn recreate_framebuffer(
                 gbuffer_textures.emissive.image_view.clone(),
                 gbuffer_textures.pbr.image_view.clone(),
                 gbuffer_textures.velocity.image_view.clone(),
+                gbuffer_textures.material.image_view.clone(),
+                gbuffer_textures.material_ext.image_view.clone(),
                 gbuffer_textures.depth.image_view.clone(),
             ],
             ..Default::default()
@@ -651,9 +909,10 @@ fn add_default_textures(
     );
 }
 
+/// Returns the index of the first material, the materials of the meshes are added in order.
 fn add_model(
     gpu_materials: &mut Vec<gbuffer_fs::GltfMaterialGPU>,
-    model: &mut Model,
+    model: &Model,
     default_diffuse_map_index: u32,
     default_normal_map_index: u32,
     default_occlusion_map_index: u32,
@@ -662,62 +921,78 @@ fn add_model(
     textures: &mut Vec<(Arc<ImageView>, Arc<Sampler>)>,
     sampler: &Arc<Sampler>,
     next_bindless_image_index: &mut u32,
-) {
+) -> u32 {
     // Add the images from the new model to the bindless descriptor set and
     // also update the mappings for each primitive to be indexes corresponding
     // to the ordering in the bindless descriptor set texture array.
-    for mesh in &mut model.meshes {
-        let diffuse_bindless_index = match mesh.material.base_color_texture {
-            DEFAULT_TEXTURE_MAP => default_diffuse_map_index,
-            _ => add_bindless_texture(
-                textures,
-                &model.textures[mesh.material.base_color_texture as usize],
-                sampler,
-                next_bindless_image_index,
-            ),
-        };
-
-        let normal_bindless_index = match mesh.material.normal_texture {
-            DEFAULT_TEXTURE_MAP => default_normal_map_index,
-            _ => add_bindless_texture(
-                textures,
-                &model.textures[mesh.material.normal_texture as usize],
-                sampler,
-                next_bindless_image_index,
-            ),
-        };
+    let first_material_index = gpu_materials.len() as u32;
 
-        let metallic_roughness_bindless_index = match mesh.material.surface_properties_texture {
-            DEFAULT_TEXTURE_MAP => default_metallic_roughness_map_index,
+    // Variant materials follow the materials of the meshes, see `Model::material`.
+    let materials = model.meshes.iter().map(|mesh| &mesh.material);
+    for material in materials.chain(&model.variant_materials) {
+        let mut bindless_index = |texture: u32, default_index: u32| match texture {
+            DEFAULT_TEXTURE_MAP => default_index,
             _ => add_bindless_texture(
                 textures,
-                &model.textures[mesh.material.surface_properties_texture as usize],
+                &model.textures[texture as usize],
                 sampler,
                 next_bindless_image_index,
             ),
         };
 
-        let occlusion_bindless_index = match mesh.material.occlusion_texture {
-            DEFAULT_TEXTURE_MAP => default_occlusion_map_index,
-            _ => add_bindless_texture(
-                textures,
-                &model.textures[mesh.material.occlusion_texture as usize],
-                sampler,
-                next_bindless_image_index,
-            ),
-        };
+        let diffuse_bindless_index =
+            bindless_index(material.base_color_texture, default_diffuse_map_index);
+        let normal_bindless_index =
+            bindless_index(material.normal_texture, default_normal_map_index);
+        // Without a specular-glossiness texture the factors alone set F0 and glossiness, the
+        // metallic-roughness default would tint them.
+        let default_surface_properties_map_index =
+            if matches!(material.material_type, MaterialType::SpecularGlossiness) {
+                default_diffuse_map_index
+            } else {
+                default_metallic_roughness_map_index
+            };
+        let metallic_roughness_bindless_index = bindless_index(
+            material.surface_properties_texture,
+            default_surface_properties_map_index,
+        );
+        let occlusion_bindless_index =
+            bindless_index(material.occlusion_texture, default_occlusion_map_index);
+        // Without an emissive texture the emissive factor and strength alone set the emission.
+        let emissive_bindless_index =
+            bindless_index(material.emissive_texture, default_diffuse_map_index);
 
-        let emissive_bindless_index = match mesh.material.emissive_texture {
-            DEFAULT_TEXTURE_MAP => default_black_map_index,
-            _ => add_bindless_texture(
-                textures,
-                &model.textures[mesh.material.emissive_texture as usize],
-                sampler,
-                next_bindless_image_index,
-            ),
-        };
+        // The factors of the layers multiply the white default textures.
+        let clearcoat_bindless_index =
+            bindless_index(material.clearcoat_texture, default_diffuse_map_index);
+        let clearcoat_roughness_bindless_index = bindless_index(
+            material.clearcoat_roughness_texture,
+            default_diffuse_map_index,
+        );
+        let clearcoat_normal_bindless_index =
+            bindless_index(material.clearcoat_normal_texture, default_normal_map_index);
+        let sheen_color_bindless_index =
+            bindless_index(material.sheen_texture, default_diffuse_map_index);
+        let sheen_roughness_bindless_index =
+            bindless_index(material.sheen_roughness_texture, default_diffuse_map_index);
+        let transmission_bindless_index =
+            bindless_index(material.transmission_texture, default_diffuse_map_index);
+        let thickness_bindless_index =
+            bindless_index(material.thickness_texture, default_diffuse_map_index);
+        let specular_bindless_index =
+            bindless_index(material.specular_texture, default_diffuse_map_index);
+        let specular_color_bindless_index =
+            bindless_index(material.specular_color_texture, default_diffuse_map_index);
+        let iridescence_bindless_index =
+            bindless_index(material.iridescence_texture, default_diffuse_map_index);
+        let iridescence_thickness_bindless_index = bindless_index(
+            material.iridescence_thickness_texture,
+            default_diffuse_map_index,
+        );
+        let anisotropy_bindless_index =
+            bindless_index(material.anisotropy_texture, default_diffuse_map_index);
 
-        let material_index = add_material(
+        add_material(
             gpu_materials,
             gbuffer_fs::GltfMaterialGPU {
                 base_color_map: diffuse_bindless_index,
@@ -726,31 +1001,103 @@ fn add_model(
                 occlusion_map: occlusion_bindless_index,
                 emissive_map: emissive_bindless_index,
                 // UV sets
-                base_color_uv_set: mesh.material.base_color_uv,
-                normal_uv_set: mesh.material.normal_uv,
-                metallic_roughness_uv_set: mesh.material.surface_properties_uv,
-                occlusion_uv_set: mesh.material.occlusion_uv,
-                emissive_uv_set: mesh.material.emissive_uv,
-                padding: [0.0; 2],
+                base_color_uv_set: material.uv_set(ETextureSlot::BaseColor),
+                normal_uv_set: material.uv_set(ETextureSlot::Normal),
+                metallic_roughness_uv_set: material.uv_set(ETextureSlot::SurfaceProperties),
+                occlusion_uv_set: material.uv_set(ETextureSlot::Occlusion),
+                emissive_uv_set: material.uv_set(ETextureSlot::Emissive),
+                material_type: material.material_type as u32,
+                padding: 0,
                 // Factors
-                base_color_factor: mesh.material.base_color_factor.into(),
+                base_color_factor: material.base_color_factor.into(),
                 emissive_factor: [
-                    mesh.material.emissive_factor_alpha_cutoff.x,
-                    mesh.material.emissive_factor_alpha_cutoff.y,
-                    mesh.material.emissive_factor_alpha_cutoff.z,
-                    1.0,
+                    material.emissive_factor_alpha_cutoff.x,
+                    material.emissive_factor_alpha_cutoff.y,
+                    material.emissive_factor_alpha_cutoff.z,
+                    material.emissive_strength,
                 ],
-                metallic_factor: mesh.material.ormn.z,
-                roughness_factor: mesh.material.ormn.y,
+                metallic_factor: material.ormn.z,
+                roughness_factor: material.ormn.y,
                 // alpha
-                alpha_mode: mesh.material.alpha_mode as u32,
-                alpha_cutoff: mesh.material.emissive_factor_alpha_cutoff.w,
+                alpha_mode: material.alpha_mode as u32,
+                alpha_cutoff: material.emissive_factor_alpha_cutoff.w,
                 raytrace_properties: [0.0, 0.0, 0.0, 0.0],
+                // Clearcoat and sheen
+                clearcoat_map: clearcoat_bindless_index,
+                clearcoat_roughness_map: clearcoat_roughness_bindless_index,
+                clearcoat_normal_map: clearcoat_normal_bindless_index,
+                sheen_color_map: sheen_color_bindless_index,
+                sheen_roughness_map: sheen_roughness_bindless_index,
+                clearcoat_uv_set: material.uv_set(ETextureSlot::Clearcoat),
+                clearcoat_roughness_uv_set: material.uv_set(ETextureSlot::ClearcoatRoughness),
+                clearcoat_normal_uv_set: material.uv_set(ETextureSlot::ClearcoatNormal),
+                sheen_color_uv_set: material.uv_set(ETextureSlot::Sheen),
+                sheen_roughness_uv_set: material.uv_set(ETextureSlot::SheenRoughness),
+                padding2: [0.0; 2],
+                clearcoat_factors: [
+                    material.clearcoat_transmission_thickness.x,
+                    material.clearcoat_transmission_thickness.y,
+                    0.0,
+                    0.0,
+                ],
+                sheen_factors: material.sheen_factors.into(),
+                // Transmission and volume
+                transmission_map: transmission_bindless_index,
+                thickness_map: thickness_bindless_index,
+                transmission_uv_set: material.uv_set(ETextureSlot::Transmission),
+                thickness_uv_set: material.uv_set(ETextureSlot::Thickness),
+                transmission_factors: [
+                    material.clearcoat_transmission_thickness.z,
+                    material.clearcoat_transmission_thickness.w,
+                    material.ior,
+                    0.0,
+                ],
+                attenuation: material.attenuation.into(),
+                // Specular
+                specular_map: specular_bindless_index,
+                specular_color_map: specular_color_bindless_index,
+                specular_uv_set: material.uv_set(ETextureSlot::Specular),
+                specular_color_uv_set: material.uv_set(ETextureSlot::SpecularColor),
+                specular_factors: material.specular_factors.into(),
+                // Iridescence and anisotropy
+                iridescence_map: iridescence_bindless_index,
+                iridescence_thickness_map: iridescence_thickness_bindless_index,
+                anisotropy_map: anisotropy_bindless_index,
+                iridescence_uv_set: material.uv_set(ETextureSlot::Iridescence),
+                iridescence_thickness_uv_set: material.uv_set(ETextureSlot::IridescenceThickness),
+                anisotropy_uv_set: material.uv_set(ETextureSlot::Anisotropy),
+                padding3: [0.0; 2],
+                iridescence_factors: material.iridescence_factors.into(),
+                anisotropy_factors: [
+                    material.anisotropy_factors.x,
+                    material.anisotropy_factors.y,
+                    if material.anisotropy_texture == DEFAULT_TEXTURE_MAP {
+                        0.0
+                    } else {
+                        1.0
+                    },
+                    0.0,
+                ],
+                // Specular-glossiness
+                specular_glossiness_factors: material.specular_glossiness.into(),
+                // Texture transforms
+                uv_transforms: uv_transforms(material),
             },
         );
-
-        mesh.gpu_mat_index = material_index;
     }
+
+    first_material_index
+}
+
+/// Rows of the KHR_texture_transform matrix of every texture slot
+fn uv_transforms(material: &GltfMaterialCPU) -> [gbuffer_fs::UvTransform; ETextureSlot::COUNT] {
+    material.texture_transforms.map(|transform| {
+        let matrix = transform.matrix();
+        gbuffer_fs::UvTransform {
+            row0: [matrix.x_axis.x, matrix.y_axis.x, matrix.z_axis.x, 0.0],
+            row1: [matrix.x_axis.y, matrix.y_axis.y, matrix.z_axis.y, 0.0],
+        }
+    })
 }
 
 fn add_bindless_texture(
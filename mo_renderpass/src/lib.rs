@@ -1,3 +1,6 @@
+pub mod error;
+pub use error::RenderError;
+
 pub mod present_pass;
 pub use present_pass::PresentPass;
 
@@ -10,9 +13,18 @@ pub use shading_pass::{ShadingPass};
 pub mod shadow_pass;
 pub use shadow_pass::ShadowPass;
 
+pub mod skinning_pass;
+pub use skinning_pass::SkinningPass;
+
 pub mod ssao_pass;
 pub use ssao_pass::SSAOPass;
 
+pub mod transmission_pass;
+pub use transmission_pass::TransmissionPass;
+
+pub mod ui_pass;
+pub use ui_pass::UiPass;
+
 pub mod utils;
 
 use bevy_ecs::prelude::*;
@@ -23,6 +35,12 @@ use vulkano::image::view::ImageView;
 use winit::dpi::PhysicalSize;
 
 pub trait RenderPassTrait {
+    /// Updates GPU resources from the world before the frame is recorded, e.g. to register the
+    /// materials of models spawned at runtime.
+    fn prepare(&mut self, _world: &mut World) -> Result<(), RenderError> {
+        Ok(())
+    }
+
     /// Renders the current frame using the provided resources and world state.
     ///
     /// # Arguments
@@ -31,13 +49,15 @@ pub trait RenderPassTrait {
     /// * `world` - The ECS world containing the current game state.
     /// * `descriptor_set_allocator` - Allocator for descriptor sets.
     /// * `command_buffer_allocator` - Builder for the command buffer.
+    ///
+    /// An error aborts the recording of the current frame.
     fn render(
         &mut self,
         image_idx: u32,
         world: &World,
         descriptor_set_allocator: Arc<StandardDescriptorSetAllocator>,
         builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
-    );
+    ) -> Result<(), RenderError>;
 
     /// Framebuffers and pipeline need to be recreated when swapchain changes, and viewport information also needs to be set again.
     fn on_swapchain_recreate(
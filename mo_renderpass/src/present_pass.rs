@@ -1,4 +1,4 @@
-use crate::RenderPassTrait;
+use crate::{RenderError, RenderPassTrait};
 use bevy_ecs::prelude::World;
 use mo_ecs::resource::GlobalSamplers;
 use mo_vk::{Texture, VulkanoWindowRenderer, VULKAN};
@@ -155,7 +155,7 @@ impl RenderPassTrait for PresentPass {
         _world: &World,
         _descriptor_set_allocator: Arc<StandardDescriptorSetAllocator>,
         builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
-    ) {
+    ) -> Result<(), RenderError> {
         builder
             .begin_render_pass(
                 RenderPassBeginInfo {
@@ -165,22 +165,20 @@ impl RenderPassTrait for PresentPass {
                     )
                 },
                 Default::default(),
-            )
-            .unwrap()
-            .set_viewport(0, [self.viewport.clone()].into_iter().collect())
-            .unwrap()
-            .bind_pipeline_graphics(self.pipeline.clone())
-            .unwrap()
+            )?
+            .set_viewport(0, [self.viewport.clone()].into_iter().collect())?
+            .bind_pipeline_graphics(self.pipeline.clone())?
             .bind_descriptor_sets(
                 PipelineBindPoint::Graphics,
                 self.pipeline.layout().clone(),
                 0,
                 self.present_descriptor_set.clone(),
-            )
-            .unwrap();
-        unsafe { builder.draw(4, 1, 0, 0) }.unwrap();
+            )?;
+        unsafe { builder.draw(4, 1, 0, 0) }?;
+
+        builder.end_render_pass(Default::default())?;
 
-        builder.end_render_pass(Default::default()).unwrap();
+        Ok(())
     }
 
     fn on_swapchain_recreate(
@@ -1,15 +1,19 @@
-use crate::{RenderPassTrait, GBufferTextures};
+use crate::{GBufferTextures, RenderError, RenderPassTrait};
 use bevy_ecs::prelude::*;
 use bevy_math::prelude::*;
-use mo_ecs::resource::{GlobalSamplers, IBLResource};
-use mo_ecs::{component::DirectionalLight, resource::Camera};
+use mo_ecs::resource::console::{CVAR_SHADOW_BIAS, CVAR_TONEMAP, DEFAULT_SHADOW_BIAS};
+use mo_ecs::resource::{Console, GlobalSamplers, IBLResource};
+use mo_ecs::{
+    component::{DirectionalLight, PointLight, SpotLight},
+    resource::Camera,
+};
 use mo_vk::{Texture, TextureCreateInfo, VulkanoWindowRenderer, VULKAN};
 use std::{cell::RefCell, sync::Arc};
 use vulkano::pipeline::graphics::depth_stencil::{CompareOp, DepthState, DepthStencilState};
 use vulkano::{
     buffer::{
-        allocator::{SubbufferAllocator, SubbufferAllocatorCreateInfo}, Buffer, BufferCreateInfo,
-        BufferUsage,
+        allocator::{SubbufferAllocator, SubbufferAllocatorCreateInfo},
+        BufferUsage, Subbuffer,
     },
     command_buffer::{AutoCommandBufferBuilder, PrimaryAutoCommandBuffer, RenderPassBeginInfo},
     descriptor_set::{
@@ -22,7 +26,7 @@ use vulkano::{
     },
     format::{ClearValue, Format},
     image::{sampler::Sampler, view::ImageView, ImageUsage},
-    memory::allocator::{AllocationCreateInfo, MemoryTypeFilter},
+    memory::allocator::MemoryTypeFilter,
     pipeline::{
         graphics::{
             color_blend::{ColorBlendAttachmentState, ColorBlendState, ColorComponents},
@@ -46,15 +50,20 @@ pub struct ShadingPass {
     pub output_image: Arc<Texture>,
     shading_pipeline: Arc<GraphicsPipeline>,
     uniform_buffer_allocator: SubbufferAllocator,
-    light_descriptor_set: Arc<DescriptorSet>,
+    light_buffer_allocator: SubbufferAllocator,
     gbuffer_images_descriptor_set: Arc<DescriptorSet>,
-    num_lights: u32,
     viewport: Viewport,
 
     // extra textures
     environment_map: Arc<Texture>,
     depth: Arc<Texture>,
     sampler: Arc<Sampler>,
+    /// Reads the integer material layers of the GBuffer
+    nearest_sampler: Arc<Sampler>,
+    // kept to rebuild the texture descriptor set when the environment changes
+    gbuffer_textures: Arc<GBufferTextures>,
+    shadow_map: Arc<Texture>,
+    ssao_texture: Arc<Texture>,
 
     skybox_pipeline: Arc<GraphicsPipeline>,
 }
@@ -77,7 +86,8 @@ impl ShadingPass {
             ],
             usage: ImageUsage::COLOR_ATTACHMENT
                 | ImageUsage::SAMPLED
-                | ImageUsage::INPUT_ATTACHMENT,
+                | ImageUsage::INPUT_ATTACHMENT
+                | ImageUsage::TRANSFER_SRC,
             ..Default::default()
         };
         let output_image = Arc::new(Texture::new(final_output_info));
@@ -122,9 +132,6 @@ impl ShadingPass {
         let shading_pipeline = create_shading_pipeline(&renderpass);
         let skybox_pipeline = create_skybox_pipeline(&renderpass);
 
-        let (light_descriptor_set, num_lights) =
-            create_light_descriptor_set(world, &descriptor_set_alloc, &shading_pipeline);
-
         let framebuffer =
             recreate_shading_framebuffer(&output_image, &gbuffer_textures.depth, &renderpass);
 
@@ -138,6 +145,8 @@ impl ShadingPass {
             },
         );
 
+        let light_buffer_allocator = create_light_buffer_allocator();
+
         let viewport = Viewport {
             offset: [0.0, 0.0],
             extent: renderer.window_size(),
@@ -145,16 +154,19 @@ impl ShadingPass {
         };
 
         let sampler = world.borrow().resource::<GlobalSamplers>().wrap.clone();
+        let nearest_sampler = world.borrow().resource::<GlobalSamplers>().nearest.clone();
 
         let gbuffer_images_descriptor_set = create_textures_descriptor_set(
-            world,
+            world.borrow().resource::<IBLResource>(),
             &descriptor_set_alloc,
             &shading_pipeline,
             gbuffer_textures,
             shadow_map,
             ssao_texture,
             &sampler,
-        );
+            &nearest_sampler,
+        )
+        .unwrap();
 
         ShadingPass {
             renderpass,
@@ -162,14 +174,17 @@ impl ShadingPass {
             shading_pipeline,
             output_image,
             uniform_buffer_allocator,
+            light_buffer_allocator,
             viewport,
-            light_descriptor_set,
             gbuffer_images_descriptor_set,
-            num_lights,
 
             environment_map,
             depth: gbuffer_textures.depth.clone(),
             sampler,
+            nearest_sampler,
+            gbuffer_textures: gbuffer_textures.clone(),
+            shadow_map: shadow_map.clone(),
+            ssao_texture: ssao_texture.clone(),
 
             skybox_pipeline,
         }
@@ -183,7 +198,7 @@ impl RenderPassTrait for ShadingPass {
         world: &World,
         descriptor_set_allocator: Arc<StandardDescriptorSetAllocator>,
         builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
-    ) {
+    ) -> Result<(), RenderError> {
         let mut light_proj_view: Mat4 = Mat4::IDENTITY;
         for entity in world.iter_entities().into_iter() {
             if entity
@@ -196,7 +211,32 @@ impl RenderPassTrait for ShadingPass {
             }
         }
 
+        // The environment was switched at runtime.
+        let ibl = world.resource::<IBLResource>();
+        if !Arc::ptr_eq(&ibl.environment_map, &self.environment_map) {
+            self.environment_map = ibl.environment_map.clone();
+            self.gbuffer_images_descriptor_set = create_textures_descriptor_set(
+                ibl,
+                &descriptor_set_allocator,
+                &self.shading_pipeline,
+                &self.gbuffer_textures,
+                &self.shadow_map,
+                &self.ssao_texture,
+                &self.sampler,
+                &self.nearest_sampler,
+            )?;
+        }
+
+        // Lights are uploaded every frame so that edits show up immediately.
+        let (light_descriptor_set, num_lights) = create_light_descriptor_set(
+            world,
+            &self.light_buffer_allocator,
+            &descriptor_set_allocator,
+            &self.shading_pipeline,
+        )?;
+
         let camera = world.resource::<Camera>();
+        let console = world.get_resource::<Console>();
         let uniform_buffer_subbuffer = {
             let uniform_data = shading_fs::UBO_view {
                 proj_view: camera.projection_view().to_cols_array_2d(),
@@ -204,11 +244,15 @@ impl RenderPassTrait for ShadingPass {
                 inverse_projection: camera.inverse_projection().to_cols_array_2d(),
                 inverse_view: camera.inverse_view().to_cols_array_2d(),
                 light_proj_view: light_proj_view.to_cols_array_2d(),
-                num_lights: self.num_lights,
+                num_lights,
+                shadow_bias: console.map_or(DEFAULT_SHADOW_BIAS, |console| {
+                    console.get_or(CVAR_SHADOW_BIAS, DEFAULT_SHADOW_BIAS)
+                }),
+                tonemap: console.map_or(0, |console| console.get_or(CVAR_TONEMAP, 0)) as u32,
             };
 
-            let subbuffer = self.uniform_buffer_allocator.allocate_sized().unwrap();
-            *subbuffer.write().unwrap() = uniform_data;
+            let subbuffer = self.uniform_buffer_allocator.allocate_sized()?;
+            *subbuffer.write()? = uniform_data;
 
             subbuffer
         };
@@ -219,8 +263,7 @@ impl RenderPassTrait for ShadingPass {
             layout2.clone(),
             [WriteDescriptorSet::buffer(0, uniform_buffer_subbuffer)],
             [],
-        )
-        .unwrap();
+        )?;
 
         builder
             .begin_render_pass(
@@ -229,29 +272,23 @@ impl RenderPassTrait for ShadingPass {
                     ..RenderPassBeginInfo::framebuffer(self.framebuffer.clone())
                 },
                 Default::default(),
-            )
-            .unwrap()
-            .bind_pipeline_graphics(self.shading_pipeline.clone())
-            .unwrap()
-            .set_viewport(0, [self.viewport.clone()].into_iter().collect())
-            .unwrap()
+            )?
+            .bind_pipeline_graphics(self.shading_pipeline.clone())?
+            .set_viewport(0, [self.viewport.clone()].into_iter().collect())?
             .bind_descriptor_sets(
                 PipelineBindPoint::Graphics,
                 self.shading_pipeline.layout().clone(),
                 0,
                 vec![
-                    self.light_descriptor_set.clone(),
+                    light_descriptor_set,
                     self.gbuffer_images_descriptor_set.clone(),
                     descriptor_set_2,
                 ],
-            )
-            .unwrap();
+            )?;
 
-        unsafe { builder.draw(4, 1, 0, 0) }.unwrap();
+        unsafe { builder.draw(4, 1, 0, 0) }?;
 
-        builder
-            .next_subpass(Default::default(), Default::default())
-            .unwrap();
+        builder.next_subpass(Default::default(), Default::default())?;
 
         let uniform_buffer_subbuffer2 = {
             let mut near_plane_width_height = Vec2::ZERO;
@@ -268,8 +305,8 @@ impl RenderPassTrait for ShadingPass {
                 viewportWidthHeight: [self.viewport.extent[0], self.viewport.extent[1]],
             };
 
-            let subbuffer = self.uniform_buffer_allocator.allocate_sized().unwrap();
-            *subbuffer.write().unwrap() = uniform_data;
+            let subbuffer = self.uniform_buffer_allocator.allocate_sized()?;
+            *subbuffer.write()? = uniform_data;
 
             subbuffer
         };
@@ -287,23 +324,22 @@ impl RenderPassTrait for ShadingPass {
                 WriteDescriptorSet::buffer(1, uniform_buffer_subbuffer2),
             ],
             [],
-        )
-        .unwrap();
+        )?;
 
         builder
-            .bind_pipeline_graphics(self.skybox_pipeline.clone())
-            .unwrap()
+            .bind_pipeline_graphics(self.skybox_pipeline.clone())?
             .bind_descriptor_sets(
                 PipelineBindPoint::Graphics,
                 self.skybox_pipeline.layout().clone(),
                 0,
                 vec![descriptor_set_skybox],
-            )
-            .unwrap();
+            )?;
+
+        unsafe { builder.draw(4, 1, 0, 0) }?;
 
-        unsafe { builder.draw(4, 1, 0, 0) }.unwrap();
+        builder.end_render_pass(Default::default())?;
 
-        builder.end_render_pass(Default::default()).unwrap();
+        Ok(())
     }
 
     fn on_swapchain_recreate(
@@ -328,7 +364,7 @@ mod shading_vs {
     }
 }
 
-mod shading_fs {
+pub(crate) mod shading_fs {
     vulkano_shaders::shader! {
         ty: "fragment",
         path: "../resources/shaders/shading_pass.frag",
@@ -580,6 +616,16 @@ fn create_shading_pipeline(renderpass: &Arc<RenderPass>) -> Arc<GraphicsPipeline
                                     )
                                 },
                             ),
+                            (
+                                12,
+                                DescriptorSetLayoutBinding {
+                                    stages: ShaderStages::FRAGMENT,
+                                    descriptor_count: 1,
+                                    ..DescriptorSetLayoutBinding::descriptor_type(
+                                        DescriptorType::CombinedImageSampler,
+                                    )
+                                },
+                            ),
                         ]
                         .into_iter()
                         .collect(),
@@ -650,17 +696,34 @@ fn create_shading_pipeline(renderpass: &Arc<RenderPass>) -> Arc<GraphicsPipeline
     .unwrap()
 }
 
-fn create_light_descriptor_set(
-    world: &RefCell<World>,
-    descriptor_set_alloc: &Arc<StandardDescriptorSetAllocator>,
-    pipeline: &Arc<GraphicsPipeline>,
-) -> (Arc<DescriptorSet>, u32) {
+/// Creates the allocator the lights are uploaded into, the memory of earlier frames is reused.
+pub(crate) fn create_light_buffer_allocator() -> SubbufferAllocator {
+    SubbufferAllocator::new(
+        VULKAN.memory_allocator().clone(),
+        SubbufferAllocatorCreateInfo {
+            buffer_usage: BufferUsage::STORAGE_BUFFER,
+            memory_type_filter: MemoryTypeFilter::PREFER_DEVICE
+                | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
+            ..Default::default()
+        },
+    )
+}
+
+/// Uploads the lights of the world, also read by the transmission pass. Returns the buffer and
+/// the number of lights.
+pub(crate) fn create_light_buffer(
+    world: &World,
+    allocator: &SubbufferAllocator,
+) -> Result<(Subbuffer<[shading_fs::GpuLight]>, u32), RenderError> {
     let mut gpu_lights: Vec<shading_fs::GpuLight> = Vec::new();
 
     let mut total_light_num = 0;
 
-    let mut query = world.borrow_mut().query::<&DirectionalLight>();
-    for light in query.iter(&world.borrow()) {
+    for light in world
+        .iter_entities()
+        .filter_map(|entity| entity.get::<DirectionalLight>())
+    {
+        let color = light.linear_color();
         gpu_lights.push(shading_fs::GpuLight {
             type_range_spot_id: [0.0, 0.0, 0.0, 0.0],
             position: [
@@ -669,7 +732,7 @@ fn create_light_descriptor_set(
                 light.transform.translation.z,
                 0.0,
             ],
-            color: [light.color.x, light.color.y, light.color.z, 0.0],
+            color: [color.r, color.g, color.b, 0.0],
             direction: [
                 light.transform.direction().x,
                 light.transform.direction().y,
@@ -687,22 +750,61 @@ fn create_light_descriptor_set(
         total_light_num += 1;
     }
 
-    let memory_allocator = VULKAN.memory_allocator().clone();
+    // Point and spot lights carry their intensity in attenuation.x and fade out at their range.
+    let point_lights = world
+        .iter_entities()
+        .filter_map(|entity| entity.get::<PointLight>())
+        .map(|light| {
+            let light_type = [1.0, light.range.unwrap_or(0.0), 0.0, 0.0];
+            (
+                light_type,
+                light.transform,
+                light.linear_color(),
+                light.intensity,
+            )
+        });
+    let spot_lights = world
+        .iter_entities()
+        .filter_map(|entity| entity.get::<SpotLight>())
+        .map(|light| {
+            let (cone_scale, cone_offset) = light.cone_scale_offset();
+            let light_type = [2.0, light.range.unwrap_or(0.0), cone_scale, cone_offset];
+            (
+                light_type,
+                light.transform,
+                light.linear_color(),
+                light.intensity,
+            )
+        });
+    for (light_type, transform, color, intensity) in point_lights.chain(spot_lights) {
+        let direction = transform.direction();
+        gpu_lights.push(shading_fs::GpuLight {
+            type_range_spot_id: light_type,
+            position: transform.translation.extend(1.0).into(),
+            color: [color.r, color.g, color.b, 0.0],
+            direction: direction.extend(0.0).into(),
+            attenuation: [intensity, 0.0, 0.0, 0.0],
+        });
 
-    let gpu_light_buffer = Buffer::from_iter(
-        memory_allocator.clone(),
-        BufferCreateInfo {
-            usage: BufferUsage::STORAGE_BUFFER,
-            ..Default::default()
-        },
-        AllocationCreateInfo {
-            memory_type_filter: MemoryTypeFilter::PREFER_DEVICE
-                | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
-            ..Default::default()
-        },
-        gpu_lights,
-    )
-    .unwrap();
+        total_light_num += 1;
+    }
+
+    // Empty buffers can not be bound, the shader only reads `total_light_num` lights.
+    let gpu_light_buffer = allocator.allocate_slice(gpu_lights.len().max(1) as u64)?;
+    for (gpu_light, light) in gpu_light_buffer.write()?.iter_mut().zip(gpu_lights) {
+        *gpu_light = light;
+    }
+
+    Ok((gpu_light_buffer, total_light_num))
+}
+
+fn create_light_descriptor_set(
+    world: &World,
+    light_buffer_allocator: &SubbufferAllocator,
+    descriptor_set_alloc: &Arc<StandardDescriptorSetAllocator>,
+    pipeline: &Arc<GraphicsPipeline>,
+) -> Result<(Arc<DescriptorSet>, u32), RenderError> {
+    let (gpu_light_buffer, total_light_num) = create_light_buffer(world, light_buffer_allocator)?;
 
     let gpu_light_writes = WriteDescriptorSet::buffer(0, gpu_light_buffer);
     let layout = &pipeline.layout().set_layouts()[0];
@@ -712,35 +814,27 @@ fn create_light_descriptor_set(
         layout.clone(),
         [gpu_light_writes],
         [],
-    )
-    .unwrap();
+    )?;
 
-    (set, total_light_num)
+    Ok((set, total_light_num))
 }
 
 fn create_textures_descriptor_set(
-    world: &RefCell<World>,
+    ibl: &IBLResource,
     descriptor_set_alloc: &Arc<StandardDescriptorSetAllocator>,
     shading_pipeline: &Arc<GraphicsPipeline>,
     gbuffer_textures: &GBufferTextures,
     shadow_map: &Arc<Texture>,
     ssao_texture: &Arc<Texture>,
     sampler: &Arc<Sampler>,
-) -> Arc<DescriptorSet> {
-    let irradiance_map = world
-        .borrow()
-        .resource::<IBLResource>()
-        .irradiance_map
-        .clone();
-    let prefiltered_map = world
-        .borrow()
-        .resource::<IBLResource>()
-        .specular_map
-        .clone();
-    let brdf_lut = world.borrow().resource::<IBLResource>().brdf_lut.clone();
+    nearest_sampler: &Arc<Sampler>,
+) -> Result<Arc<DescriptorSet>, RenderError> {
+    let irradiance_map = ibl.irradiance_map.clone();
+    let prefiltered_map = ibl.specular_map.clone();
+    let brdf_lut = ibl.brdf_lut.clone();
 
     let layout1 = &shading_pipeline.layout().set_layouts()[1];
-    DescriptorSet::new(
+    let set = DescriptorSet::new(
         descriptor_set_alloc.clone(),
         layout1.clone(),
         [
@@ -799,10 +893,21 @@ fn create_textures_descriptor_set(
                 brdf_lut.image_view.clone(),
                 sampler.clone(),
             ),
+            WriteDescriptorSet::image_view_sampler(
+                11,
+                gbuffer_textures.material.image_view.clone(),
+                nearest_sampler.clone(),
+            ),
+            WriteDescriptorSet::image_view_sampler(
+                12,
+                gbuffer_textures.material_ext.image_view.clone(),
+                nearest_sampler.clone(),
+            ),
         ],
         [],
-    )
-    .unwrap()
+    )?;
+
+    Ok(set)
 }
 
 fn create_skybox_pipeline(renderpass: &Arc<RenderPass>) -> Arc<GraphicsPipeline> {
@@ -1,8 +1,9 @@
-use crate::RenderPassTrait;
+use crate::{RenderError, RenderPassTrait};
 use bevy_ecs::prelude::*;
 use bevy_math::Mat4;
-use mo_ecs::component::{DirectionalLight, Transform};
-use mo_ecs::model::{Model, StaticVertex};
+use mo_ecs::asset::AssetServer;
+use mo_ecs::component::DirectionalLight;
+use mo_ecs::model::{ModelInstance, SkinnedVertices, StaticVertex};
 use mo_vk::{Texture, TextureCreateInfo, VULKAN};
 use std::sync::Arc;
 use vulkano::buffer::allocator::{SubbufferAllocator, SubbufferAllocatorCreateInfo};
@@ -192,7 +193,7 @@ impl RenderPassTrait for ShadowPass {
         world: &World,
         descriptor_set_allocator: Arc<StandardDescriptorSetAllocator>,
         builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
-    ) {
+    ) -> Result<(), RenderError> {
         let mut proj_view: Mat4 = Mat4::IDENTITY;
         for entity in world.iter_entities().into_iter() {
             if entity
@@ -210,8 +211,8 @@ impl RenderPassTrait for ShadowPass {
                 proj_view: proj_view.to_cols_array_2d(),
             };
 
-            let subbuffer = self.uniform_buffer_allocator.allocate_sized().unwrap();
-            *subbuffer.write().unwrap() = uniform_data;
+            let subbuffer = self.uniform_buffer_allocator.allocate_sized()?;
+            *subbuffer.write()? = uniform_data;
 
             subbuffer
         };
@@ -222,8 +223,7 @@ impl RenderPassTrait for ShadowPass {
             layout.clone(),
             [WriteDescriptorSet::buffer(0, uniform_buffer_subbuffer)],
             [],
-        )
-        .unwrap();
+        )?;
 
         builder
             .begin_render_pass(
@@ -232,49 +232,47 @@ impl RenderPassTrait for ShadowPass {
                     ..RenderPassBeginInfo::framebuffer(self.framebuffer.clone())
                 },
                 Default::default(),
-            )
-            .unwrap()
-            .bind_pipeline_graphics(self.pipeline.clone())
-            .unwrap()
+            )?
+            .bind_pipeline_graphics(self.pipeline.clone())?
             .bind_descriptor_sets(
                 PipelineBindPoint::Graphics,
                 self.pipeline.layout().clone(),
                 0,
                 vec![descriptor_set],
-            )
-            .unwrap();
+            )?;
+
+        let asset_server = world.resource::<AssetServer>();
 
         // 绘制逻辑...
+        let skinned_vertices = world.get_resource::<SkinnedVertices>();
         for entity in world.iter_entities().into_iter() {
-            if entity.get::<Model>().is_some() {
-                let transform = entity.get::<Transform>().unwrap();
-                let model = entity.get::<Model>().unwrap();
+            let Some(instance) = ModelInstance::from_entity(entity, asset_server, skinned_vertices)
+            else {
+                continue;
+            };
 
-                for mesh in &model.meshes {
-                    builder
-                        .push_constants(
-                            self.pipeline.layout().clone(),
-                            0,
-                            vs::PushConsts {
-                                model: (transform.model_matrix() * mesh.world).to_cols_array_2d(),
-                            },
-                        )
-                        .unwrap()
-                        .bind_vertex_buffers(0, mesh.primitive.vertex_buffer.clone())
-                        .unwrap()
-                        .bind_index_buffer(mesh.primitive.index_buffer.clone())
-                        .unwrap();
+            for mesh in &instance.meshes {
+                let index_buffer = &instance.model.meshes[mesh.index].primitive.index_buffer;
+                builder
+                    .push_constants(
+                        self.pipeline.layout().clone(),
+                        0,
+                        vs::PushConsts {
+                            model: mesh.world.to_cols_array_2d(),
+                        },
+                    )?
+                    .bind_vertex_buffers(0, mesh.vertex_buffer.clone())?
+                    .bind_index_buffer(index_buffer.clone())?;
 
-                    unsafe {
-                        builder
-                            .draw_indexed(mesh.primitive.index_buffer.len() as u32, 1, 0, 0, 0)
-                            .unwrap();
-                    }
+                unsafe {
+                    builder.draw_indexed(index_buffer.len() as u32, 1, 0, 0, 0)?;
                 }
             }
         }
 
-        builder.end_render_pass(Default::default()).unwrap();
+        builder.end_render_pass(Default::default())?;
+
+        Ok(())
     }
 
     fn on_swapchain_recreate(
@@ -0,0 +1,354 @@
+use crate::{RenderError, RenderPassTrait};
+use bevy_ecs::prelude::*;
+use mo_ecs::{
+    asset::AssetServer,
+    component::MorphWeights,
+    model::{ModelInstance, MorphVertex, SkinVertex, SkinnedVertices, StaticVertex},
+};
+use mo_vk::VULKAN;
+use std::{collections::HashMap, sync::Arc};
+use vulkano::{
+    buffer::{
+        allocator::{SubbufferAllocator, SubbufferAllocatorCreateInfo},
+        Buffer, BufferCreateInfo, BufferUsage, Subbuffer,
+    },
+    command_buffer::{AutoCommandBufferBuilder, PrimaryAutoCommandBuffer},
+    descriptor_set::{
+        allocator::StandardDescriptorSetAllocator,
+        layout::{
+            DescriptorSetLayout, DescriptorSetLayoutBinding, DescriptorSetLayoutCreateInfo,
+            DescriptorType,
+        },
+        DescriptorSet, WriteDescriptorSet,
+    },
+    image::view::ImageView,
+    memory::allocator::{AllocationCreateInfo, MemoryTypeFilter},
+    pipeline::{
+        compute::ComputePipelineCreateInfo,
+        layout::{PipelineLayoutCreateInfo, PushConstantRange},
+        ComputePipeline, Pipeline, PipelineBindPoint, PipelineLayout,
+        PipelineShaderStageCreateInfo,
+    },
+    shader::ShaderStages,
+};
+use winit::dpi::PhysicalSize;
+
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Deforms the skinned and morphed meshes before the geometry passes
+///
+/// Morph targets are blended first, then skinned meshes are transformed into world space. The
+/// buffers are published in the [`SkinnedVertices`] resource, the GBuffer and shadow passes draw
+/// them instead of the bind pose vertices.
+pub struct SkinningPass {
+    pipeline: Arc<ComputePipeline>,
+    /// Joint matrices and morph weights of the current frame
+    frame_buffer: SubbufferAllocator,
+    /// Bound in place of the skin, joint and morph buffers of meshes without them
+    unused_buffer: Subbuffer<[[[f32; 4]; 4]]>,
+    /// Output vertices of every skinned mesh, by entity and mesh index
+    outputs: HashMap<(Entity, usize), Subbuffer<[StaticVertex]>>,
+    jobs: Vec<SkinningJob>,
+}
+
+/// One mesh to deform this frame
+struct SkinningJob {
+    source: Subbuffer<[StaticVertex]>,
+    /// Skin vertices and joint matrices of skinned meshes
+    skin: Option<(Subbuffer<[SkinVertex]>, Subbuffer<[[[f32; 4]; 4]]>)>,
+    /// Morph targets and weights of morphed meshes
+    morph: Option<(Subbuffer<[MorphVertex]>, Subbuffer<[f32]>)>,
+    morph_target_count: u32,
+    output: Subbuffer<[StaticVertex]>,
+}
+
+impl SkinningPass {
+    pub fn new() -> Self {
+        let frame_buffer = SubbufferAllocator::new(
+            VULKAN.memory_allocator().clone(),
+            SubbufferAllocatorCreateInfo {
+                buffer_usage: BufferUsage::STORAGE_BUFFER,
+                memory_type_filter: MemoryTypeFilter::PREFER_DEVICE
+                    | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
+                ..Default::default()
+            },
+        );
+
+        let unused_buffer = Buffer::from_iter(
+            VULKAN.memory_allocator().clone(),
+            BufferCreateInfo {
+                usage: BufferUsage::STORAGE_BUFFER,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                memory_type_filter: MemoryTypeFilter::PREFER_DEVICE
+                    | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
+                ..Default::default()
+            },
+            [[[0.0; 4]; 4]],
+        )
+        .unwrap();
+
+        tracing::info!("Render - Render pass Skinning Pass successfully created.");
+
+        Self {
+            pipeline: create_skinning_pipeline(),
+            frame_buffer,
+            unused_buffer,
+            outputs: HashMap::new(),
+            jobs: Vec::new(),
+        }
+    }
+}
+
+impl Default for SkinningPass {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RenderPassTrait for SkinningPass {
+    fn prepare(&mut self, world: &mut World) -> Result<(), RenderError> {
+        self.jobs.clear();
+        let mut outputs = HashMap::new();
+
+        {
+            let world: &World = world;
+            let asset_server = world.resource::<AssetServer>();
+
+            for entity in world.iter_entities() {
+                let Some(instance) = ModelInstance::from_entity(entity, asset_server, None) else {
+                    continue;
+                };
+
+                for draw in &instance.meshes {
+                    let mesh = &instance.model.meshes[draw.index];
+                    let primitive = &mesh.primitive;
+
+                    let skin = match (
+                        mesh.skin.and_then(|skin| instance.model.skins.get(skin)),
+                        &primitive.skin_buffer,
+                    ) {
+                        (Some(skin), Some(skin_buffer)) => {
+                            let Some(joint_matrices) =
+                                skin.joint_matrices(world, entity, instance.model)
+                            else {
+                                continue;
+                            };
+                            let joints = self
+                                .frame_buffer
+                                .allocate_slice(joint_matrices.len().max(1) as u64)?;
+                            for (joint, matrix) in joints.write()?.iter_mut().zip(&joint_matrices) {
+                                *joint = matrix.to_cols_array_2d();
+                            }
+                            Some((skin_buffer.clone(), joints))
+                        }
+                        _ => None,
+                    };
+
+                    // Weights of the entity, or the defaults of the file. Targets with zero
+                    // weights leave the vertices as they are.
+                    let weights = entity
+                        .get::<MorphWeights>()
+                        .map_or(&mesh.morph_weights, |weights| &weights.0);
+                    let morph = match &primitive.morph_buffer {
+                        Some(morph_buffer) if weights.iter().any(|weight| *weight != 0.0) => {
+                            let morph_weights = self
+                                .frame_buffer
+                                .allocate_slice(primitive.morph_target_count as u64)?;
+                            for (index, weight) in morph_weights.write()?.iter_mut().enumerate() {
+                                *weight = weights.get(index).copied().unwrap_or_default();
+                            }
+                            Some((morph_buffer.clone(), morph_weights))
+                        }
+                        _ => None,
+                    };
+                    if skin.is_none() && morph.is_none() {
+                        continue;
+                    }
+
+                    let key = (entity.id(), draw.index);
+                    let source = primitive.vertex_buffer.clone();
+                    // Reuse the output of the previous frame unless the model was reloaded.
+                    let output = match self.outputs.remove(&key) {
+                        Some(output) if output.len() == source.len() => output,
+                        _ => create_output_buffer(source.len())?,
+                    };
+
+                    self.jobs.push(SkinningJob {
+                        source,
+                        skin,
+                        morph_target_count: if morph.is_some() {
+                            primitive.morph_target_count as u32
+                        } else {
+                            0
+                        },
+                        morph,
+                        output: output.clone(),
+                    });
+                    outputs.insert(key, output);
+                }
+            }
+        }
+
+        // Outputs of despawned entities are dropped here.
+        self.outputs = outputs;
+        if !self.outputs.is_empty() || world.contains_resource::<SkinnedVertices>() {
+            world.insert_resource(SkinnedVertices::new(self.outputs.clone()));
+        }
+
+        Ok(())
+    }
+
+    fn render(
+        &mut self,
+        _image_idx: u32,
+        _world: &World,
+        descriptor_set_allocator: Arc<StandardDescriptorSetAllocator>,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) -> Result<(), RenderError> {
+        if self.jobs.is_empty() {
+            return Ok(());
+        }
+
+        builder.bind_pipeline_compute(self.pipeline.clone())?;
+
+        let layout = &self.pipeline.layout().set_layouts()[0];
+        for job in self.jobs.drain(..) {
+            let vertex_count = job.source.len() as u32;
+            let skinned = job.skin.is_some();
+            let (skin, joints) = match job.skin {
+                Some((skin, joints)) => (
+                    WriteDescriptorSet::buffer(1, skin),
+                    WriteDescriptorSet::buffer(2, joints),
+                ),
+                None => (
+                    WriteDescriptorSet::buffer(1, self.unused_buffer.clone()),
+                    WriteDescriptorSet::buffer(2, self.unused_buffer.clone()),
+                ),
+            };
+            let (morph_targets, morph_weights) = match job.morph {
+                Some((morph_targets, morph_weights)) => (
+                    WriteDescriptorSet::buffer(4, morph_targets),
+                    WriteDescriptorSet::buffer(5, morph_weights),
+                ),
+                None => (
+                    WriteDescriptorSet::buffer(4, self.unused_buffer.clone()),
+                    WriteDescriptorSet::buffer(5, self.unused_buffer.clone()),
+                ),
+            };
+            let descriptor_set = DescriptorSet::new(
+                descriptor_set_allocator.clone(),
+                layout.clone(),
+                [
+                    WriteDescriptorSet::buffer(0, job.source),
+                    skin,
+                    joints,
+                    WriteDescriptorSet::buffer(3, job.output),
+                    morph_targets,
+                    morph_weights,
+                ],
+                [],
+            )?;
+
+            builder
+                .bind_descriptor_sets(
+                    PipelineBindPoint::Compute,
+                    self.pipeline.layout().clone(),
+                    0,
+                    descriptor_set,
+                )?
+                .push_constants(
+                    self.pipeline.layout().clone(),
+                    0,
+                    skinning_cs::PushConsts {
+                        vertexCount: vertex_count,
+                        morphTargetCount: job.morph_target_count,
+                        skinned: skinned as u32,
+                    },
+                )?;
+
+            unsafe {
+                builder.dispatch([vertex_count.div_ceil(WORKGROUP_SIZE), 1, 1])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn on_swapchain_recreate(
+        &mut self,
+        _swapchain_images: &[Arc<ImageView>],
+        _window_size: PhysicalSize<u32>,
+    ) {
+    }
+}
+
+fn create_output_buffer(len: u64) -> Result<Subbuffer<[StaticVertex]>, RenderError> {
+    Ok(Buffer::new_slice(
+        VULKAN.memory_allocator().clone(),
+        BufferCreateInfo {
+            usage: BufferUsage::STORAGE_BUFFER | BufferUsage::VERTEX_BUFFER,
+            ..Default::default()
+        },
+        AllocationCreateInfo {
+            memory_type_filter: MemoryTypeFilter::PREFER_DEVICE,
+            ..Default::default()
+        },
+        len,
+    )?)
+}
+
+mod skinning_cs {
+    vulkano_shaders::shader! {
+        ty: "compute",
+        path: "../resources/shaders/skinning.comp"
+    }
+}
+
+fn create_skinning_pipeline() -> Arc<ComputePipeline> {
+    let shader = skinning_cs::load(VULKAN.device().clone())
+        .unwrap()
+        .entry_point("main")
+        .unwrap();
+
+    let stage = PipelineShaderStageCreateInfo::new(shader);
+
+    let storage_buffer = DescriptorSetLayoutBinding {
+        stages: ShaderStages::COMPUTE,
+        ..DescriptorSetLayoutBinding::descriptor_type(DescriptorType::StorageBuffer)
+    };
+    let pipeline_layout = PipelineLayout::new(
+        VULKAN.device().clone(),
+        PipelineLayoutCreateInfo {
+            set_layouts: vec![
+                // Source vertices, skin vertices, joint matrices, output vertices, morph targets
+                // and morph weights.
+                DescriptorSetLayout::new(
+                    VULKAN.device().clone(),
+                    DescriptorSetLayoutCreateInfo {
+                        bindings: (0..6)
+                            .map(|binding| (binding, storage_buffer.clone()))
+                            .collect(),
+                        ..Default::default()
+                    },
+                )
+                .unwrap(),
+            ],
+            push_constant_ranges: vec![PushConstantRange {
+                stages: ShaderStages::COMPUTE,
+                offset: 0,
+                size: size_of::<skinning_cs::PushConsts>() as u32,
+            }],
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    ComputePipeline::new(
+        VULKAN.device().clone(),
+        None,
+        ComputePipelineCreateInfo::stage_layout(stage, pipeline_layout),
+    )
+    .unwrap()
+}
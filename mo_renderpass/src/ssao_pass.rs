@@ -1,5 +1,6 @@
-use crate::RenderPassTrait;
+use crate::{RenderError, RenderPassTrait};
 use bevy_ecs::prelude::World;
+use mo_ecs::resource::{Console, console::CVAR_SSAO_ENABLED};
 use mo_vk::{Texture, TextureCreateInfo, VULKAN, VulkanoWindowRenderer};
 use std::sync::Arc;
 use vulkano::descriptor_set::layout::{
@@ -11,6 +12,8 @@ use vulkano::pipeline::layout::{PipelineLayoutCreateInfo, PushConstantRange};
 use vulkano::pipeline::{
     Pipeline, PipelineBindPoint, PipelineLayout, PipelineShaderStageCreateInfo,
 };
+use vulkano::command_buffer::ClearColorImageInfo;
+use vulkano::format::ClearColorValue;
 use vulkano::shader::ShaderStages;
 use vulkano::{
     command_buffer::{AutoCommandBufferBuilder, PrimaryAutoCommandBuffer},
@@ -53,7 +56,7 @@ impl SSAOPass {
                 renderer.window_size()[1] as u32,
                 1,
             ],
-            usage: ImageUsage::SAMPLED | ImageUsage::STORAGE,
+            usage: ImageUsage::SAMPLED | ImageUsage::STORAGE | ImageUsage::TRANSFER_DST,
             ..Default::default()
         };
 
@@ -76,10 +79,24 @@ impl RenderPassTrait for SSAOPass {
     fn render(
         &mut self,
         _image_idx: u32,
-        _world: &World,
+        world: &World,
         descriptor_set_allocator: Arc<StandardDescriptorSetAllocator>,
         builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
-    ) {
+    ) -> Result<(), RenderError> {
+        let enabled = world
+            .get_resource::<Console>()
+            .is_none_or(|console| console.get_or(CVAR_SSAO_ENABLED, true));
+
+        // When disabled, the occlusion is cleared to 1.0 so shading sees no occlusion.
+        if !enabled {
+            builder
+                .clear_color_image(ClearColorImageInfo {
+                    clear_value: ClearColorValue::Float([1.0; 4]),
+                    ..ClearColorImageInfo::image(self.ssao_texture.image_view.image().clone())
+                })?;
+            return Ok(());
+        }
+
         let layout = &self.pipeline.layout().set_layouts()[0];
         let set0 = DescriptorSet::new(
             descriptor_set_allocator.clone(),
@@ -89,8 +106,7 @@ impl RenderPassTrait for SSAOPass {
                 self.ssao_texture.image_view.clone(),
             )],
             [],
-        )
-        .unwrap();
+        )?;
         let layout = &self.pipeline.layout().set_layouts()[1];
         let set1 = DescriptorSet::new(
             descriptor_set_allocator.clone(),
@@ -101,12 +117,10 @@ impl RenderPassTrait for SSAOPass {
                 self.sampler.clone(),
             )],
             [],
-        )
-        .unwrap();
+        )?;
 
         builder
-            .bind_pipeline_compute(self.pipeline.clone())
-            .unwrap()
+            .bind_pipeline_compute(self.pipeline.clone())?
             .push_constants(
                 self.pipeline.layout().clone(),
                 0,
@@ -114,15 +128,13 @@ impl RenderPassTrait for SSAOPass {
                     textureResolution: [self.window_size.width, self.window_size.height],
                     frameIndex: 0,
                 },
-            )
-            .unwrap()
+            )?
             .bind_descriptor_sets(
                 PipelineBindPoint::Compute,
                 self.pipeline.layout().clone(),
                 0,
                 vec![set0, set1],
-            )
-            .unwrap();
+            )?;
 
         unsafe {
             builder
@@ -130,9 +142,10 @@ impl RenderPassTrait for SSAOPass {
                     self.window_size.width / 16 + 1,
                     self.window_size.height / 16 + 1,
                     1,
-                ])
-                .unwrap();
+                ])?;
         }
+
+        Ok(())
     }
 
     fn on_swapchain_recreate(
@@ -0,0 +1,605 @@
+use crate::{
+    gbuffer_pass::{collect_bindless_resources, create_bindless_descriptor_sets, loaded_models},
+    shading_pass::{create_light_buffer, create_light_buffer_allocator},
+    GBufferTextures, RenderError, RenderPassTrait,
+};
+use bevy_ecs::prelude::*;
+use bevy_math::Mat4;
+use mo_ecs::{
+    asset::{AssetId, AssetServer},
+    model::{ModelInstance, SkinnedVertices, StaticVertex},
+    resource::{console::CVAR_TONEMAP, Camera, Console, GlobalSamplers, IBLResource},
+};
+use mo_vk::{Texture, TextureCreateInfo, VulkanoWindowRenderer, VULKAN};
+use std::{cell::RefCell, collections::HashMap, sync::Arc};
+use vulkano::{
+    buffer::{
+        allocator::{SubbufferAllocator, SubbufferAllocatorCreateInfo},
+        BufferUsage, Subbuffer,
+    },
+    command_buffer::{
+        AutoCommandBufferBuilder, CopyImageInfo, PrimaryAutoCommandBuffer, RenderPassBeginInfo,
+    },
+    descriptor_set::{
+        allocator::StandardDescriptorSetAllocator,
+        layout::{
+            DescriptorSetLayout, DescriptorSetLayoutBinding, DescriptorSetLayoutCreateInfo,
+            DescriptorType,
+        },
+        DescriptorSet, WriteDescriptorSet,
+    },
+    image::{
+        max_mip_levels,
+        sampler::{
+            Filter, Sampler, SamplerAddressMode, SamplerCreateInfo, SamplerMipmapMode,
+            LOD_CLAMP_NONE,
+        },
+        view::ImageView,
+        ImageUsage,
+    },
+    memory::allocator::MemoryTypeFilter,
+    pipeline::{
+        graphics::{
+            color_blend::{ColorBlendAttachmentState, ColorBlendState, ColorComponents},
+            depth_stencil::{CompareOp, DepthState, DepthStencilState},
+            input_assembly::InputAssemblyState,
+            multisample::MultisampleState,
+            rasterization::{CullMode, RasterizationState},
+            vertex_input::{Vertex, VertexDefinition},
+            viewport::{Viewport, ViewportState},
+            GraphicsPipelineCreateInfo,
+        },
+        layout::{PipelineLayoutCreateInfo, PushConstantRange},
+        DynamicState, GraphicsPipeline, Pipeline, PipelineBindPoint, PipelineLayout,
+        PipelineShaderStageCreateInfo,
+    },
+    render_pass::{Framebuffer, FramebufferCreateInfo, RenderPass, Subpass},
+    shader::ShaderStages,
+};
+use winit::dpi::PhysicalSize;
+
+/// Draws the meshes with transmissive materials on top of the lit opaque scene
+///
+/// The output of the shading pass is copied into a mip-chained texture first. Transmissive
+/// surfaces sample it along the view ray refracted through their volume, rough ones from blurrier
+/// mips. The GBuffer pass skips these meshes, so they neither occlude nor light the scene behind
+/// them.
+pub struct TransmissionPass {
+    render_pass: Arc<RenderPass>,
+    framebuffer: Arc<Framebuffer>,
+    pipeline: Arc<GraphicsPipeline>,
+    output_image: Arc<Texture>,
+    depth: Arc<Texture>,
+    /// Mip-chained copy of the lit opaque scene
+    scene_color: Arc<Texture>,
+    texture_descriptor_set: Arc<DescriptorSet>,
+    material_descriptor_set: Arc<DescriptorSet>,
+    /// Index of the first material of each registered model, same as in the GBuffer pass
+    material_offsets: HashMap<AssetId, u32>,
+    uniform_buffer: SubbufferAllocator,
+    light_buffer: SubbufferAllocator,
+    descriptor_set_alloc: Arc<StandardDescriptorSetAllocator>,
+    material_sampler: Arc<Sampler>,
+    /// Trilinear sampler for the scene color mips
+    scene_sampler: Arc<Sampler>,
+    viewport: Viewport,
+}
+
+/// A transmissive mesh to draw this frame
+struct TransmissiveDraw {
+    vertex_buffer: Subbuffer<[StaticVertex]>,
+    index_buffer: Subbuffer<[u32]>,
+    world: Mat4,
+    mat_index: u32,
+}
+
+impl TransmissionPass {
+    pub fn new(
+        world: &RefCell<World>,
+        gbuffer_textures: &GBufferTextures,
+        output_image: Arc<Texture>,
+        renderer: &VulkanoWindowRenderer,
+        descriptor_set_alloc: Arc<StandardDescriptorSetAllocator>,
+    ) -> Self {
+        let extent = [
+            renderer.window_size()[0] as u32,
+            renderer.window_size()[1] as u32,
+            1,
+        ];
+        let scene_color_info = TextureCreateInfo {
+            format: output_image.image_view.format(),
+            extent,
+            usage: ImageUsage::SAMPLED | ImageUsage::TRANSFER_SRC | ImageUsage::TRANSFER_DST,
+            mip_levels: max_mip_levels(extent),
+            ..Default::default()
+        };
+        let scene_color = Arc::new(Texture::new(scene_color_info));
+
+        let render_pass = vulkano::single_pass_renderpass!(
+            VULKAN.device().clone(),
+            attachments: {
+                final_output: {
+                    format: output_image.image_view.format(),
+                    samples: 1,
+                    load_op: Load,
+                    store_op: Store,
+                },
+                depth: {
+                    format: gbuffer_textures.depth.image_view.format(),
+                    samples: 1,
+                    load_op: Load,
+                    store_op: Store,
+                }
+            },
+            pass: {
+                color: [final_output],
+                depth_stencil: {depth},
+            },
+        )
+        .unwrap();
+
+        let framebuffer = create_framebuffer(&output_image, &gbuffer_textures.depth, &render_pass);
+
+        let material_sampler = world.borrow().resource::<GlobalSamplers>().clamp.clone();
+        let scene_sampler = Sampler::new(
+            VULKAN.device().clone(),
+            SamplerCreateInfo {
+                mag_filter: Filter::Linear,
+                min_filter: Filter::Linear,
+                mipmap_mode: SamplerMipmapMode::Linear,
+                address_mode: [SamplerAddressMode::ClampToEdge; 3],
+                lod: 0.0..=LOD_CLAMP_NONE,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let resources = collect_bindless_resources(&mut world.borrow_mut(), &material_sampler);
+        let pipeline = create_pipeline(&render_pass, resources.textures.len() as u32);
+        let (texture_descriptor_set, material_descriptor_set) = create_bindless_descriptor_sets(
+            &pipeline,
+            &descriptor_set_alloc,
+            resources.textures,
+            resources.gpu_materials,
+        )
+        .unwrap();
+
+        let uniform_buffer = SubbufferAllocator::new(
+            VULKAN.memory_allocator().clone(),
+            SubbufferAllocatorCreateInfo {
+                buffer_usage: BufferUsage::UNIFORM_BUFFER,
+                memory_type_filter: MemoryTypeFilter::PREFER_DEVICE
+                    | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
+                ..Default::default()
+            },
+        );
+
+        let light_buffer = create_light_buffer_allocator();
+
+        let viewport = Viewport {
+            offset: [0.0, 0.0],
+            extent: renderer.window_size(),
+            depth_range: 0.0..=1.0,
+        };
+
+        tracing::info!("Render - Render pass Transmission Pass successfully created.");
+
+        TransmissionPass {
+            render_pass,
+            framebuffer,
+            pipeline,
+            output_image,
+            depth: gbuffer_textures.depth.clone(),
+            scene_color,
+            texture_descriptor_set,
+            material_descriptor_set,
+            material_offsets: resources.material_offsets,
+            uniform_buffer,
+            light_buffer,
+            descriptor_set_alloc,
+            material_sampler,
+            scene_sampler,
+            viewport,
+        }
+    }
+
+    /// Transmissive meshes of every loaded entity
+    fn transmissive_draws(&self, world: &World) -> Vec<TransmissiveDraw> {
+        let asset_server = world.resource::<AssetServer>();
+        let skinned_vertices = world.get_resource::<SkinnedVertices>();
+
+        let mut draws = Vec::new();
+        for entity in world.iter_entities() {
+            let Some(instance) = ModelInstance::from_entity(entity, asset_server, skinned_vertices)
+            else {
+                continue;
+            };
+            let Some(&material_offset) = self.material_offsets.get(&instance.handle.id()) else {
+                continue;
+            };
+
+            for mesh in &instance.meshes {
+                let model_mesh = &instance.model.meshes[mesh.index];
+                if !instance.model.material(mesh.material).is_transmissive() {
+                    continue;
+                }
+                draws.push(TransmissiveDraw {
+                    vertex_buffer: mesh.vertex_buffer.clone(),
+                    index_buffer: model_mesh.primitive.index_buffer.clone(),
+                    world: mesh.world,
+                    mat_index: material_offset + mesh.material as u32,
+                });
+            }
+        }
+
+        draws
+    }
+}
+
+impl RenderPassTrait for TransmissionPass {
+    fn prepare(&mut self, world: &mut World) -> Result<(), RenderError> {
+        // Same registration as the GBuffer pass, so the material indices match.
+        let models = loaded_models(world);
+        let unchanged = models.len() == self.material_offsets.len()
+            && models
+                .iter()
+                .all(|handle| self.material_offsets.contains_key(&handle.id()));
+        if unchanged {
+            return Ok(());
+        }
+
+        let resources = collect_bindless_resources(world, &self.material_sampler);
+        let pipeline = create_pipeline(&self.render_pass, resources.textures.len() as u32);
+        let (texture_descriptor_set, material_descriptor_set) = create_bindless_descriptor_sets(
+            &pipeline,
+            &self.descriptor_set_alloc,
+            resources.textures,
+            resources.gpu_materials,
+        )?;
+
+        self.pipeline = pipeline;
+        self.texture_descriptor_set = texture_descriptor_set;
+        self.material_descriptor_set = material_descriptor_set;
+        self.material_offsets = resources.material_offsets;
+
+        Ok(())
+    }
+
+    fn render(
+        &mut self,
+        _image_idx: u32,
+        world: &World,
+        descriptor_set_allocator: Arc<StandardDescriptorSetAllocator>,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) -> Result<(), RenderError> {
+        let draws = self.transmissive_draws(world);
+        if draws.is_empty() {
+            return Ok(());
+        }
+
+        // Snapshot of the opaque scene with its mip chain.
+        builder.copy_image(CopyImageInfo::images(
+            self.output_image.image_view.image().clone(),
+            self.scene_color.image_view.image().clone(),
+        ))?;
+        self.scene_color.record_mipmaps(builder)?;
+
+        let camera = world.resource::<Camera>();
+        let console = world.get_resource::<Console>();
+
+        let projview_buffer = self.uniform_buffer.allocate_sized()?;
+        *projview_buffer.write()? = transmission_fs::UBO_projview {
+            projection: camera.projection().to_cols_array_2d(),
+            view: camera.view().to_cols_array_2d(),
+            prev_view: camera.prev_view().to_cols_array_2d(),
+        };
+
+        let (light_buffer, num_lights) = create_light_buffer(world, &self.light_buffer)?;
+        let view_buffer = self.uniform_buffer.allocate_sized()?;
+        *view_buffer.write()? = transmission_fs::UBO_view {
+            eye_pos: camera.position().into(),
+            num_lights,
+            tonemap: console.map_or(0, |console| console.get_or(CVAR_TONEMAP, 0)) as u32,
+        };
+
+        let projview_descriptor_set = DescriptorSet::new(
+            descriptor_set_allocator.clone(),
+            self.pipeline.layout().set_layouts()[2].clone(),
+            [WriteDescriptorSet::buffer(0, projview_buffer)],
+            [],
+        )?;
+
+        let ibl = world.resource::<IBLResource>();
+        let lighting_descriptor_set = DescriptorSet::new(
+            descriptor_set_allocator.clone(),
+            self.pipeline.layout().set_layouts()[3].clone(),
+            [
+                WriteDescriptorSet::buffer(0, light_buffer),
+                WriteDescriptorSet::image_view_sampler(
+                    1,
+                    ibl.irradiance_map.image_view.clone(),
+                    self.material_sampler.clone(),
+                ),
+                WriteDescriptorSet::image_view_sampler(
+                    2,
+                    ibl.specular_map.image_view.clone(),
+                    self.material_sampler.clone(),
+                ),
+                WriteDescriptorSet::image_view_sampler(
+                    3,
+                    ibl.brdf_lut.image_view.clone(),
+                    self.material_sampler.clone(),
+                ),
+                WriteDescriptorSet::image_view_sampler(
+                    4,
+                    self.scene_color.image_view.clone(),
+                    self.scene_sampler.clone(),
+                ),
+                WriteDescriptorSet::buffer(5, view_buffer),
+            ],
+            [],
+        )?;
+
+        builder
+            .begin_render_pass(
+                RenderPassBeginInfo {
+                    clear_values: vec![None, None],
+                    ..RenderPassBeginInfo::framebuffer(self.framebuffer.clone())
+                },
+                Default::default(),
+            )?
+            .bind_pipeline_graphics(self.pipeline.clone())?
+            .set_viewport(0, [self.viewport.clone()].into_iter().collect())?
+            .bind_descriptor_sets(
+                PipelineBindPoint::Graphics,
+                self.pipeline.layout().clone(),
+                0,
+                vec![
+                    self.texture_descriptor_set.clone(),
+                    self.material_descriptor_set.clone(),
+                    projview_descriptor_set,
+                    lighting_descriptor_set,
+                ],
+            )?;
+
+        for draw in draws {
+            let normal_matrix = draw.world.inverse().transpose();
+            builder
+                .push_constants(
+                    self.pipeline.layout().clone(),
+                    0,
+                    transmission_vs::PushConsts {
+                        world: draw.world.to_cols_array_2d(),
+                        mat_index: draw.mat_index,
+                        normal_matrix: normal_matrix.to_cols_array_2d(),
+                        pad: [0, 0, 0],
+                    },
+                )?
+                .bind_vertex_buffers(0, draw.vertex_buffer)?
+                .bind_index_buffer(draw.index_buffer.clone())?;
+
+            unsafe {
+                builder.draw_indexed(draw.index_buffer.len() as u32, 1, 0, 0, 0)?;
+            }
+        }
+
+        builder.end_render_pass(Default::default())?;
+
+        Ok(())
+    }
+
+    fn on_swapchain_recreate(
+        &mut self,
+        _swapchain_images: &[Arc<ImageView>],
+        window_size: PhysicalSize<u32>,
+    ) {
+        self.framebuffer = create_framebuffer(&self.output_image, &self.depth, &self.render_pass);
+        self.viewport.extent = [window_size.width as f32, window_size.height as f32];
+    }
+}
+
+fn create_framebuffer(
+    output_image: &Arc<Texture>,
+    depth: &Arc<Texture>,
+    render_pass: &Arc<RenderPass>,
+) -> Arc<Framebuffer> {
+    Framebuffer::new(
+        render_pass.clone(),
+        FramebufferCreateInfo {
+            attachments: vec![output_image.image_view.clone(), depth.image_view.clone()],
+            ..Default::default()
+        },
+    )
+    .unwrap()
+}
+
+fn create_pipeline(render_pass: &Arc<RenderPass>, texture_count: u32) -> Arc<GraphicsPipeline> {
+    let vs = transmission_vs::load(VULKAN.device().clone())
+        .unwrap()
+        .entry_point("main")
+        .unwrap();
+    let fs = transmission_fs::load(VULKAN.device().clone())
+        .unwrap()
+        .entry_point("main")
+        .unwrap();
+
+    let vertex_input_state = [StaticVertex::per_vertex()].definition(&vs).unwrap();
+    let stages = [
+        PipelineShaderStageCreateInfo::new(vs),
+        PipelineShaderStageCreateInfo::new(fs),
+    ];
+
+    let binding = |stages, descriptor_type| DescriptorSetLayoutBinding {
+        stages,
+        descriptor_count: 1,
+        ..DescriptorSetLayoutBinding::descriptor_type(descriptor_type)
+    };
+
+    let pipeline_layout = PipelineLayout::new(
+        VULKAN.device().clone(),
+        PipelineLayoutCreateInfo {
+            set_layouts: vec![
+                // Bindless textures and materials. Number 0 and 1, same as the GBuffer pass.
+                DescriptorSetLayout::new(
+                    VULKAN.device().clone(),
+                    DescriptorSetLayoutCreateInfo {
+                        bindings: [(
+                            0,
+                            DescriptorSetLayoutBinding {
+                                stages: ShaderStages::FRAGMENT,
+                                descriptor_count: texture_count,
+                                ..DescriptorSetLayoutBinding::descriptor_type(
+                                    DescriptorType::CombinedImageSampler,
+                                )
+                            },
+                        )]
+                        .into_iter()
+                        .collect(),
+                        ..Default::default()
+                    },
+                )
+                .unwrap(),
+                DescriptorSetLayout::new(
+                    VULKAN.device().clone(),
+                    DescriptorSetLayoutCreateInfo {
+                        bindings: [(
+                            0,
+                            binding(ShaderStages::FRAGMENT, DescriptorType::StorageBuffer),
+                        )]
+                        .into_iter()
+                        .collect(),
+                        ..Default::default()
+                    },
+                )
+                .unwrap(),
+                // Camera matrices. Number 2, also used to project the refracted rays.
+                DescriptorSetLayout::new(
+                    VULKAN.device().clone(),
+                    DescriptorSetLayoutCreateInfo {
+                        bindings: [(
+                            0,
+                            binding(
+                                ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+                                DescriptorType::UniformBuffer,
+                            ),
+                        )]
+                        .into_iter()
+                        .collect(),
+                        ..Default::default()
+                    },
+                )
+                .unwrap(),
+                // Lights, IBL, the scene color and the view. Number 3.
+                DescriptorSetLayout::new(
+                    VULKAN.device().clone(),
+                    DescriptorSetLayoutCreateInfo {
+                        bindings: [
+                            (
+                                0,
+                                binding(ShaderStages::FRAGMENT, DescriptorType::StorageBuffer),
+                            ),
+                            (
+                                1,
+                                binding(
+                                    ShaderStages::FRAGMENT,
+                                    DescriptorType::CombinedImageSampler,
+                                ),
+                            ),
+                            (
+                                2,
+                                binding(
+                                    ShaderStages::FRAGMENT,
+                                    DescriptorType::CombinedImageSampler,
+                                ),
+                            ),
+                            (
+                                3,
+                                binding(
+                                    ShaderStages::FRAGMENT,
+                                    DescriptorType::CombinedImageSampler,
+                                ),
+                            ),
+                            (
+                                4,
+                                binding(
+                                    ShaderStages::FRAGMENT,
+                                    DescriptorType::CombinedImageSampler,
+                                ),
+                            ),
+                            (
+                                5,
+                                binding(ShaderStages::FRAGMENT, DescriptorType::UniformBuffer),
+                            ),
+                        ]
+                        .into_iter()
+                        .collect(),
+                        ..Default::default()
+                    },
+                )
+                .unwrap(),
+            ],
+            push_constant_ranges: vec![PushConstantRange {
+                stages: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+                offset: 0,
+                size: size_of::<transmission_vs::PushConsts>() as u32,
+            }],
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let subpass = Subpass::from(render_pass.clone(), 0).unwrap();
+
+    GraphicsPipeline::new(
+        VULKAN.device().clone(),
+        None,
+        GraphicsPipelineCreateInfo {
+            stages: stages.into_iter().collect(),
+            vertex_input_state: Some(vertex_input_state),
+            input_assembly_state: Some(InputAssemblyState::default()),
+            viewport_state: Some(ViewportState::default()),
+            rasterization_state: Some(RasterizationState {
+                cull_mode: CullMode::Back,
+                ..Default::default()
+            }),
+            multisample_state: Some(MultisampleState::default()),
+            color_blend_state: Some(ColorBlendState::with_attachment_states(
+                subpass.num_color_attachments(),
+                ColorBlendAttachmentState {
+                    color_write_mask: ColorComponents::all(),
+                    ..Default::default()
+                },
+            )),
+            // The nearest transmissive surface wins, the ones behind it are not refracted.
+            depth_stencil_state: Some(DepthStencilState {
+                depth: Some(DepthState {
+                    compare_op: CompareOp::LessOrEqual,
+                    write_enable: true,
+                }),
+                ..Default::default()
+            }),
+            dynamic_state: [DynamicState::Viewport].into_iter().collect(),
+            subpass: Some(subpass.into()),
+            ..GraphicsPipelineCreateInfo::layout(pipeline_layout)
+        },
+    )
+    .unwrap()
+}
+
+mod transmission_vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "../resources/shaders/gbuffer.vert",
+        vulkan_version: "1.2",
+        spirv_version: "1.5",
+    }
+}
+
+mod transmission_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "../resources/shaders/transmission.frag",
+        vulkan_version: "1.2",
+        spirv_version: "1.5",
+    }
+}
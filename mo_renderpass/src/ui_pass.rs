@@ -0,0 +1,469 @@
+use crate::{RenderError, RenderPassTrait};
+use bevy_ecs::prelude::World;
+use egui::{epaint::Primitive, ClippedPrimitive, ImageData, TextureId};
+use mo_ecs::resource::{DebugUi, GlobalSamplers};
+use mo_vk::{Texture, TextureCreateInfo, VulkanoWindowRenderer, VULKAN};
+use std::{cell::RefCell, collections::HashMap, sync::Arc};
+use vulkano::{
+    buffer::{
+        allocator::{SubbufferAllocator, SubbufferAllocatorCreateInfo}, Buffer, BufferContents,
+        BufferCreateInfo, BufferUsage,
+    },
+    command_buffer::{
+        AutoCommandBufferBuilder, BufferImageCopy, CopyBufferToImageInfo,
+        PrimaryAutoCommandBuffer, RenderPassBeginInfo,
+    },
+    descriptor_set::{
+        allocator::StandardDescriptorSetAllocator, DescriptorSet, WriteDescriptorSet,
+    },
+    image::{sampler::Sampler, view::ImageView, ImageUsage},
+    memory::allocator::{AllocationCreateInfo, MemoryTypeFilter},
+    pipeline::{
+        graphics::{
+            color_blend::{
+                AttachmentBlend, BlendFactor, BlendOp, ColorBlendAttachmentState, ColorBlendState,
+            },
+            input_assembly::InputAssemblyState,
+            multisample::MultisampleState,
+            rasterization::RasterizationState,
+            vertex_input::{Vertex, VertexDefinition},
+            viewport::{Scissor, Viewport, ViewportState},
+            GraphicsPipelineCreateInfo,
+        }, layout::PipelineDescriptorSetLayoutCreateInfo, DynamicState, GraphicsPipeline, Pipeline,
+        PipelineBindPoint,
+        PipelineLayout,
+        PipelineShaderStageCreateInfo,
+    },
+    render_pass::{Framebuffer, FramebufferCreateInfo, Subpass},
+};
+use winit::dpi::PhysicalSize;
+
+#[derive(BufferContents, Vertex, Clone, Copy)]
+#[repr(C)]
+struct UiVertex {
+    #[format(R32G32_SFLOAT)]
+    position: [f32; 2],
+    #[format(R32G32_SFLOAT)]
+    uv: [f32; 2],
+    #[format(R8G8B8A8_UNORM)]
+    color: [u8; 4],
+}
+
+struct UiTexture {
+    texture: Arc<Texture>,
+    descriptor_set: Arc<DescriptorSet>,
+}
+
+/// Draws the [`DebugUi`] on top of the shaded image.
+///
+/// The target is the gamma encoded output of the shading pass, so the premultiplied sRGB colors
+/// of the UI are blended without conversion.
+pub struct UiPass {
+    framebuffer: Arc<Framebuffer>,
+    pipeline: Arc<GraphicsPipeline>,
+    viewport: Viewport,
+    window_size: [f32; 2],
+
+    buffer_allocator: SubbufferAllocator,
+    sampler: Arc<Sampler>,
+    textures: HashMap<TextureId, UiTexture>,
+}
+
+impl UiPass {
+    pub fn new(
+        world: &RefCell<World>,
+        vulkano_window_renderer: &VulkanoWindowRenderer,
+        target: Arc<Texture>,
+    ) -> Self {
+        let device = VULKAN.device().clone();
+        let render_pass = vulkano::single_pass_renderpass!(
+            device.clone(),
+            attachments: {
+                color: {
+                    format: target.image_view.format(),
+                    samples: 1,
+                    load_op: Load,
+                    store_op: Store,
+                },
+            },
+            pass: {
+                color: [color],
+                depth_stencil: {},
+            },
+        )
+        .unwrap();
+
+        let vs = ui_vs::load(device.clone())
+            .unwrap()
+            .entry_point("main")
+            .unwrap();
+        let fs = ui_fs::load(device.clone())
+            .unwrap()
+            .entry_point("main")
+            .unwrap();
+
+        let pipeline = {
+            let vertex_input_state = [UiVertex::per_vertex()].definition(&vs).unwrap();
+            let stages = [
+                PipelineShaderStageCreateInfo::new(vs.clone()),
+                PipelineShaderStageCreateInfo::new(fs.clone()),
+            ];
+            let layout = PipelineLayout::new(
+                device.clone(),
+                PipelineDescriptorSetLayoutCreateInfo::from_stages(&stages)
+                    .into_pipeline_layout_create_info(device.clone())
+                    .unwrap(),
+            )
+            .unwrap();
+            let subpass = Subpass::from(render_pass.clone(), 0).unwrap();
+
+            GraphicsPipeline::new(
+                device.clone(),
+                None,
+                GraphicsPipelineCreateInfo {
+                    stages: stages.into_iter().collect(),
+                    vertex_input_state: Some(vertex_input_state),
+                    input_assembly_state: Some(InputAssemblyState::default()),
+                    viewport_state: Some(ViewportState::default()),
+                    rasterization_state: Some(RasterizationState::default()),
+                    multisample_state: Some(MultisampleState::default()),
+                    // egui outputs premultiplied alpha
+                    color_blend_state: Some(ColorBlendState::with_attachment_states(
+                        subpass.num_color_attachments(),
+                        ColorBlendAttachmentState {
+                            blend: Some(AttachmentBlend {
+                                src_color_blend_factor: BlendFactor::One,
+                                dst_color_blend_factor: BlendFactor::OneMinusSrcAlpha,
+                                color_blend_op: BlendOp::Add,
+                                src_alpha_blend_factor: BlendFactor::OneMinusDstAlpha,
+                                dst_alpha_blend_factor: BlendFactor::One,
+                                alpha_blend_op: BlendOp::Add,
+                            }),
+                            ..Default::default()
+                        },
+                    )),
+                    dynamic_state: [DynamicState::Viewport, DynamicState::Scissor]
+                        .into_iter()
+                        .collect(),
+                    subpass: Some(subpass.into()),
+                    ..GraphicsPipelineCreateInfo::layout(layout)
+                },
+            )
+            .unwrap()
+        };
+
+        let framebuffer = Framebuffer::new(
+            render_pass,
+            FramebufferCreateInfo {
+                attachments: vec![target.image_view.clone()],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        // The shading output keeps its size and is stretched over the window when presented.
+        let viewport = Viewport {
+            offset: [0.0, 0.0],
+            extent: [target.info.extent[0] as f32, target.info.extent[1] as f32],
+            depth_range: 0.0..=1.0,
+        };
+
+        let buffer_allocator = SubbufferAllocator::new(
+            VULKAN.memory_allocator().clone(),
+            SubbufferAllocatorCreateInfo {
+                buffer_usage: BufferUsage::VERTEX_BUFFER | BufferUsage::INDEX_BUFFER,
+                memory_type_filter: MemoryTypeFilter::PREFER_DEVICE
+                    | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
+                ..Default::default()
+            },
+        );
+
+        let sampler = world.borrow().resource::<GlobalSamplers>().clamp.clone();
+
+        tracing::info!("Render - Render pass UI Pass successfully created.");
+
+        Self {
+            framebuffer,
+            pipeline,
+            viewport,
+            window_size: vulkano_window_renderer.window_size(),
+            buffer_allocator,
+            sampler,
+            textures: HashMap::new(),
+        }
+    }
+
+    /// Creates or partially updates a UI texture. The upload is recorded before the render pass.
+    fn update_texture(
+        &mut self,
+        id: TextureId,
+        delta: &egui::epaint::ImageDelta,
+        descriptor_set_allocator: &Arc<StandardDescriptorSetAllocator>,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) -> Result<(), RenderError> {
+        let [width, height] = delta.image.size();
+        let pixels: Vec<u8> = match &delta.image {
+            ImageData::Color(image) => image
+                .pixels
+                .iter()
+                .flat_map(|color| color.to_array())
+                .collect(),
+            ImageData::Font(image) => image
+                .srgba_pixels(None)
+                .flat_map(|color| color.to_array())
+                .collect(),
+        };
+
+        let (texture, offset) = match (delta.pos, self.textures.get(&id)) {
+            (Some([x, y]), Some(ui_texture)) => {
+                (ui_texture.texture.clone(), [x as u32, y as u32, 0])
+            }
+            (Some(_), None) => {
+                tracing::warn!("Render - Partial update of unknown UI texture {:?}", id);
+                return Ok(());
+            }
+            (None, _) => {
+                let texture = Arc::new(Texture::new(TextureCreateInfo {
+                    extent: [width as u32, height as u32, 1],
+                    usage: ImageUsage::SAMPLED | ImageUsage::TRANSFER_DST,
+                    ..Default::default()
+                }));
+
+                let layout = &self.pipeline.layout().set_layouts()[0];
+                let descriptor_set = DescriptorSet::new(
+                    descriptor_set_allocator.clone(),
+                    layout.clone(),
+                    [WriteDescriptorSet::image_view_sampler(
+                        0,
+                        texture.image_view.clone(),
+                        self.sampler.clone(),
+                    )],
+                    [],
+                )?;
+
+                self.textures.insert(
+                    id,
+                    UiTexture {
+                        texture: texture.clone(),
+                        descriptor_set,
+                    },
+                );
+
+                (texture, [0, 0, 0])
+            }
+        };
+
+        let upload_buffer = Buffer::from_iter(
+            VULKAN.memory_allocator().clone(),
+            BufferCreateInfo {
+                usage: BufferUsage::TRANSFER_SRC,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                memory_type_filter: MemoryTypeFilter::PREFER_HOST
+                    | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
+                ..Default::default()
+            },
+            pixels,
+        )?;
+
+        let image = texture.image_view.image().clone();
+        builder.copy_buffer_to_image(CopyBufferToImageInfo {
+            regions: [BufferImageCopy {
+                image_subresource: image.subresource_layers(),
+                image_offset: offset,
+                image_extent: [width as u32, height as u32, 1],
+                ..Default::default()
+            }]
+            .into(),
+            ..CopyBufferToImageInfo::buffer_image(upload_buffer, image)
+        })?;
+
+        Ok(())
+    }
+
+    fn draw_primitives(
+        &mut self,
+        primitives: &[ClippedPrimitive],
+        pixels_per_point: f32,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) -> Result<(), RenderError> {
+        let screen_size = [
+            self.window_size[0] / pixels_per_point,
+            self.window_size[1] / pixels_per_point,
+        ];
+        // Target pixels per point, the target does not follow the window size.
+        let scale = [
+            self.viewport.extent[0] / screen_size[0],
+            self.viewport.extent[1] / screen_size[1],
+        ];
+
+        builder
+            .begin_render_pass(
+                RenderPassBeginInfo {
+                    clear_values: vec![None],
+                    ..RenderPassBeginInfo::framebuffer(self.framebuffer.clone())
+                },
+                Default::default(),
+            )?
+            .bind_pipeline_graphics(self.pipeline.clone())?
+            .set_viewport(0, [self.viewport.clone()].into_iter().collect())?
+            .push_constants(
+                self.pipeline.layout().clone(),
+                0,
+                ui_vs::PushConsts { screen_size },
+            )?;
+
+        for ClippedPrimitive {
+            clip_rect,
+            primitive,
+        } in primitives
+        {
+            // Paint callbacks are not supported.
+            let Primitive::Mesh(mesh) = primitive else {
+                continue;
+            };
+            let Some(ui_texture) = self.textures.get(&mesh.texture_id) else {
+                continue;
+            };
+            if mesh.indices.is_empty() {
+                continue;
+            }
+
+            let min_x = (clip_rect.min.x * scale[0]).clamp(0.0, self.viewport.extent[0]) as u32;
+            let min_y = (clip_rect.min.y * scale[1]).clamp(0.0, self.viewport.extent[1]) as u32;
+            let max_x =
+                (clip_rect.max.x * scale[0]).clamp(0.0, self.viewport.extent[0]).ceil() as u32;
+            let max_y =
+                (clip_rect.max.y * scale[1]).clamp(0.0, self.viewport.extent[1]).ceil() as u32;
+            if max_x <= min_x || max_y <= min_y {
+                continue;
+            }
+
+            let vertex_buffer = self
+                .buffer_allocator
+                .allocate_slice::<UiVertex>(mesh.vertices.len() as u64)?;
+            for (dst, src) in vertex_buffer.write()?.iter_mut().zip(&mesh.vertices) {
+                *dst = UiVertex {
+                    position: [src.pos.x, src.pos.y],
+                    uv: [src.uv.x, src.uv.y],
+                    color: src.color.to_array(),
+                };
+            }
+
+            let index_buffer = self
+                .buffer_allocator
+                .allocate_slice::<u32>(mesh.indices.len() as u64)?;
+            index_buffer.write()?.copy_from_slice(&mesh.indices);
+
+            builder
+                .set_scissor(
+                    0,
+                    [Scissor {
+                        offset: [min_x, min_y],
+                        extent: [max_x - min_x, max_y - min_y],
+                    }]
+                    .into_iter()
+                    .collect(),
+                )?
+                .bind_descriptor_sets(
+                    PipelineBindPoint::Graphics,
+                    self.pipeline.layout().clone(),
+                    0,
+                    ui_texture.descriptor_set.clone(),
+                )?
+                .bind_vertex_buffers(0, vertex_buffer)?
+                .bind_index_buffer(index_buffer)?;
+
+            unsafe { builder.draw_indexed(mesh.indices.len() as u32, 1, 0, 0, 0) }?;
+        }
+
+        builder.end_render_pass(Default::default())?;
+
+        Ok(())
+    }
+}
+
+impl RenderPassTrait for UiPass {
+    fn render(
+        &mut self,
+        _image_idx: u32,
+        world: &World,
+        descriptor_set_allocator: Arc<StandardDescriptorSetAllocator>,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) -> Result<(), RenderError> {
+        let Some(debug_ui) = world.get_resource::<DebugUi>() else {
+            return Ok(());
+        };
+
+        let textures_delta = debug_ui.take_textures_delta();
+        for (id, delta) in &textures_delta.set {
+            self.update_texture(*id, delta, &descriptor_set_allocator, builder)?;
+        }
+
+        if !debug_ui.primitives().is_empty() {
+            self.draw_primitives(debug_ui.primitives(), debug_ui.pixels_per_point(), builder)?;
+        }
+
+        // The command buffer keeps freed textures alive until it finished executing.
+        for id in &textures_delta.free {
+            self.textures.remove(id);
+        }
+
+        Ok(())
+    }
+
+    fn on_swapchain_recreate(
+        &mut self,
+        _swapchain_images: &[Arc<ImageView>],
+        window_size: PhysicalSize<u32>,
+    ) {
+        self.window_size = [window_size.width as f32, window_size.height as f32];
+    }
+}
+
+mod ui_vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        src: "\
+        #version 460
+
+        layout (location = 0) in vec2 position;
+        layout (location = 1) in vec2 uv;
+        layout (location = 2) in vec4 color;
+
+        layout (push_constant) uniform PushConsts {
+            vec2 screen_size;
+        };
+
+        layout (location = 0) out vec2 fragTexCoord;
+        layout (location = 1) out vec4 fragColor;
+
+        void main() {
+            // 逻辑像素坐标（左上角为原点）转换到 NDC
+            gl_Position = vec4(2.0 * position / screen_size - 1.0, 0.0, 1.0);
+            fragTexCoord = uv;
+            fragColor = color;
+        }
+        ",
+    }
+}
+
+mod ui_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        src: "\
+        #version 460
+
+        layout (set = 0, binding = 0) uniform sampler2D Image;
+
+        layout (location = 0) in vec2 fragTexCoord;
+        layout (location = 1) in vec4 fragColor;
+        layout (location = 0) out vec4 outColor;
+
+        void main() {
+            outColor = fragColor * texture(Image, fragTexCoord);
+        }
+        ",
+    }
+}
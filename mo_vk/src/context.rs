@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 #[cfg(target_os = "macos")]
 use vulkano::instance::InstanceCreateFlags;
 use vulkano::{
@@ -175,8 +175,8 @@ impl VulkanoContext {
             .expect("failed to create physical device");
         // Print used device
         if config.print_device_name {
-            println!(
-                "Using device {}, type: {:?}",
+            tracing::info!(
+                "Context - Using device {}, type: {:?}",
                 physical_device.properties().device_name,
                 physical_device.properties().device_type,
             );
@@ -393,3 +393,91 @@ struct Queues {
     compute_queue: Arc<Queue>,
     transfer_queue: Option<Arc<Queue>>,
 }
+
+/// Shared handle to the current [`VulkanoContext`]
+///
+/// Accessors return owned clones so the context can be replaced with
+/// [`VulkanoContextCell::recreate`] after the device is lost. Every GPU resource created from the
+/// old context must be recreated afterwards.
+pub struct VulkanoContextCell {
+    context: RwLock<Arc<VulkanoContext>>,
+}
+
+impl VulkanoContextCell {
+    pub fn new(context: VulkanoContext) -> Self {
+        Self {
+            context: RwLock::new(Arc::new(context)),
+        }
+    }
+
+    /// Returns the current context.
+    #[inline]
+    pub fn get(&self) -> Arc<VulkanoContext> {
+        self.context.read().unwrap().clone()
+    }
+
+    /// Replaces the current context with a newly created one and returns it.
+    pub fn recreate(&self, config: VulkanoConfig) -> Arc<VulkanoContext> {
+        let context = Arc::new(VulkanoContext::new(config));
+        *self.context.write().unwrap() = context.clone();
+
+        tracing::info!(
+            "Context - Vulkan context recreated on device {}.",
+            context.device_name()
+        );
+        context
+    }
+
+    /// Returns the instance.
+    #[inline]
+    pub fn instance(&self) -> Arc<Instance> {
+        self.get().instance().clone()
+    }
+
+    /// Returns the device.
+    #[inline]
+    pub fn device(&self) -> Arc<Device> {
+        self.get().device().clone()
+    }
+
+    /// Returns the graphics queue.
+    #[inline]
+    pub fn graphics_queue(&self) -> Arc<Queue> {
+        self.get().graphics_queue().clone()
+    }
+
+    /// Returns the compute queue.
+    #[inline]
+    pub fn compute_queue(&self) -> Arc<Queue> {
+        self.get().compute_queue().clone()
+    }
+
+    /// Returns the transfer queue, if the device has a dedicated one.
+    #[inline]
+    pub fn transfer_queue(&self) -> Option<Arc<Queue>> {
+        self.get().transfer_queue().cloned()
+    }
+
+    /// Returns the memory allocator.
+    #[inline]
+    pub fn memory_allocator(&self) -> Arc<StandardMemoryAllocator> {
+        self.get().memory_allocator().clone()
+    }
+
+    /// Returns the command buffer allocator.
+    #[inline]
+    pub fn command_buffer_allocator(&self) -> Arc<StandardCommandBufferAllocator> {
+        self.get().command_buffer_allocator().clone()
+    }
+
+    /// Returns the max descriptors per set supported by the device.
+    #[inline]
+    pub fn max_descriptors_per_set(&self) -> u32 {
+        self.get().max_descriptors_per_set()
+    }
+
+    #[inline]
+    pub fn max_per_stage_descriptor_storage_buffers(&self) -> u32 {
+        self.get().max_per_stage_descriptor_storage_buffers()
+    }
+}
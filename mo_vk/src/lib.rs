@@ -3,7 +3,7 @@ pub mod texture;
 pub mod window;
 
 // pub use pass::test_pass::TeapotPass;
-pub use context::{VulkanoConfig, VulkanoContext};
+pub use context::{VulkanoConfig, VulkanoContext, VulkanoContextCell};
 pub use texture::{Texture, TextureCreateInfo};
 pub use window::{VulkanoWindows, WindowDescriptor};
 
@@ -21,10 +21,8 @@ use vulkano::{
 };
 use winit::window::Window;
 
-pub static VULKAN: LazyLock<Arc<VulkanoContext>> = LazyLock::new(|| {
-    let vulkano_context = Arc::new(VulkanoContext::new(VulkanoConfig::default()));
-    vulkano_context
-});
+pub static VULKAN: LazyLock<VulkanoContextCell> =
+    LazyLock::new(|| VulkanoContextCell::new(VulkanoContext::new(VulkanoConfig::default())));
 
 /// A window renderer struct holding the winit window surface and functionality for organizing your
 /// render between frames.
@@ -271,6 +269,10 @@ impl VulkanoWindowRenderer {
     /// after which the swapchain image has been acquired and previous frame ended.
     /// Execute your command buffers after calling this function and
     /// finish rendering by calling [`VulkanoWindowRenderer::present`].
+    ///
+    /// Returns [`VulkanError::OutOfDate`] when the frame should be skipped, the swapchain is
+    /// recreated on the next call. Any other error, such as [`VulkanError::DeviceLost`], is
+    /// returned to the caller.
     #[inline]
     pub fn acquire(
         &mut self,
@@ -280,7 +282,7 @@ impl VulkanoWindowRenderer {
         // Recreate swap chain if needed (when resizing of window occurs or swapchain is outdated)
         // Also resize render views if needed
         if self.recreate_swapchain {
-            self.recreate_swapchain_and_views();
+            self.recreate_swapchain_and_views()?;
             on_recreate_swapchain(&self.final_views);
         }
 
@@ -294,7 +296,7 @@ impl VulkanoWindowRenderer {
                     self.recreate_swapchain = true;
                     return Err(VulkanError::OutOfDate);
                 }
-                Err(e) => panic!("failed to acquire next image: {e}"),
+                Err(e) => return Err(e),
             };
         if suboptimal {
             self.recreate_swapchain = true;
@@ -302,7 +304,11 @@ impl VulkanoWindowRenderer {
         // Update our image index
         self.image_index = image_index;
 
-        let future = self.previous_frame_end.take().unwrap().join(acquire_future);
+        let previous_frame_end = self
+            .previous_frame_end
+            .take()
+            .unwrap_or_else(|| sync::now(self.graphics_queue.device().clone()).boxed());
+        let future = previous_frame_end.join(acquire_future);
 
         Ok(future.boxed())
     }
@@ -313,8 +319,15 @@ impl VulkanoWindowRenderer {
     /// Depending on your implementation, you may want to wait on your future. For example, a
     /// compute shader dispatch using an image that's being later drawn should probably be waited
     /// on.
+    ///
+    /// Errors other than [`VulkanError::OutOfDate`] are returned after the frame state has been
+    /// reset, so the caller can decide whether to skip the frame or recover the device.
     #[inline]
-    pub fn present(&mut self, after_future: Box<dyn GpuFuture>, wait_future: bool) {
+    pub fn present(
+        &mut self,
+        after_future: Box<dyn GpuFuture>,
+        wait_future: bool,
+    ) -> Result<(), VulkanError> {
         let future = after_future
             .then_swapchain_present(
                 self.graphics_queue.clone(),
@@ -326,34 +339,37 @@ impl VulkanoWindowRenderer {
             .then_signal_fence_and_flush();
         match future.map_err(Validated::unwrap) {
             Ok(mut future) => {
-                if wait_future {
-                    future.wait(None).unwrap_or_else(|e| println!("{e}"))
+                let result = if wait_future {
                     // wait allows you to organize resource waiting yourself.
+                    future.wait(None).map_err(Validated::unwrap)
                 } else {
                     future.cleanup_finished();
-                }
+                    Ok(())
+                };
 
                 self.previous_frame_end = Some(future.boxed());
+                result
             }
             Err(VulkanError::OutOfDate) => {
                 self.recreate_swapchain = true;
                 self.previous_frame_end =
                     Some(sync::now(self.graphics_queue.device().clone()).boxed());
+                Ok(())
             }
             Err(e) => {
-                println!("failed to flush future: {e}");
                 self.previous_frame_end =
                     Some(sync::now(self.graphics_queue.device().clone()).boxed());
+                Err(e)
             }
         }
     }
 
     /// Recreates swapchain images and image views which follow the window size.
-    fn recreate_swapchain_and_views(&mut self) {
+    fn recreate_swapchain_and_views(&mut self) -> Result<(), VulkanError> {
         let image_extent: [u32; 2] = self.window().inner_size().into();
 
         if image_extent.contains(&0) {
-            return;
+            return Ok(());
         }
 
         let (new_swapchain, new_images) = self
@@ -364,7 +380,7 @@ impl VulkanoWindowRenderer {
                 present_mode: self.present_mode,
                 ..self.swapchain.create_info()
             })
-            .expect("failed to recreate swapchain");
+            .map_err(Validated::unwrap)?;
 
         self.swapchain = new_swapchain;
         let new_images = new_images
@@ -385,5 +401,6 @@ impl VulkanoWindowRenderer {
             self.add_additional_image_view(i, format, usage);
         }
         self.recreate_swapchain = false;
+        Ok(())
     }
 }
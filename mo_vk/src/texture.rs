@@ -5,6 +5,7 @@ use vulkano::command_buffer::{BlitImageInfo, ImageBlit};
 use vulkano::image::sampler::Filter;
 use vulkano::image::{ImageLayout, ImageSubresourceLayers};
 use vulkano::sync::GpuFuture;
+use vulkano::ValidationError;
 use vulkano::{
     buffer::{Buffer, BufferCreateInfo, BufferUsage},
     command_buffer::{
@@ -72,13 +73,21 @@ pub struct Texture {
 
 impl Texture {
     pub fn load_from_file(path: &str, create_info: &TextureCreateInfo) -> Texture {
+        match Self::try_load_from_file(path, create_info) {
+            Ok(texture) => texture,
+            Err(_err) => panic!("Unable to load \"{}\"", path),
+        }
+    }
+
+    /// Same as [`Texture::load_from_file`], but returns the error if the image can not be decoded.
+    pub fn try_load_from_file(
+        path: &str,
+        create_info: &TextureCreateInfo,
+    ) -> Result<Texture, image::ImageError> {
         let root = std::env::current_dir().expect("Current working directory must be accessible");
         let path_buf = root.join("resources").join("tex").join(path);
 
-        let image = match image::open(path_buf) {
-            Ok(image) => image,
-            Err(_err) => panic!("Unable to load \"{}\"", path),
-        };
+        let image = image::open(path_buf)?;
 
         if !create_info.hdr {
             let image = image.to_rgba8();
@@ -88,7 +97,7 @@ impl Texture {
             let mut updated_create_info = create_info.clone();
             updated_create_info.extent = [width, height, 1];
 
-            Self::create::<u8>(image_data, updated_create_info)
+            Ok(Self::create::<u8>(image_data, updated_create_info))
         } else {
             let image = image.to_rgba32f();
             let (width, height) = (image.width(), image.height());
@@ -97,7 +106,7 @@ impl Texture {
             let mut updated_create_info = create_info.clone();
             updated_create_info.extent = [width, height, 1];
 
-            Self::create::<f32>(image_data, updated_create_info)
+            Ok(Self::create::<f32>(image_data, updated_create_info))
         }
     }
 
@@ -246,10 +255,6 @@ impl Texture {
     /// Generate mipmaps for the texture. Note that this function should be called only when
     /// the image is created with mipmap level info, and the first mipmap level is filled.
     pub fn generate_mipmaps(&self) {
-        let mip_levels = self.image_view.image().mip_levels();
-        let mut mip_width = self.info.extent[0];
-        let mut mip_height = self.info.extent[1];
-
         let mut builder = AutoCommandBufferBuilder::primary(
             VULKAN.command_buffer_allocator().clone(),
             VULKAN.graphics_queue().queue_family_index(),
@@ -257,6 +262,30 @@ impl Texture {
         )
         .unwrap();
 
+        self.record_mipmaps(&mut builder).unwrap();
+
+        // Finish recording the command buffer by calling `end`.
+        let command_buffer = builder.build().unwrap();
+
+        let future = sync::now(VULKAN.device().clone())
+            .then_execute(VULKAN.graphics_queue().clone(), command_buffer)
+            .unwrap()
+            .then_signal_fence_and_flush()
+            .unwrap();
+
+        future.wait(None).unwrap();
+    }
+
+    /// Records the blits of [`Texture::generate_mipmaps`] into `builder`, so that the mip chain
+    /// of a texture written every frame is regenerated on the GPU timeline.
+    pub fn record_mipmaps<L>(
+        &self,
+        builder: &mut AutoCommandBufferBuilder<L>,
+    ) -> Result<(), Box<ValidationError>> {
+        let mip_levels = self.image_view.image().mip_levels();
+        let mut mip_width = self.info.extent[0];
+        let mut mip_height = self.info.extent[1];
+
         // 循环生成每个mip层级
         for i in 1..mip_levels {
             let src_level = i - 1;
@@ -266,50 +295,39 @@ impl Texture {
             let (next_width, next_height) = ((mip_width >> 1).max(1), (mip_height >> 1).max(1));
 
             // 执行blit操作
-            builder
-                .blit_image(BlitImageInfo {
-                    // Same as above applies for blitting.
-                    src_image_layout: ImageLayout::General,
-                    dst_image_layout: ImageLayout::General,
-                    regions: [ImageBlit {
-                        src_subresource: ImageSubresourceLayers {
-                            aspects: ImageAspects::COLOR,
-                            mip_level: src_level,
-                            array_layers: 0..1,
-                        },
-                        src_offsets: [[0, 0, 0], [mip_width, mip_width, 1]],
-                        dst_subresource: ImageSubresourceLayers {
-                            aspects: ImageAspects::COLOR,
-                            mip_level: dst_level,
-                            array_layers: 0..1,
-                        },
-                        // Swapping the two corners results in flipped image.
-                        dst_offsets: [[0, 0, 0], [next_width, next_height, 1]],
-                        ..Default::default()
-                    }]
-                    .into(),
-                    filter: Filter::Linear,
-                    ..BlitImageInfo::images(
-                        self.image_view.image().clone(),
-                        self.image_view.image().clone(),
-                    )
-                })
-                .unwrap();
+            builder.blit_image(BlitImageInfo {
+                // Same as above applies for blitting.
+                src_image_layout: ImageLayout::General,
+                dst_image_layout: ImageLayout::General,
+                regions: [ImageBlit {
+                    src_subresource: ImageSubresourceLayers {
+                        aspects: ImageAspects::COLOR,
+                        mip_level: src_level,
+                        array_layers: 0..1,
+                    },
+                    src_offsets: [[0, 0, 0], [mip_width, mip_height, 1]],
+                    dst_subresource: ImageSubresourceLayers {
+                        aspects: ImageAspects::COLOR,
+                        mip_level: dst_level,
+                        array_layers: 0..1,
+                    },
+                    // Swapping the two corners results in flipped image.
+                    dst_offsets: [[0, 0, 0], [next_width, next_height, 1]],
+                    ..Default::default()
+                }]
+                .into(),
+                filter: Filter::Linear,
+                ..BlitImageInfo::images(
+                    self.image_view.image().clone(),
+                    self.image_view.image().clone(),
+                )
+            })?;
 
             mip_width = next_width;
             mip_height = next_height;
         }
 
-        // Finish recording the command buffer by calling `end`.
-        let command_buffer = builder.build().unwrap();
-
-        let future = sync::now(VULKAN.device().clone())
-            .then_execute(VULKAN.graphics_queue().clone(), command_buffer)
-            .unwrap()
-            .then_signal_fence_and_flush()
-            .unwrap();
-
-        future.wait(None).unwrap();
+        Ok(())
     }
 
     pub fn copy_to_mip_level(&self, target_mip_level: u32) {
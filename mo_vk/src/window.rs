@@ -401,15 +401,15 @@ impl WindowResizeConstraints {
         min_width = min_width.max(1.);
         min_height = min_height.max(1.);
         if max_width < min_width {
-            println!(
-                "The given maximum width {} is smaller than the minimum width {}",
+            tracing::warn!(
+                "Window - The given maximum width {} is smaller than the minimum width {}",
                 max_width, min_width
             );
             max_width = min_width;
         }
         if max_height < min_height {
-            println!(
-                "The given maximum height {} is smaller than the minimum height {}",
+            tracing::warn!(
+                "Window - The given maximum height {} is smaller than the minimum height {}",
                 max_height, min_height
             );
             max_height = min_height;
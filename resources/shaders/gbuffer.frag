@@ -3,6 +3,8 @@
 #extension GL_GOOGLE_include_directive: require
 
 #include "include/bindless.glsl"
+#include "include/gbuffer.glsl"
+#include "include/pbr.glsl"
 #include "include/utility.glsl"
 
 layout (location = 0) in vec2 uv0;
@@ -21,6 +23,8 @@ layout (location = 2) out vec4 out_gbuffer_normal;
 layout (location = 3) out vec4 out_gbuffer_emissive;
 layout (location = 4) out vec4 out_gbuffer_pbr;
 layout (location = 5) out vec2 out_gbuffer_velocity;
+layout (location = 6) out uvec4 out_gbuffer_material;
+layout (location = 7) out uvec2 out_gbuffer_material_ext;
 
 layout (push_constant) uniform PushConsts {
     mat4 world;
@@ -32,40 +36,121 @@ layout (push_constant) uniform PushConsts {
 void main() {
     GltfMaterialGPU material = materialsSSBO.materials[pushConsts.mat_index];
 
-    vec2 base_color_uv = material.base_color_uv_set == 0 ? uv0 : uv1;
+    vec2 base_color_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_BASE_COLOR], material.base_color_uv_set, uv0, uv1);
     vec4 diffuse_color = texture(nonuniformEXT(sampledTextures[material.base_color_map]), base_color_uv);
 
-    vec2 normal_uv = material.normal_uv_set == 0 ? uv0 : uv1;
+    vec2 normal_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_NORMAL], material.normal_uv_set, uv0, uv1);
     vec4 normal_map = texture(nonuniformEXT(sampledTextures[material.normal_map]), normal_uv);
 
-    vec2 metallic_roughness_uv = material.metallic_roughness_uv_set == 0 ? uv0 : uv1;
+    vec2 metallic_roughness_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_METALLIC_ROUGHNESS], material.metallic_roughness_uv_set, uv0, uv1);
     float metallic = texture(nonuniformEXT(sampledTextures[material.metallic_roughness_map]), metallic_roughness_uv).b;
     float roughness = texture(nonuniformEXT(sampledTextures[material.metallic_roughness_map]), metallic_roughness_uv).g;
 
-    vec2 occlusion_uv = material.occlusion_uv_set == 0 ? uv0 : uv1;
+    vec2 occlusion_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_OCCLUSION], material.occlusion_uv_set, uv0, uv1);
     float occlusion = texture(nonuniformEXT(sampledTextures[material.occlusion_map]), occlusion_uv).r;
 
+    // Color textures are sRGB but loaded as UNORM, the factors are already linear.
+    diffuse_color.rgb = toLinear(diffuse_color.rgb);
+
     diffuse_color *= material.base_color_factor;
     roughness *= material.roughness_factor;
     metallic *= material.metallic_factor;
 
-    // From sRGB space to Linear space, since in gltf, we loaded everything in linear space.
-    diffuse_color.rgb = toLinear(diffuse_color.rgb);
-
     vec3 normal = normalize(in_normal);
+    vec3 clearcoat_normal = normal;
     if (in_tangent.xyz != vec3(0.0f))
     {
         normal = normalize(normal_map.xyz * 2.0 - 1.0);
         normal = normalize(in_tbn * normal);
     }
 
+    MaterialLayers layers;
+    layers.clearcoat = material.clearcoat_factors.x;
+    layers.clearcoatRoughness = material.clearcoat_factors.y;
+    layers.clearcoatNormal = clearcoat_normal;
+    if (layers.clearcoat > 0.0)
+    {
+        vec2 clearcoat_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_CLEARCOAT], material.clearcoat_uv_set, uv0, uv1);
+        layers.clearcoat *= texture(nonuniformEXT(sampledTextures[material.clearcoat_map]), clearcoat_uv).r;
+
+        vec2 clearcoat_roughness_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_CLEARCOAT_ROUGHNESS], material.clearcoat_roughness_uv_set, uv0, uv1);
+        layers.clearcoatRoughness *= texture(nonuniformEXT(sampledTextures[material.clearcoat_roughness_map]), clearcoat_roughness_uv).g;
+
+        if (in_tangent.xyz != vec3(0.0f))
+        {
+            vec2 clearcoat_normal_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_CLEARCOAT_NORMAL], material.clearcoat_normal_uv_set, uv0, uv1);
+            vec3 clearcoat_normal_map = texture(nonuniformEXT(sampledTextures[material.clearcoat_normal_map]), clearcoat_normal_uv).xyz;
+            layers.clearcoatNormal = normalize(in_tbn * normalize(clearcoat_normal_map * 2.0 - 1.0));
+        }
+    }
+
+    layers.sheenColor = material.sheen_factors.rgb;
+    layers.sheenRoughness = material.sheen_factors.a;
+    if (layers.sheenColor != vec3(0.0f))
+    {
+        vec2 sheen_color_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_SHEEN_COLOR], material.sheen_color_uv_set, uv0, uv1);
+        layers.sheenColor *= toLinear(texture(nonuniformEXT(sampledTextures[material.sheen_color_map]), sheen_color_uv).rgb);
+
+        vec2 sheen_roughness_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_SHEEN_ROUGHNESS], material.sheen_roughness_uv_set, uv0, uv1);
+        layers.sheenRoughness *= texture(nonuniformEXT(sampledTextures[material.sheen_roughness_map]), sheen_roughness_uv).a;
+    }
+
+    vec2 specular_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_SPECULAR], material.specular_uv_set, uv0, uv1);
+    vec2 specular_color_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_SPECULAR_COLOR], material.specular_color_uv_set, uv0, uv1);
+    vec3 specular_color = material.specular_factors.rgb * toLinear(texture(nonuniformEXT(sampledTextures[material.specular_color_map]), specular_color_uv).rgb);
+    layers.specularF0 = dielectricF0(material.transmission_factors.z, specular_color);
+    layers.specularWeight = material.specular_factors.a * texture(nonuniformEXT(sampledTextures[material.specular_map]), specular_uv).a;
+
+    // KHR_materials_pbrSpecularGlossiness, shaded as a dielectric reflecting the specular color
+    if (material.material_type == MATERIAL_ID_SPECULAR_GLOSSINESS)
+    {
+        vec4 specular_glossiness = texture(nonuniformEXT(sampledTextures[material.metallic_roughness_map]), metallic_roughness_uv);
+        metallic = 0.0;
+        roughness = 1.0 - specular_glossiness.a * material.specular_glossiness_factors.a;
+        layers.specularF0 = toLinear(specular_glossiness.rgb) * material.specular_glossiness_factors.rgb;
+        layers.specularWeight = 1.0;
+    }
+
+    vec2 iridescence_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_IRIDESCENCE], material.iridescence_uv_set, uv0, uv1);
+    vec2 iridescence_thickness_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_IRIDESCENCE_THICKNESS], material.iridescence_thickness_uv_set, uv0, uv1);
+    float iridescence = material.iridescence_factors.x * texture(nonuniformEXT(sampledTextures[material.iridescence_map]), iridescence_uv).r;
+    float iridescence_thickness = mix(material.iridescence_factors.z, material.iridescence_factors.w, texture(nonuniformEXT(sampledTextures[material.iridescence_thickness_map]), iridescence_thickness_uv).g);
+
+    // The anisotropy direction is given in tangent space and needs tangents, like the normal map.
+    float anisotropy = 0.0;
+    vec3 anisotropy_direction = vec3(1.0, 0.0, 0.0);
+    if (material.anisotropy_factors.x > 0.0 && in_tangent.xyz != vec3(0.0f))
+    {
+        vec2 direction = vec2(1.0, 0.0);
+        anisotropy = material.anisotropy_factors.x;
+        if (material.anisotropy_factors.z > 0.0)
+        {
+            vec2 anisotropy_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_ANISOTROPY], material.anisotropy_uv_set, uv0, uv1);
+            vec3 anisotropy_map = texture(nonuniformEXT(sampledTextures[material.anisotropy_map]), anisotropy_uv).rgb;
+            direction = anisotropy_map.rg * 2.0 - 1.0;
+            anisotropy *= anisotropy_map.b;
+        }
+        float rotation = material.anisotropy_factors.y;
+        direction = mat2(cos(rotation), sin(rotation), -sin(rotation), cos(rotation)) * direction;
+        anisotropy_direction = normalize(in_tbn * vec3(direction, 0.0));
+    }
+    layers.anisotropy = anisotropy;
+    layers.anisotropyDirection = anisotropy_direction;
+    layers.iridescence = iridescence;
+    layers.iridescenceIor = material.iridescence_factors.y;
+    layers.iridescenceThickness = iridescence_thickness;
+
+    packMaterialLayers(layers, out_gbuffer_material, out_gbuffer_material_ext);
+
     out_gbuffer_base_color = vec4(diffuse_color.rgb, 1.0);
     out_gbuffer_position = in_clip_space_pos;
     out_gbuffer_normal = vec4(normal, 1.0);
-    out_gbuffer_pbr = vec4(occlusion, roughness, metallic, 1.0);
+    out_gbuffer_pbr = vec4(occlusion, roughness, metallic, packMaterialId(material.material_type));
 
-    vec2 emissive_uv = material.emissive_uv_set == 0 ? uv0 : uv1;
-    out_gbuffer_emissive = texture(nonuniformEXT(sampledTextures[uint(material.emissive_map)]), emissive_uv) * vec4(material.emissive_factor);
+    vec2 emissive_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_EMISSIVE], material.emissive_uv_set, uv0, uv1);
+    vec4 emissive = texture(nonuniformEXT(sampledTextures[uint(material.emissive_map)]), emissive_uv);
+    emissive.rgb = toLinear(emissive.rgb);
+    out_gbuffer_emissive = vec4(emissive.rgb * material.emissive_factor.rgb * material.emissive_factor.a, 1.0);
     out_gbuffer_position = vec4(in_model_space_pos.xyz, 1.0);
 
     {
@@ -1,6 +1,33 @@
 #ifndef SHADER_INCLUDE_BINDLESS
 #define SHADER_INCLUDE_BINDLESS
 
+// Texture slots of a material, the values of ETextureSlot
+const uint TEXTURE_SLOT_BASE_COLOR = 0;
+const uint TEXTURE_SLOT_NORMAL = 1;
+const uint TEXTURE_SLOT_METALLIC_ROUGHNESS = 2;
+const uint TEXTURE_SLOT_OCCLUSION = 3;
+const uint TEXTURE_SLOT_EMISSIVE = 4;
+const uint TEXTURE_SLOT_CLEARCOAT = 5;
+const uint TEXTURE_SLOT_CLEARCOAT_ROUGHNESS = 6;
+const uint TEXTURE_SLOT_CLEARCOAT_NORMAL = 7;
+const uint TEXTURE_SLOT_SHEEN_COLOR = 8;
+const uint TEXTURE_SLOT_SHEEN_ROUGHNESS = 9;
+const uint TEXTURE_SLOT_TRANSMISSION = 10;
+const uint TEXTURE_SLOT_THICKNESS = 11;
+const uint TEXTURE_SLOT_SPECULAR = 12;
+const uint TEXTURE_SLOT_SPECULAR_COLOR = 13;
+const uint TEXTURE_SLOT_IRIDESCENCE = 14;
+const uint TEXTURE_SLOT_IRIDESCENCE_THICKNESS = 15;
+const uint TEXTURE_SLOT_ANISOTROPY = 16;
+const uint TEXTURE_SLOT_COUNT = 17;
+
+// KHR_texture_transform as the rows of an affine 2D matrix, w unused
+struct UvTransform
+{
+    vec4 row0;
+    vec4 row1;
+};
+
 struct GltfMaterialGPU
 {
     uint base_color_map;
@@ -13,8 +40,11 @@ struct GltfMaterialGPU
     uint metallic_roughness_uv_set;
     uint occlusion_uv_set;
     uint emissive_uv_set;
-    vec2 padding;
+    // MaterialType, written to the GBuffer as the material ID
+    uint material_type;
+    uint padding;
     vec4 base_color_factor;
+    // rgb = emissive factor, a = emissive strength
     vec4 emissive_factor;
     float metallic_factor;
     float roughness_factor;
@@ -26,8 +56,70 @@ struct GltfMaterialGPU
     // x = type (0 = lambertian, 1 = metal, 2 = dielectric, 3 = diffuse light)
     // y = metal -> fuzz, dielectric -> index of refractions
     vec4 raytrace_properties;
+
+    // KHR_materials_clearcoat and KHR_materials_sheen
+    uint clearcoat_map;
+    uint clearcoat_roughness_map;
+    uint clearcoat_normal_map;
+    uint sheen_color_map;
+    uint sheen_roughness_map;
+    uint clearcoat_uv_set;
+    uint clearcoat_roughness_uv_set;
+    uint clearcoat_normal_uv_set;
+    uint sheen_color_uv_set;
+    uint sheen_roughness_uv_set;
+    vec2 padding2;
+    // x = clearcoat factor, y = clearcoat roughness factor
+    vec4 clearcoat_factors;
+    // rgb = sheen color factor, a = sheen roughness factor
+    vec4 sheen_factors;
+
+    // KHR_materials_transmission, KHR_materials_volume and KHR_materials_ior
+    uint transmission_map;
+    uint thickness_map;
+    uint transmission_uv_set;
+    uint thickness_uv_set;
+    // x = transmission factor, y = thickness factor, z = ior
+    vec4 transmission_factors;
+    // rgb = attenuation color, a = attenuation distance
+    vec4 attenuation;
+
+    // KHR_materials_specular, the ior above also sets the reflectance of dielectrics
+    uint specular_map;
+    uint specular_color_map;
+    uint specular_uv_set;
+    uint specular_color_uv_set;
+    // rgb = specular color factor, a = specular factor
+    vec4 specular_factors;
+
+    // KHR_materials_iridescence and KHR_materials_anisotropy
+    uint iridescence_map;
+    uint iridescence_thickness_map;
+    uint anisotropy_map;
+    uint iridescence_uv_set;
+    uint iridescence_thickness_uv_set;
+    uint anisotropy_uv_set;
+    vec2 padding3;
+    // x = iridescence factor, y = iridescence ior, z = minimum thickness, w = maximum thickness (nm)
+    vec4 iridescence_factors;
+    // x = anisotropy strength, y = anisotropy rotation, z = 1 with a direction texture
+    vec4 anisotropy_factors;
+
+    // KHR_materials_pbrSpecularGlossiness, rgb = specular factor, a = glossiness factor. The
+    // diffuse and specular-glossiness maps take the place of base color and metallic-roughness.
+    vec4 specular_glossiness_factors;
+
+    // KHR_texture_transform, indexed by TEXTURE_SLOT_*. Texture coordinate overrides are already
+    // applied to the uv sets above.
+    UvTransform uv_transforms[TEXTURE_SLOT_COUNT];
 };
 
+vec2 transformUv(const UvTransform transform, uint uv_set, vec2 uv0, vec2 uv1)
+{
+    vec3 uv = vec3(uv_set == 0 ? uv0 : uv1, 1.0);
+    return vec2(dot(transform.row0.xyz, uv), dot(transform.row1.xyz, uv));
+}
+
 layout (set = 0, binding = 0) uniform sampler2D sampledTextures[];
 
 layout (set = 1, binding = 0) readonly buffer MaterialsSSBO
@@ -0,0 +1,110 @@
+#ifndef _GBUFFER_H_
+#define _GBUFFER_H_
+
+// Layers of the extension materials, packed into the uint material attachments of the GBuffer.
+// The first one:
+//   x = clearcoat normal, octahedral encoded halfs
+//   y = clearcoat factor, clearcoat roughness (unorm8), zw unused
+//   z = sheen color, sheen roughness (unorm8)
+//   w = square root of the dielectric F0, specular weight (unorm8), the root keeps the precision
+//       of the usual F0 around 0.04
+// The second one:
+//   x = anisotropy direction, octahedral encoded halfs
+//   y = anisotropy strength, iridescence factor, (iridescence ior - 1) / 2,
+//       iridescence thickness / IRIDESCENCE_MAX_THICKNESS (unorm8)
+// A cleared texel has neither a clearcoat nor a sheen, nor a specular reflection.
+struct MaterialLayers
+{
+    float clearcoat;
+    float clearcoatRoughness;
+    vec3 clearcoatNormal;
+    vec3 sheenColor;
+    float sheenRoughness;
+    vec3 specularF0;
+    float specularWeight;
+    float anisotropy;
+    vec3 anisotropyDirection;
+    float iridescence;
+    float iridescenceIor;
+    // Thin-film thickness in nanometers
+    float iridescenceThickness;
+};
+
+const float IRIDESCENCE_MAX_THICKNESS = 1200.0;
+
+// Material IDs stored in the alpha of the pbr attachment, the values of MaterialType
+const uint MATERIAL_ID_METALLIC_ROUGHNESS = 0x1u;
+const uint MATERIAL_ID_SPECULAR_GLOSSINESS = 0x2u;
+const uint MATERIAL_ID_UNLIT = 0x80u;
+
+float packMaterialId(uint id)
+{
+    return float(id) / 255.0;
+}
+
+uint unpackMaterialId(float packed)
+{
+    return uint(round(packed * 255.0));
+}
+
+vec2 octWrap(vec2 v)
+{
+    return (1.0 - abs(v.yx)) * vec2(v.x >= 0.0 ? 1.0 : -1.0, v.y >= 0.0 ? 1.0 : -1.0);
+}
+
+vec2 octEncode(vec3 n)
+{
+    n /= abs(n.x) + abs(n.y) + abs(n.z);
+    n.xy = n.z >= 0.0 ? n.xy : octWrap(n.xy);
+    return n.xy;
+}
+
+vec3 octDecode(vec2 f)
+{
+    vec3 n = vec3(f.x, f.y, 1.0 - abs(f.x) - abs(f.y));
+    float t = clamp(-n.z, 0.0, 1.0);
+    n.x += n.x >= 0.0 ? -t : t;
+    n.y += n.y >= 0.0 ? -t : t;
+    return normalize(n);
+}
+
+void packMaterialLayers(const MaterialLayers layers, out uvec4 packed, out uvec2 packedExt)
+{
+    packed = uvec4(
+        packHalf2x16(octEncode(layers.clearcoatNormal)),
+        packUnorm4x8(vec4(layers.clearcoat, layers.clearcoatRoughness, 0.0, 0.0)),
+        packUnorm4x8(vec4(layers.sheenColor, layers.sheenRoughness)),
+        packUnorm4x8(vec4(sqrt(layers.specularF0), layers.specularWeight)));
+    packedExt = uvec2(
+        packHalf2x16(octEncode(layers.anisotropyDirection)),
+        packUnorm4x8(vec4(
+            layers.anisotropy,
+            layers.iridescence,
+            (layers.iridescenceIor - 1.0) * 0.5,
+            layers.iridescenceThickness / IRIDESCENCE_MAX_THICKNESS)));
+}
+
+MaterialLayers unpackMaterialLayers(uvec4 packed, uvec2 packedExt)
+{
+    vec4 clearcoat = unpackUnorm4x8(packed.y);
+    vec4 sheen = unpackUnorm4x8(packed.z);
+    vec4 specular = unpackUnorm4x8(packed.w);
+    vec4 anisotropyIridescence = unpackUnorm4x8(packedExt.y);
+
+    MaterialLayers layers;
+    layers.clearcoat = clearcoat.x;
+    layers.clearcoatRoughness = clearcoat.y;
+    layers.clearcoatNormal = layers.clearcoat > 0.0 ? octDecode(unpackHalf2x16(packed.x)) : vec3(0.0, 0.0, 1.0);
+    layers.sheenColor = sheen.rgb;
+    layers.sheenRoughness = sheen.a;
+    layers.specularF0 = specular.rgb * specular.rgb;
+    layers.specularWeight = specular.a;
+    layers.anisotropy = anisotropyIridescence.x;
+    layers.anisotropyDirection = layers.anisotropy > 0.0 ? octDecode(unpackHalf2x16(packedExt.x)) : vec3(1.0, 0.0, 0.0);
+    layers.iridescence = anisotropyIridescence.y;
+    layers.iridescenceIor = anisotropyIridescence.z * 2.0 + 1.0;
+    layers.iridescenceThickness = anisotropyIridescence.w * IRIDESCENCE_MAX_THICKNESS;
+    return layers;
+}
+
+#endif
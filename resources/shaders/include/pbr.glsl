@@ -160,6 +160,147 @@ vec3 fresnelSchlickRoughness(float cosTheta, vec3 F0, float roughness)
     return F0 + (max(vec3(1.0 - roughness), F0) - F0) * pow(clamp(1.0 - cosTheta, 0.0, 1.0), 5.0);
 }
 
+// Reflectance at normal incidence of a dielectric, tinted by the specular color of KHR_materials_specular
+vec3 dielectricF0(float ior, vec3 specularColor)
+{
+    float f0 = pow((ior - 1.0) / (ior + 1.0), 2.0);
+    return min(f0 * specularColor, vec3(1.0));
+}
+
+// Charlie sheen distribution from "Production Friendly Microfacet Sheen BRDF", Estevez and Kulla 2017
+float DistributionCharlie(float NdotH, float roughness)
+{
+    float alpha = max(roughness * roughness, 0.0001);
+    float invAlpha = 1.0 / alpha;
+    float sin2h = max(1.0 - NdotH * NdotH, 0.0078125);
+    return (2.0 + invAlpha) * pow(sin2h, invAlpha * 0.5) / TWO_PI;
+}
+
+// Sheen visibility from "Physically Based Rendering in Filament", Neubelt and Pettineo 2013
+float VisibilityNeubelt(float NdotV, float NdotL)
+{
+    return 1.0 / (4.0 * (NdotL + NdotV - NdotL * NdotV) + 0.0001);
+}
+
+// Directional albedo of the Charlie sheen lobe, analytic fit of the LUT used by three.js
+float sheenAlbedo(float NdotV, float roughness)
+{
+    float r2 = roughness * roughness;
+    float a = roughness < 0.25 ? -339.2 * r2 + 161.4 * roughness - 25.9 : -8.48 * r2 + 14.3 * roughness - 9.95;
+    float b = roughness < 0.25 ? 44.0 * r2 - 23.7 * roughness + 3.26 : 1.97 * r2 - 3.27 * roughness + 0.72;
+    float DG = exp(a * NdotV + b) + (roughness < 0.25 ? 0.0 : 0.1 * (roughness - 0.25));
+    return clamp(DG * One_OVER_PI, 0.0, 1.0);
+}
+
+// Anisotropic GGX distribution and height-correlated visibility, as in the glTF sample viewer.
+// at and ab are the alpha roughness along the tangent and the bitangent.
+float DistributionGGXAnisotropic(float NdotH, float TdotH, float BdotH, float at, float ab)
+{
+    float a2 = at * ab;
+    vec3 f = vec3(ab * TdotH, at * BdotH, a2 * NdotH);
+    float w2 = a2 / dot(f, f);
+    return a2 * w2 * w2 / PI;
+}
+
+float VisibilityGGXAnisotropic(float NdotL, float NdotV, float BdotV, float TdotV, float TdotL, float BdotL, float at, float ab)
+{
+    float GGXV = NdotL * length(vec3(at * TdotV, ab * BdotV, NdotV));
+    float GGXL = NdotV * length(vec3(at * TdotL, ab * BdotL, NdotL));
+    return clamp(0.5 / (GGXV + GGXL), 0.0, 1.0);
+}
+
+// Thin-film interference from "A Practical Extension to Microfacet Theory for the Modeling of
+// Varying Iridescence", Belcour and Barla 2017, following the glTF sample viewer.
+const mat3 XYZ_TO_REC709 = mat3(
+     3.2404542, -0.9692660,  0.0556434,
+    -1.5371385,  1.8760108, -0.2040259,
+    -0.4985314,  0.0415560,  1.0572252
+);
+
+vec3 fresnel0ToIor(vec3 fresnel0)
+{
+    vec3 sqrtF0 = sqrt(fresnel0);
+    return (vec3(1.0) + sqrtF0) / (vec3(1.0) - sqrtF0);
+}
+
+vec3 iorToFresnel0(vec3 transmittedIor, float incidentIor)
+{
+    vec3 r = (transmittedIor - vec3(incidentIor)) / (transmittedIor + vec3(incidentIor));
+    return r * r;
+}
+
+float iorToFresnel0(float transmittedIor, float incidentIor)
+{
+    float r = (transmittedIor - incidentIor) / (transmittedIor + incidentIor);
+    return r * r;
+}
+
+// XYZ sensitivity curves evaluated in Fourier space, OPD is the optical path difference in nanometers
+vec3 evalSensitivity(float OPD, vec3 shift)
+{
+    float phase = TWO_PI * OPD * 1.0e-9;
+    vec3 val = vec3(5.4856e-13, 4.4201e-13, 5.2481e-13);
+    vec3 pos = vec3(1.6810e+06, 1.7953e+06, 2.2084e+06);
+    vec3 var = vec3(4.3278e+09, 9.3046e+09, 6.6121e+09);
+
+    vec3 xyz = val * sqrt(TWO_PI * var) * cos(pos * phase + shift) * exp(-phase * phase * var);
+    xyz.x += 9.7470e-14 * sqrt(TWO_PI * 4.5282e+09) * cos(2.2399e+06 * phase + shift[0]) * exp(-4.5282e+09 * phase * phase);
+    xyz /= 1.0685e-7;
+
+    return XYZ_TO_REC709 * xyz;
+}
+
+// Fresnel of a thin film of the given IOR and thickness (nm) on top of a base with reflectance baseF0
+vec3 evalIridescence(float outsideIor, float filmIor, float cosTheta1, float thickness, vec3 baseF0)
+{
+    // The film fades out as it gets thinner.
+    float iridescenceIor = mix(outsideIor, filmIor, smoothstep(0.0, 0.03, thickness));
+    float sinTheta2Sq = pow(outsideIor / iridescenceIor, 2.0) * (1.0 - cosTheta1 * cosTheta1);
+
+    // Total internal reflection
+    float cosTheta2Sq = 1.0 - sinTheta2Sq;
+    if (cosTheta2Sq < 0.0)
+    {
+        return vec3(1.0);
+    }
+    float cosTheta2 = sqrt(cosTheta2Sq);
+
+    // First interface
+    float R0 = iorToFresnel0(iridescenceIor, outsideIor);
+    float R12 = fresnelSchlick(cosTheta1, vec3(R0)).x;
+    float T121 = 1.0 - R12;
+    float phi12 = iridescenceIor < outsideIor ? PI : 0.0;
+    float phi21 = PI - phi12;
+
+    // Second interface
+    vec3 baseIor = fresnel0ToIor(clamp(baseF0, 0.0, 0.9999));
+    vec3 R1 = iorToFresnel0(baseIor, iridescenceIor);
+    vec3 R23 = fresnelSchlick(cosTheta2, R1);
+    vec3 phi23 = mix(vec3(0.0), vec3(PI), lessThan(baseIor, vec3(iridescenceIor)));
+
+    // Phase shift
+    float OPD = 2.0 * iridescenceIor * thickness * cosTheta2;
+    vec3 phi = vec3(phi21) + phi23;
+
+    // Compound terms
+    vec3 R123 = clamp(R12 * R23, 1e-5, 0.9999);
+    vec3 r123 = sqrt(R123);
+    vec3 Rs = T121 * T121 * R23 / (vec3(1.0) - R123);
+
+    // Reflectance term for m = 0, then the pairs of diracs for m > 0
+    vec3 I = R12 + Rs;
+    vec3 Cm = Rs - T121;
+    for (int m = 1; m <= 2; ++m)
+    {
+        Cm *= r123;
+        vec3 Sm = 2.0 * evalSensitivity(float(m) * OPD, float(m) * phi);
+        I += Cm * Sm;
+    }
+
+    // Out of gamut colors may be negative.
+    return max(I, vec3(0.0));
+}
+
 struct PixelParams
 {
     vec3 position;
@@ -168,9 +309,40 @@ struct PixelParams
     float metallic;
     float roughness;
     float occlusion;
-    uint padding;
+    // KHR_materials_clearcoat, a clearcoat of 0 disables the layer
+    float clearcoat;
+    float clearcoatRoughness;
+    vec3 clearcoatNormal;
+    // KHR_materials_sheen, a black sheen color disables the layer
+    vec3 sheenColor;
+    float sheenRoughness;
+    // KHR_materials_specular and KHR_materials_ior, the weight only scales the dielectric specular
+    vec3 specularF0;
+    float specularWeight;
+    // KHR_materials_anisotropy, the direction is the world space tangent the highlight stretches along
+    float anisotropy;
+    vec3 anisotropyDirection;
+    // KHR_materials_iridescence, a factor of 0 disables the thin film
+    float iridescence;
+    float iridescenceIor;
+    float iridescenceThickness;
 };
 
+// Blends the Fresnel of the dielectric and the metal base towards the one of the thin film. As in
+// the glTF sample viewer, the thin film is evaluated at the view angle for every light.
+void applyIridescence(const PixelParams pixel, float NdotV, inout vec3 dielectricF, inout vec3 metalF)
+{
+    if (pixel.iridescence > 0.0)
+    {
+        vec3 dielectricIridescence = evalIridescence(1.0, pixel.iridescenceIor, NdotV, pixel.iridescenceThickness, pixel.specularF0);
+        vec3 metalIridescence = evalIridescence(1.0, pixel.iridescenceIor, NdotV, pixel.iridescenceThickness, pixel.baseColor);
+        dielectricF = mix(dielectricF, dielectricIridescence, pixel.iridescence);
+        metalF = mix(metalF, metalIridescence, pixel.iridescence);
+    }
+}
+
+// x = type (0 = directional, 1 = point, 2 = spot), y = range (0 = unlimited),
+// zw = cone scale and offset of spot lights. attenuation.x = intensity.
 struct GpuLight
 {
     vec4 type_range_spot_id;
@@ -190,9 +362,6 @@ vec3 surfaceShading(const PixelParams pixel, GpuLight light, const vec3 eyePos,
     vec3 V = normalize(eyePos - pixel.position);
     vec3 R = reflect(V, N);
 
-    vec3 F0 = vec3(0.04);
-    F0 = mix(F0, pixel.baseColor, pixel.metallic);
-
     vec3 L = vec3(0.0);
     float attenuation = 1.0f;
     vec3 posToLight = light.position.xyz - pixel.position;
@@ -202,18 +371,24 @@ vec3 surfaceShading(const PixelParams pixel, GpuLight light, const vec3 eyePos,
         L = normalize(light.direction.xyz * vec3(-1, 1, -1));
         attenuation = light.attenuation.x;
     }
-    else if (light.type_range_spot_id.x == 1.0f)// Point light
-    {
-        L = normalize(posToLight);
-        float d = length(posToLight);
-        attenuation = 1.0f / dot(light.attenuation.xyz, vec3(1.0f, d, d * d));
-    }
-    else if (light.type_range_spot_id.x == 2.0f)// Spot light
+    else// Point and spot lights
     {
         L = normalize(posToLight);
         float d = length(posToLight);
-        float spot = pow(max(dot(L, normalize(light.direction.xyz)), 0.0f), light.type_range_spot_id.z);
-        attenuation = spot / dot(light.attenuation.xyz, vec3(1.0f, d, d * d));
+        // Inverse square falloff, smoothly windowed to zero at the range as in KHR_lights_punctual
+        attenuation = light.attenuation.x / max(d * d, 0.0001f);
+        float range = light.type_range_spot_id.y;
+        if (range > 0.0f)
+        {
+            attenuation *= pow(clamp(1.0f - pow(d / range, 4.0f), 0.0f, 1.0f), 2.0f);
+        }
+        if (light.type_range_spot_id.x == 2.0f)// Spot light
+        {
+            // Like for directional lights, the direction points back against the cone.
+            float cd = dot(normalize(light.direction.xyz * vec3(-1, 1, -1)), L);
+            float cone = clamp(cd * light.type_range_spot_id.z + light.type_range_spot_id.w, 0.0f, 1.0f);
+            attenuation *= cone * cone;
+        }
     }
 
     // Reflectance equation
@@ -223,22 +398,70 @@ vec3 surfaceShading(const PixelParams pixel, GpuLight light, const vec3 eyePos,
     vec3 radiance = light.color.rgb * attenuation * lightColorFactor;
 
     // Cook-torrance brdf
-    float NDF = DistributionGGX(N, H, pixel.roughness);
-    float G = GeometrySmith(N, V, L, pixel.roughness);
-    vec3 F = fresnelSchlick(max(dot(H, V), 0.0), F0);
-
-    vec3 kS = F;
-    vec3 kD = vec3(1.0) - kS;
+    float NdotL = max(dot(N, L), 0.0);
+    float VdotH = max(dot(H, V), 0.0);
+    vec3 dielectricF = fresnelSchlick(VdotH, pixel.specularF0);
+    vec3 metalF = fresnelSchlick(VdotH, pixel.baseColor);
+    applyIridescence(pixel, max(dot(N, V), 0.0), dielectricF, metalF);
+    dielectricF *= pixel.specularWeight;
+    vec3 F = mix(dielectricF, metalF, pixel.metallic);
+
+    // The diffuse base only gets the light the dielectric specular does not reflect.
+    vec3 kD = vec3(1.0) - dielectricF;
     kD *= 1.0 - pixel.metallic;
 
-    vec3 numerator = NDF * G * F;
-    float denominator = 4.0 * max(dot(N, V), 0.0) * max(dot(N, L), 0.0) + 0.0001;
-    vec3 specular = numerator / denominator;
+    vec3 specular;
+    if (pixel.anisotropy > 0.0)
+    {
+        // The roughness along the tangent grows with the anisotropy strength.
+        vec3 T = normalize(pixel.anisotropyDirection - N * dot(N, pixel.anisotropyDirection));
+        vec3 B = cross(N, T);
+        float ab = max(pixel.roughness * pixel.roughness, 0.001);
+        float at = mix(ab, 1.0, pixel.anisotropy * pixel.anisotropy);
+
+        float D = DistributionGGXAnisotropic(max(dot(N, H), 0.0), dot(T, H), dot(B, H), at, ab);
+        float Vis = VisibilityGGXAnisotropic(NdotL, max(dot(N, V), 0.0), dot(B, V), dot(T, V), dot(T, L), dot(B, L), at, ab);
+        specular = F * D * Vis;
+    }
+    else
+    {
+        float NDF = DistributionGGX(N, H, pixel.roughness);
+        float G = GeometrySmith(N, V, L, pixel.roughness);
+
+        vec3 numerator = NDF * G * F;
+        float denominator = 4.0 * max(dot(N, V), 0.0) * NdotL + 0.0001;
+        specular = numerator / denominator;
+    }
 
     // Add to outgoing radiance Lo
-    float NdotL = max(dot(N, L), 0.0);
     color = (kD * pixel.baseColor / PI + specular) * radiance * NdotL;
 
+    // The sheen lobe sits on top of the base and takes away the energy it reflects.
+    if (pixel.sheenColor != vec3(0.0))
+    {
+        float NdotV = max(dot(N, V), 0.0);
+        float sheenMax = max(max(pixel.sheenColor.r, pixel.sheenColor.g), pixel.sheenColor.b);
+        float albedoScaling = 1.0 - sheenMax * max(sheenAlbedo(NdotV, pixel.sheenRoughness), sheenAlbedo(NdotL, pixel.sheenRoughness));
+
+        float sheenD = DistributionCharlie(max(dot(N, H), 0.0), pixel.sheenRoughness);
+        float sheenV = VisibilityNeubelt(NdotV, NdotL);
+        color = color * albedoScaling + pixel.sheenColor * sheenD * sheenV * radiance * NdotL;
+    }
+
+    // The clearcoat is a dielectric GGX layer with an IOR of 1.5 and its own normal.
+    if (pixel.clearcoat > 0.0)
+    {
+        vec3 Nc = pixel.clearcoatNormal;
+        float NcdotL = max(dot(Nc, L), 0.0);
+
+        float clearcoatD = DistributionGGX(Nc, H, pixel.clearcoatRoughness);
+        float clearcoatG = GeometrySmith(Nc, V, L, pixel.clearcoatRoughness);
+        float clearcoatF = fresnelSchlick(max(dot(H, V), 0.0), vec3(0.04)).x * pixel.clearcoat;
+        float clearcoatSpecular = clearcoatD * clearcoatG * clearcoatF / (4.0 * max(dot(Nc, V), 0.0) * NcdotL + 0.0001);
+
+        color = color * (1.0 - clearcoatF) + clearcoatSpecular * radiance * NcdotL;
+    }
+
     return color;
 }
 
@@ -247,12 +470,22 @@ vec3 imageBasedLighting(const PixelParams pixel, const vec3 eyePos, samplerCube
     vec3 V = normalize(eyePos - pixel.position);
     vec3 R = reflect(V, pixel.normal.xyz);// Note: -1 indicates that the specular cubemp not being as expected
 
-    vec3 F0 = vec3(0.04);
-    F0 = mix(F0, pixel.baseColor, pixel.metallic);
+    // Anisotropic surfaces reflect along a normal bent towards the anisotropy direction.
+    vec3 specularR = R;
+    if (pixel.anisotropy > 0.0)
+    {
+        vec3 anisotropicTangent = cross(pixel.anisotropyDirection, V);
+        vec3 anisotropicNormal = cross(anisotropicTangent, pixel.anisotropyDirection);
+        float bendFactor = 1.0 - pixel.anisotropy * (1.0 - pixel.roughness);
+        vec3 bentNormal = normalize(mix(anisotropicNormal, pixel.normal.xyz, pow(bendFactor, 4.0)));
+        specularR = reflect(V, bentNormal);
+    }
 
-    vec3 F = fresnelSchlickRoughness(max(dot(pixel.normal.xyz, V), 0.0), F0, pixel.roughness);
-    vec3 kS = F;
-    vec3 kD = 1.0 - kS;
+    float NdotV = max(dot(pixel.normal.xyz, V), 0.0);
+    vec3 dielectricF = fresnelSchlickRoughness(NdotV, pixel.specularF0, pixel.roughness);
+    vec3 metalF = fresnelSchlickRoughness(NdotV, pixel.baseColor, pixel.roughness);
+    applyIridescence(pixel, NdotV, dielectricF, metalF);
+    vec3 kD = 1.0 - pixel.specularWeight * dielectricF;
     kD *= 1.0 - pixel.metallic;
 
     vec3 irradiance = texture(in_irradiance_map, pixel.normal.xyz).rgb;
@@ -261,12 +494,39 @@ vec3 imageBasedLighting(const PixelParams pixel, const vec3 eyePos, samplerCube
     // Sample both the pre-filter map and the BRDF lut and combine them together as per the Split-Sum approximation to get the IBL specular part.
     // Note: 1 - roughness, same as Vulkan-glTF-PBR but differs from LearnOpenGL
     const float MAX_REFLECTION_LOD = 7.0;
-    vec3 prefilteredColor = textureLod(in_specular_map, R, pixel.roughness * MAX_REFLECTION_LOD).rgb;
-    vec2 brdf = texture(in_brdf_lut, vec2(max(dot(pixel.normal.xyz, V), 0.0), 1.0f - pixel.roughness)).rg;
-    vec3 specular = prefilteredColor * (F * brdf.x + brdf.y);
+    vec3 prefilteredColor = textureLod(in_specular_map, specularR, pixel.roughness * MAX_REFLECTION_LOD).rgb;
+    vec2 brdf = texture(in_brdf_lut, vec2(NdotV, 1.0f - pixel.roughness)).rg;
+    vec3 dielectricSpecular = pixel.specularWeight * (dielectricF * brdf.x + brdf.y);
+    vec3 metalSpecular = metalF * brdf.x + brdf.y;
+    vec3 specular = prefilteredColor * mix(dielectricSpecular, metalSpecular, pixel.metallic);
 
     vec3 ambient = (kD * diffuse + specular) * pixel.occlusion;
 
+    // Sheen, the prefiltered map stands in for a Charlie filtered one.
+    if (pixel.sheenColor != vec3(0.0))
+    {
+        float sheenMax = max(max(pixel.sheenColor.r, pixel.sheenColor.g), pixel.sheenColor.b);
+        float albedo = sheenAlbedo(NdotV, pixel.sheenRoughness);
+
+        vec3 sheenColor = textureLod(in_specular_map, R, pixel.sheenRoughness * MAX_REFLECTION_LOD).rgb;
+        ambient = ambient * (1.0 - sheenMax * albedo) + pixel.sheenColor * sheenColor * albedo * pixel.occlusion;
+    }
+
+    // Clearcoat, sampled along the reflection of its own normal.
+    if (pixel.clearcoat > 0.0)
+    {
+        vec3 Nc = pixel.clearcoatNormal;
+        vec3 Rc = reflect(V, Nc);
+        float NcdotV = max(dot(Nc, V), 0.0);
+
+        float clearcoatF = fresnelSchlickRoughness(NcdotV, vec3(0.04), pixel.clearcoatRoughness).x * pixel.clearcoat;
+        vec3 clearcoatColor = textureLod(in_specular_map, Rc, pixel.clearcoatRoughness * MAX_REFLECTION_LOD).rgb;
+        vec2 clearcoatBrdf = texture(in_brdf_lut, vec2(NcdotV, 1.0f - pixel.clearcoatRoughness)).rg;
+        vec3 clearcoatSpecular = clearcoatColor * (0.04 * clearcoatBrdf.x + clearcoatBrdf.y) * pixel.clearcoat;
+
+        ambient = ambient * (1.0 - clearcoatF) + clearcoatSpecular * pixel.occlusion;
+    }
+
     return ambient;
 }
 
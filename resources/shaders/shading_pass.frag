@@ -3,6 +3,7 @@
 #extension GL_GOOGLE_include_directive: require
 #extension GL_EXT_scalar_block_layout: enable
 
+#include "include/gbuffer.glsl"
 #include "include/pbr.glsl"
 #include "include/shadow.glsl"
 #include "include/tonemap.glsl"
@@ -24,6 +25,8 @@ layout (set = 1, binding = 7) uniform sampler2D inSSAO;
 layout (set = 1, binding = 8) uniform samplerCube inIrradianceMap;
 layout (set = 1, binding = 9) uniform samplerCube inPrefilterMap;
 layout (set = 1, binding = 10) uniform sampler2D inBRDFLUT;
+layout (set = 1, binding = 11) uniform usampler2D inMaterial;
+layout (set = 1, binding = 12) uniform usampler2D inMaterialExt;
 
 layout (set = 2, binding = 0) uniform UBO_view
 {
@@ -33,6 +36,8 @@ layout (set = 2, binding = 0) uniform UBO_view
     mat4 light_proj_view;
     vec3 eye_pos;
     uint num_lights;
+    float shadow_bias;
+    uint tonemap;
 } view;
 
 layout (location = 0) in vec2 fragTexCoord;
@@ -64,7 +69,7 @@ float CalculateShadow(vec3 worldPos) {
     if (projCoords.z > 1.0) return 1.0;// 超出远裁剪面
 
     // 获取当前深度
-    float currentDepth = projCoords.z - 0.000005;// Shadow Bias
+    float currentDepth = projCoords.z - view.shadow_bias;// Shadow Bias
 
     // PCF采样
     float shadow = 0.0;
@@ -92,28 +97,54 @@ void main() {
     float ssao = texture(inSSAO, fragTexCoord).r;
     vec3 emissive_color = texture(inEmissive, fragTexCoord).rgb;
 
-    PixelParams pixel;
-    pixel.position = position;
-    pixel.baseColor = diffuse_color;
-    pixel.normal = normal;
-    pixel.metallic = metallic;
-    pixel.roughness = roughness;
-    pixel.occlusion = occlusion;
-
-    vec3 Lo = vec3(0.0);
-
-    for (int i = 0; i < view.num_lights; i++)
+    // Unlit materials show their base color as is, without lights, shadows or occlusion.
+    ivec2 texel = ivec2(fragTexCoord * textureSize(inMaterial, 0));
+    uint material_id = unpackMaterialId(texelFetch(inORM, texel, 0).a);
+    vec3 color = diffuse_color;
+    if (material_id != MATERIAL_ID_UNLIT)
     {
-        Lo += surfaceShading(pixel, lightsSSBO.lights[i], view.eye_pos.xyz, 1.0f);
+        PixelParams pixel;
+        pixel.position = position;
+        pixel.baseColor = diffuse_color;
+        pixel.normal = normal;
+        pixel.metallic = metallic;
+        pixel.roughness = roughness;
+        pixel.occlusion = occlusion;
+
+        MaterialLayers layers = unpackMaterialLayers(texelFetch(inMaterial, texel, 0), texelFetch(inMaterialExt, texel, 0).xy);
+        pixel.clearcoat = layers.clearcoat;
+        pixel.clearcoatRoughness = layers.clearcoatRoughness;
+        pixel.clearcoatNormal = layers.clearcoatNormal;
+        pixel.sheenColor = layers.sheenColor;
+        pixel.sheenRoughness = layers.sheenRoughness;
+        pixel.specularF0 = layers.specularF0;
+        pixel.specularWeight = layers.specularWeight;
+        pixel.anisotropy = layers.anisotropy;
+        pixel.anisotropyDirection = layers.anisotropyDirection;
+        pixel.iridescence = layers.iridescence;
+        pixel.iridescenceIor = layers.iridescenceIor;
+        pixel.iridescenceThickness = layers.iridescenceThickness;
+
+        vec3 Lo = vec3(0.0);
+
+        for (int i = 0; i < view.num_lights; i++)
+        {
+            Lo += surfaceShading(pixel, lightsSSBO.lights[i], view.eye_pos.xyz, 1.0f);
+        }
+
+        vec3 ambient = imageBasedLighting(pixel, view.eye_pos.xyz, inIrradianceMap, inPrefilterMap, inBRDFLUT);
+
+        float shadow = CalculateShadow(position);
+        color = (ambient + Lo * shadow) * ssao;
     }
 
-    vec3 ambient = imageBasedLighting(pixel, view.eye_pos.xyz, inIrradianceMap, inPrefilterMap, inBRDFLUT);
-
-    float shadow = CalculateShadow(position);
-    vec3 color = (ambient + Lo * shadow) * ssao;
-
     color += emissive_color;
-    color = ToneMappingUnreal(color);
+    // 0 = Unreal, 1 = Reinhard, 2 = Uncharted 2
+    switch (view.tonemap) {
+        case 1u: color = ToneMappingReinhard(color); break;
+        case 2u: color = ToneMappingUncharted2(color); break;
+        default: color = ToneMappingUnreal(color); break;
+    }
 
     outColor = vec4(color, 1.0f);
 }
@@ -0,0 +1,191 @@
+#version 460
+#extension GL_EXT_nonuniform_qualifier: require
+#extension GL_GOOGLE_include_directive: require
+#extension GL_EXT_scalar_block_layout: enable
+
+#include "include/bindless.glsl"
+#include "include/pbr.glsl"
+#include "include/tonemap.glsl"
+#include "include/utility.glsl"
+
+layout (location = 0) in vec2 uv0;
+layout (location = 1) in vec2 uv1;
+layout (location = 2) in vec3 in_normal;
+layout (location = 3) in vec4 in_tangent;
+layout (location = 4) in vec3 in_model_space_pos;
+layout (location = 5) in vec4 in_clip_space_pos;
+layout (location = 6) in vec4 in_prev_clip_space_pos;
+layout (location = 7) in vec3 in_bitangent;
+layout (location = 8) in mat3 in_tbn;
+
+layout (location = 0) out vec4 outColor;
+
+layout (push_constant) uniform PushConsts {
+    mat4 world;
+    mat4 normal_matrix;
+    uint mat_index;
+    ivec3 pad;
+} pushConsts;
+
+layout (scalar, set = 3, binding = 0) readonly buffer LightsSSBO
+{
+    GpuLight lights[];
+} lightsSSBO;
+
+layout (set = 3, binding = 1) uniform samplerCube inIrradianceMap;
+layout (set = 3, binding = 2) uniform samplerCube inPrefilterMap;
+layout (set = 3, binding = 3) uniform sampler2D inBRDFLUT;
+// Mip-chained copy of the lit and tonemapped opaque scene
+layout (set = 3, binding = 4) uniform sampler2D inSceneColor;
+
+layout (set = 3, binding = 5) uniform UBO_view
+{
+    vec3 eye_pos;
+    uint num_lights;
+    uint tonemap;
+} view;
+
+vec3 toneMap(vec3 color)
+{
+    // 0 = Unreal, 1 = Reinhard, 2 = Uncharted 2, same as the shading pass
+    switch (view.tonemap) {
+        case 1u: return ToneMappingReinhard(color);
+        case 2u: return ToneMappingUncharted2(color);
+        default: return ToneMappingUnreal(color);
+    }
+}
+
+vec3 directAndAmbientLighting(const PixelParams pixel)
+{
+    vec3 color = vec3(0.0);
+    for (int i = 0; i < view.num_lights; i++)
+    {
+        color += surfaceShading(pixel, lightsSSBO.lights[i], view.eye_pos, 1.0f);
+    }
+    return color + imageBasedLighting(pixel, view.eye_pos, inIrradianceMap, inPrefilterMap, inBRDFLUT);
+}
+
+void main() {
+    GltfMaterialGPU material = materialsSSBO.materials[pushConsts.mat_index];
+
+    vec2 base_color_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_BASE_COLOR], material.base_color_uv_set, uv0, uv1);
+    vec4 diffuse_color = texture(nonuniformEXT(sampledTextures[material.base_color_map]), base_color_uv);
+    diffuse_color.rgb = toLinear(diffuse_color.rgb);
+    diffuse_color *= material.base_color_factor;
+
+    vec2 metallic_roughness_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_METALLIC_ROUGHNESS], material.metallic_roughness_uv_set, uv0, uv1);
+    vec4 metallic_roughness = texture(nonuniformEXT(sampledTextures[material.metallic_roughness_map]), metallic_roughness_uv);
+    float metallic = metallic_roughness.b * material.metallic_factor;
+    float roughness = metallic_roughness.g * material.roughness_factor;
+
+    vec2 occlusion_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_OCCLUSION], material.occlusion_uv_set, uv0, uv1);
+    float occlusion = texture(nonuniformEXT(sampledTextures[material.occlusion_map]), occlusion_uv).r;
+
+    vec2 emissive_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_EMISSIVE], material.emissive_uv_set, uv0, uv1);
+    vec3 emissive = toLinear(texture(nonuniformEXT(sampledTextures[material.emissive_map]), emissive_uv).rgb);
+    emissive *= material.emissive_factor.rgb * material.emissive_factor.a;
+
+    vec3 normal = normalize(in_normal);
+    if (in_tangent.xyz != vec3(0.0f))
+    {
+        vec2 normal_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_NORMAL], material.normal_uv_set, uv0, uv1);
+        vec3 normal_map = texture(nonuniformEXT(sampledTextures[material.normal_map]), normal_uv).xyz;
+        normal = normalize(in_tbn * normalize(normal_map * 2.0 - 1.0));
+    }
+
+    vec2 transmission_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_TRANSMISSION], material.transmission_uv_set, uv0, uv1);
+    float transmission = material.transmission_factors.x * texture(nonuniformEXT(sampledTextures[material.transmission_map]), transmission_uv).r;
+
+    vec2 thickness_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_THICKNESS], material.thickness_uv_set, uv0, uv1);
+    float thickness = material.transmission_factors.y * texture(nonuniformEXT(sampledTextures[material.thickness_map]), thickness_uv).g;
+    float ior = material.transmission_factors.z;
+
+    vec2 specular_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_SPECULAR], material.specular_uv_set, uv0, uv1);
+    vec2 specular_color_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_SPECULAR_COLOR], material.specular_color_uv_set, uv0, uv1);
+    vec3 specular_color = material.specular_factors.rgb * toLinear(texture(nonuniformEXT(sampledTextures[material.specular_color_map]), specular_color_uv).rgb);
+    float specular_weight = material.specular_factors.a * texture(nonuniformEXT(sampledTextures[material.specular_map]), specular_uv).a;
+
+    vec2 iridescence_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_IRIDESCENCE], material.iridescence_uv_set, uv0, uv1);
+    vec2 iridescence_thickness_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_IRIDESCENCE_THICKNESS], material.iridescence_thickness_uv_set, uv0, uv1);
+    float iridescence = material.iridescence_factors.x * texture(nonuniformEXT(sampledTextures[material.iridescence_map]), iridescence_uv).r;
+    float iridescence_thickness = mix(material.iridescence_factors.z, material.iridescence_factors.w, texture(nonuniformEXT(sampledTextures[material.iridescence_thickness_map]), iridescence_thickness_uv).g);
+
+    // The anisotropy direction is given in tangent space and needs tangents, like the normal map.
+    float anisotropy = 0.0;
+    vec3 anisotropy_direction = vec3(1.0, 0.0, 0.0);
+    if (material.anisotropy_factors.x > 0.0 && in_tangent.xyz != vec3(0.0f))
+    {
+        vec2 direction = vec2(1.0, 0.0);
+        anisotropy = material.anisotropy_factors.x;
+        if (material.anisotropy_factors.z > 0.0)
+        {
+            vec2 anisotropy_uv = transformUv(material.uv_transforms[TEXTURE_SLOT_ANISOTROPY], material.anisotropy_uv_set, uv0, uv1);
+            vec3 anisotropy_map = texture(nonuniformEXT(sampledTextures[material.anisotropy_map]), anisotropy_uv).rgb;
+            direction = anisotropy_map.rg * 2.0 - 1.0;
+            anisotropy *= anisotropy_map.b;
+        }
+        float rotation = material.anisotropy_factors.y;
+        direction = mat2(cos(rotation), sin(rotation), -sin(rotation), cos(rotation)) * direction;
+        anisotropy_direction = normalize(in_tbn * vec3(direction, 0.0));
+    }
+
+    vec3 world_pos = (pushConsts.world * vec4(in_model_space_pos, 1.0)).xyz;
+
+    PixelParams pixel;
+    pixel.position = world_pos;
+    pixel.baseColor = diffuse_color.rgb;
+    pixel.normal = normal;
+    pixel.metallic = metallic;
+    pixel.roughness = roughness;
+    pixel.occlusion = occlusion;
+    pixel.clearcoat = 0.0;
+    pixel.clearcoatRoughness = 0.0;
+    pixel.clearcoatNormal = normal;
+    pixel.sheenColor = vec3(0.0);
+    pixel.sheenRoughness = 0.0;
+    pixel.specularF0 = dielectricF0(ior, specular_color);
+    pixel.specularWeight = specular_weight;
+    pixel.anisotropy = anisotropy;
+    pixel.anisotropyDirection = anisotropy_direction;
+    pixel.iridescence = iridescence;
+    pixel.iridescenceIor = material.iridescence_factors.y;
+    pixel.iridescenceThickness = iridescence_thickness;
+
+    // The opaque surface, for the parts which do not transmit
+    vec3 opaque = toneMap(directAndAmbientLighting(pixel) + emissive);
+
+    // Specular reflection of the dielectric interface, the transmitted light replaces its diffuse part.
+    PixelParams dielectric = pixel;
+    dielectric.baseColor = vec3(0.0);
+    dielectric.metallic = 0.0;
+    vec3 reflection = toneMap(directAndAmbientLighting(dielectric) + emissive);
+
+    // Refract the view ray and follow it through the volume, the thickness is given in the
+    // space of the mesh. Thin-walled materials have no thickness and refract in place.
+    vec3 V = normalize(view.eye_pos - world_pos);
+    vec3 model_scale = vec3(length(pushConsts.world[0].xyz), length(pushConsts.world[1].xyz), length(pushConsts.world[2].xyz));
+    vec3 transmission_ray = normalize(refract(-V, normal, 1.0 / ior)) * thickness * model_scale;
+
+    vec4 exit_clip_pos = projview.projection * projview.view * vec4(world_pos + transmission_ray, 1.0);
+    vec2 refraction_uv = exit_clip_pos.xy / exit_clip_pos.w * 0.5 + 0.5;
+
+    // Rough surfaces read blurrier mips, an IOR of 1 refracts nothing and is never blurred.
+    float refraction_roughness = roughness * clamp(ior * 2.0 - 2.0, 0.0, 1.0);
+    float lod = log2(float(textureSize(inSceneColor, 0).x)) * refraction_roughness;
+    vec3 scene_color = textureLod(inSceneColor, refraction_uv, lod).rgb;
+
+    // Beer-Lambert absorption through the volume, an infinite distance absorbs nothing.
+    vec3 attenuation = vec3(1.0);
+    if (thickness > 0.0)
+    {
+        attenuation = pow(material.attenuation.rgb, vec3(length(transmission_ray) / material.attenuation.a));
+    }
+
+    vec3 F = specular_weight * fresnelSchlickRoughness(max(dot(normal, V), 0.0), pixel.specularF0, roughness);
+    vec3 transmittance = diffuse_color.rgb * attenuation * (1.0 - F);
+
+    // The scene copy is already tonemapped, so the linear transmittance is moved to display gamma.
+    vec3 transmitted = reflection + scene_color * pow(transmittance, vec3(1.0 / GAMMA));
+
+    outColor = vec4(mix(opaque, transmitted, transmission * (1.0 - metallic)), 1.0);
+}
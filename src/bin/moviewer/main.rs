@@ -0,0 +1,551 @@
+//! Standalone glTF model viewer
+mod options;
+
+use bevy_ecs::prelude::*;
+use bevy_math::Vec3;
+use mo_core::App;
+use mo_ecs::{
+    asset::{AssetServer, ELoadState, Handle},
+    color::{EColorSpace, FColor},
+    component::{AnimationPlayer, DirectionalLight, MaterialVariant, SceneRoot, Transform},
+    model::Model,
+    resource::{
+        camera::CameraSettings,
+        console::CVAR_CAMERA_SPEED,
+        input::{EInputEvent, FKeyEvent},
+        Camera, Console, DebugUi, DefaultTextures, FramePacing, GlobalSamplers, IBLResource,
+        Input, Timer,
+    },
+    system::{load_dropped_files, play_animations},
+};
+use mo_vk::WindowDescriptor;
+use options::{Command, Options, USAGE};
+use std::{collections::HashMap, time::Duration};
+use winit::{
+    event_loop::EventLoop,
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+/// Direction from the model to the camera when the start position is not given
+const DEFAULT_VIEW_DIRECTION: Vec3 = Vec3::new(-1.0, 0.5, 1.0);
+const ENVIRONMENT_DIR: &str = "resources/tex/env";
+/// Blend time when switching to the next animation
+const ANIMATION_CROSS_FADE: Duration = Duration::from_millis(300);
+
+/// Model shown by the viewer, framed by the camera once it finished loading
+#[derive(Resource)]
+struct ViewedModel {
+    handle: Handle<Model>,
+    entity: Entity,
+    camera_position: Option<Vec3>,
+    camera_target: Option<Vec3>,
+    /// Name or index of the camera of the model to start from
+    camera_preset: Option<String>,
+    /// Index into [`Model::cameras`] of the camera in use, `None` while framing the model
+    current_camera: Option<usize>,
+    framed: bool,
+}
+
+impl ViewedModel {
+    /// This system frames the camera and places the lights around the model once it is loaded.
+    fn frame_model(
+        mut viewed_model: ResMut<ViewedModel>,
+        asset_server: Res<AssetServer>,
+        mut camera: ResMut<Camera>,
+        mut console: ResMut<Console>,
+        mut lights: Query<(&mut DirectionalLight, &ViewerLight)>,
+    ) {
+        if viewed_model.framed {
+            return;
+        }
+
+        match asset_server.load_state(&viewed_model.handle) {
+            ELoadState::Loading => return,
+            ELoadState::Failed => {
+                tracing::error!("Viewer - Nothing to frame, the model failed to load.");
+                viewed_model.framed = true;
+                return;
+            }
+            ELoadState::Loaded => {}
+        }
+
+        let model = asset_server.get(&viewed_model.handle);
+        let (min, max) = model
+            .and_then(|model| model.bounds())
+            .unwrap_or((Vec3::splat(-1.0), Vec3::splat(1.0)));
+        let center = (min + max) * 0.5;
+        let radius = ((max - min) * 0.5).length().max(0.01);
+        tracing::info!(
+            "Viewer - Loaded {} with bounds {min} .. {max}",
+            viewed_model.handle.path()
+        );
+
+        frame_camera(
+            &mut camera,
+            viewed_model.camera_position,
+            viewed_model.camera_target,
+            center,
+            radius,
+        );
+        if let Some(preset) = viewed_model.camera_preset.clone() {
+            let index = model.and_then(|model| {
+                // An index unless a camera is named like one
+                model.find_camera(&preset).or_else(|| {
+                    preset
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|&index| index < model.cameras.len())
+                })
+            });
+            match (model, index) {
+                (Some(model), Some(index)) => {
+                    // The model stays at the origin.
+                    camera.set_preset(&model.cameras[index], &Transform::IDENTITY);
+                    viewed_model.current_camera = Some(index);
+                }
+                _ => tracing::warn!("Viewer - The model has no camera {preset}, framing it."),
+            }
+        }
+        // Movement speed relative to the model size
+        console.set(CVAR_CAMERA_SPEED, radius * 0.01).ok();
+        for (mut light, viewer_light) in &mut lights {
+            viewer_light.place(&mut light, center, radius);
+        }
+
+        viewed_model.framed = true;
+    }
+
+    /// This system switches to the next camera of the model on C, after the last one the model
+    /// is framed again.
+    fn cycle_cameras(
+        mut viewed_model: ResMut<ViewedModel>,
+        input: Res<Input>,
+        console: Option<Res<Console>>,
+        asset_server: Res<AssetServer>,
+        mut camera: ResMut<Camera>,
+    ) {
+        // Keys typed into the console are not viewer shortcuts
+        if console.is_some_and(|console| console.is_open()) {
+            return;
+        }
+
+        let pressed = input.events.iter().any(|event| {
+            matches!(
+                event,
+                EInputEvent::Key(FKeyEvent {
+                    key_code: PhysicalKey::Code(KeyCode::KeyC),
+                    pressed: true,
+                    ..
+                })
+            )
+        });
+        let Some(model) = asset_server.get(&viewed_model.handle) else {
+            return;
+        };
+        if !pressed || model.cameras.is_empty() {
+            return;
+        }
+
+        let next = viewed_model.current_camera.map_or(0, |index| index + 1);
+        if next < model.cameras.len() {
+            let preset = &model.cameras[next];
+            camera.set_preset(preset, &Transform::IDENTITY);
+            viewed_model.current_camera = Some(next);
+            tracing::info!(
+                "Viewer - Camera {next} {}",
+                preset.name.as_deref().unwrap_or_default()
+            );
+        } else {
+            // Frame the model again
+            viewed_model.camera_preset = None;
+            viewed_model.current_camera = None;
+            viewed_model.framed = false;
+        }
+    }
+
+    /// This system switches to the next material variant of the model on V, after the last one
+    /// the default materials are shown again.
+    fn cycle_variants(
+        viewed_model: Res<ViewedModel>,
+        input: Res<Input>,
+        console: Option<Res<Console>>,
+        asset_server: Res<AssetServer>,
+        mut variants: Query<&mut MaterialVariant>,
+    ) {
+        // Keys typed into the console are not viewer shortcuts
+        if console.is_some_and(|console| console.is_open()) {
+            return;
+        }
+
+        let pressed = input.events.iter().any(|event| {
+            matches!(
+                event,
+                EInputEvent::Key(FKeyEvent {
+                    key_code: PhysicalKey::Code(KeyCode::KeyV),
+                    pressed: true,
+                    ..
+                })
+            )
+        });
+        let Some(model) = asset_server.get(&viewed_model.handle) else {
+            return;
+        };
+        let Ok(mut variant) = variants.get_mut(viewed_model.entity) else {
+            return;
+        };
+        if !pressed || model.variants.is_empty() {
+            return;
+        }
+
+        let next = variant.0.map_or(0, |index| index + 1);
+        variant.0 = (next < model.variants.len()).then_some(next);
+        tracing::info!(
+            "Viewer - Material variant {}",
+            variant
+                .0
+                .map_or("default", |index| model.variants[index].as_str())
+        );
+    }
+
+    /// This system pauses the animation of the model on space and cross-fades to the next
+    /// animation on N.
+    fn control_animation(
+        viewed_model: Res<ViewedModel>,
+        input: Res<Input>,
+        console: Option<Res<Console>>,
+        asset_server: Res<AssetServer>,
+        mut players: Query<&mut AnimationPlayer>,
+    ) {
+        // Keys typed into the console are not viewer shortcuts
+        if console.is_some_and(|console| console.is_open()) {
+            return;
+        }
+
+        let Ok(mut player) = players.get_mut(viewed_model.entity) else {
+            return;
+        };
+        let count = asset_server
+            .get(&viewed_model.handle)
+            .map_or(0, |model| model.animations.len());
+
+        for event in &input.events {
+            match event {
+                EInputEvent::Key(FKeyEvent {
+                    key_code: PhysicalKey::Code(KeyCode::Space),
+                    pressed: true,
+                    ..
+                }) => {
+                    if player.is_paused() {
+                        player.resume();
+                    } else {
+                        player.pause();
+                    }
+                }
+                EInputEvent::Key(FKeyEvent {
+                    key_code: PhysicalKey::Code(KeyCode::KeyN),
+                    pressed: true,
+                    ..
+                }) if count > 0 => {
+                    let next = player.current_clip().map_or(0, |clip| (clip + 1) % count);
+                    player.cross_fade(next, ANIMATION_CROSS_FADE);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Light placed around the model from `direction`
+#[derive(Component)]
+struct ViewerLight {
+    direction: Vec3,
+}
+
+impl ViewerLight {
+    fn place(&self, light: &mut DirectionalLight, center: Vec3, radius: f32) {
+        let position = center + self.direction.normalize() * radius * 3.0;
+        light.transform = Transform::from_translation(position).looking_at(center, Vec3::Y);
+        light.shadow_width = radius.max(1.0) * 1.5;
+        light.shadow_height = radius.max(1.0) * 1.5;
+    }
+}
+
+/// Environment maps the viewer can switch between
+#[derive(Resource)]
+struct Environments {
+    names: Vec<String>,
+    current: usize,
+    /// Keeps visited environments loaded so switching back is instant
+    loaded: HashMap<String, Handle<IBLResource>>,
+    /// Index of the environment being loaded, it replaces the current one when ready
+    pending: Option<usize>,
+}
+
+impl Environments {
+    /// Environments from the command line first, then the ones found in [`ENVIRONMENT_DIR`]
+    fn new(mut names: Vec<String>) -> Self {
+        let mut found = std::fs::read_dir(ENVIRONMENT_DIR)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path())
+                    .filter(|path| path.extension().is_some_and(|extension| extension == "hdr"))
+                    .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        found.sort();
+
+        for name in found {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        if names.is_empty() {
+            names.push("default_sky".to_string());
+        }
+
+        Self {
+            names,
+            current: 0,
+            loaded: HashMap::new(),
+            pending: None,
+        }
+    }
+
+    fn current(&self) -> &str {
+        &self.names[self.current]
+    }
+
+    /// This system switches the environment with `[` and `]` or the debug UI.
+    fn update_environment(
+        mut environments: ResMut<Environments>,
+        mut ibl: ResMut<IBLResource>,
+        mut asset_server: ResMut<AssetServer>,
+        input: Res<Input>,
+        console: Option<Res<Console>>,
+        debug_ui: Option<Res<DebugUi>>,
+    ) {
+        // An environment map dropped on the window becomes selectable too
+        if ibl.name != environments.current() {
+            let name = ibl.name.clone();
+            environments.current = match environments.names.iter().position(|n| *n == name) {
+                Some(index) => index,
+                None => {
+                    environments.names.push(name);
+                    environments.names.len() - 1
+                }
+            };
+        }
+
+        let count = environments.names.len();
+        let target = environments.pending.unwrap_or(environments.current);
+        let mut selected = target;
+
+        // Keys typed into the console are not viewer shortcuts
+        let keyboard_enabled = !console.is_some_and(|console| console.is_open());
+        for event in input.events.iter().filter(|_| keyboard_enabled) {
+            match event {
+                EInputEvent::Key(FKeyEvent {
+                    key_code: PhysicalKey::Code(KeyCode::BracketLeft),
+                    pressed: true,
+                    ..
+                }) => selected = (selected + count - 1) % count,
+                EInputEvent::Key(FKeyEvent {
+                    key_code: PhysicalKey::Code(KeyCode::BracketRight),
+                    pressed: true,
+                    ..
+                }) => selected = (selected + 1) % count,
+                _ => {}
+            }
+        }
+
+        if let Some(debug_ui) = debug_ui.filter(|debug_ui| debug_ui.is_visible()) {
+            egui::Window::new("Environment")
+                .anchor(egui::Align2::RIGHT_TOP, [-8.0, 8.0])
+                .resizable(false)
+                .show(debug_ui.context(), |ui| {
+                    egui::ComboBox::from_id_salt("environment")
+                        .selected_text(environments.current())
+                        .show_ui(ui, |ui| {
+                            for (index, name) in environments.names.iter().enumerate() {
+                                ui.selectable_value(&mut selected, index, name);
+                            }
+                        });
+                });
+        }
+
+        if selected == environments.current {
+            environments.pending = None;
+        } else if selected != target {
+            let name = environments.names[selected].clone();
+            tracing::info!("Viewer - Switching environment to {name}");
+            let handle = asset_server.load::<IBLResource>(&name);
+            environments.loaded.insert(name, handle);
+            environments.pending = Some(selected);
+        }
+
+        let Some(pending) = environments.pending else {
+            return;
+        };
+        let handle = environments.loaded[&environments.names[pending]].clone();
+        match asset_server.load_state(&handle) {
+            ELoadState::Loading => {}
+            ELoadState::Loaded => {
+                if let Some(environment) = asset_server.get(&handle) {
+                    *ibl = environment.clone();
+                }
+                environments.current = pending;
+                environments.pending = None;
+            }
+            ELoadState::Failed => {
+                let name = environments.names[pending].clone();
+                environments.loaded.remove(&name);
+                environments.pending = None;
+            }
+        }
+    }
+}
+
+fn main() {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(Command::Run(options)) => options,
+        Ok(Command::Help) => {
+            println!("{USAGE}");
+            return;
+        }
+        Err(err) => {
+            eprintln!("error: {err}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
+    let file_name = options
+        .model
+        .file_name()
+        .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+    let window_descriptor = WindowDescriptor {
+        title: format!("moviewer - {file_name}"),
+        width: options.width,
+        height: options.height,
+        ..Default::default()
+    };
+
+    let event_loop = EventLoop::new().unwrap();
+    let mut app = App::new(&event_loop, window_descriptor);
+
+    app.init_resource::<Timer>();
+    app.init_resource::<Input>();
+    app.init_resource::<DebugUi>();
+    app.insert_resource(FramePacing::on_demand());
+
+    app.init_resource::<DefaultTextures>();
+    app.init_resource::<GlobalSamplers>();
+
+    let environments = Environments::new(options.environments.clone());
+    tracing::info!("Viewer - Environment {}", environments.current());
+    app.insert_resource(IBLResource::new(environments.current()));
+    app.insert_resource(environments);
+
+    app.add_startup_system(Console::run_startup_script);
+    app.add_runtime_system(
+        (
+            Timer::update_timer,
+            Console::update_console,
+            ViewedModel::control_animation,
+            ViewedModel::cycle_cameras,
+            ViewedModel::cycle_variants,
+            play_animations,
+            DebugUi::toggle_visibility,
+            DebugUi::inspector,
+            load_dropped_files,
+            ViewedModel::frame_model,
+            Environments::update_environment,
+            Camera::update_camera,
+        )
+            .chain(),
+    );
+
+    // The model loads in the background, until then the view is framed around the placeholder.
+    // The first animation of the model plays in a loop, if it has any.
+    let model = app.load_asset::<Model>(&options.model.to_string_lossy());
+    let mut animation_player = AnimationPlayer::new();
+    animation_player.play(0);
+    let entity = app.add_entity((
+        Transform::IDENTITY,
+        SceneRoot(model.clone()),
+        MaterialVariant::default(),
+        animation_player,
+    ));
+    app.insert_resource(ViewedModel {
+        handle: model,
+        entity,
+        camera_position: options.camera_position,
+        camera_target: options.camera_target,
+        camera_preset: options.camera_preset,
+        current_camera: None,
+        framed: false,
+    });
+
+    let mut camera = Camera::default();
+    frame_camera(
+        &mut camera,
+        options.camera_position,
+        options.camera_target,
+        Vec3::ZERO,
+        1.0,
+    );
+    app.insert_resource(camera);
+    app.init_resource::<Console>();
+
+    add_lights(&mut app);
+
+    event_loop
+        .run_app(&mut app)
+        .expect("Run Application Failed");
+}
+
+/// Places the camera so that the bounding sphere fills the view, unless the pose was given. The
+/// camera keeps the aspect ratio of the window.
+fn frame_camera(
+    camera: &mut Camera,
+    camera_position: Option<Vec3>,
+    camera_target: Option<Vec3>,
+    center: Vec3,
+    radius: f32,
+) {
+    let fov = 45.0_f32.to_radians();
+    let distance = radius / (fov * 0.5).sin();
+
+    let target = camera_target.unwrap_or(center);
+    let position =
+        camera_position.unwrap_or_else(|| target + DEFAULT_VIEW_DIRECTION.normalize() * distance);
+
+    camera.set_transform(*Camera::looking_at(position, target).transform());
+    camera.set_settings(CameraSettings::Perspective {
+        fov,
+        near: (radius * 0.01).min(0.1),
+        far: (position.distance(center) + radius * 4.0).max(1000.0),
+    });
+}
+
+/// A key light casting shadows and a fill light, both around the model
+fn add_lights(app: &mut App) {
+    let lights = [
+        (Vec3::new(1.0, 1.0, 1.0), 4.0, true),
+        (Vec3::new(-1.0, 1.0, 1.0), 2.0, false),
+    ];
+
+    for (direction, intensity, is_shadow_caster) in lights {
+        let viewer_light = ViewerLight { direction };
+        let mut light = DirectionalLight::new(
+            Transform::IDENTITY,
+            FColor::white(),
+            EColorSpace::Linear,
+            intensity,
+            is_shadow_caster,
+        );
+        viewer_light.place(&mut light, Vec3::ZERO, 1.0);
+        app.add_entity((light, viewer_light));
+    }
+}
@@ -0,0 +1,147 @@
+//! Command line options of the model viewer
+use bevy_math::Vec3;
+use mo_ecs::{resource::IBLResource, system::drag_drop::is_model_file};
+use std::path::{Path, PathBuf};
+
+pub const USAGE: &str = "\
+Usage: moviewer <MODEL> [OPTIONS]
+
+Arguments:
+  <MODEL>                       glTF or GLB file to view
+
+Options:
+  -e, --env <ENV>               Environment map, either a name in resources/tex/env or an
+                                .hdr/.exr file. Can be repeated, the first one is shown first
+  -s, --size <WIDTH>x<HEIGHT>   Window size in logical pixels [default: 1280x720]
+  -p, --camera-position <X,Y,Z> Camera start position [default: framed from the model bounds]
+  -t, --camera-target <X,Y,Z>   Point the camera looks at [default: center of the model bounds]
+  -c, --camera <NAME|INDEX>     Start from a camera of the model instead of framing it
+  -h, --help                    Print this help
+
+Controls:
+  WASD / Q / E                  Move the camera
+  Right mouse button            Look around
+  [ / ]                         Previous / next environment
+  Space / N                     Pause the animation / cross-fade to the next one
+  C                             Switch to the next camera of the model
+  V                             Switch to the next material variant of the model
+  Drop .glb/.gltf or .hdr/.exr  Add a model or switch the environment
+  F1                            Toggle the debug UI
+  `                             Toggle the console";
+
+/// Parsed command line options
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub model: PathBuf,
+    pub environments: Vec<String>,
+    pub width: f32,
+    pub height: f32,
+    pub camera_position: Option<Vec3>,
+    pub camera_target: Option<Vec3>,
+    /// Name or index of a camera of the model
+    pub camera_preset: Option<String>,
+}
+
+/// Result of parsing the command line
+pub enum Command {
+    Run(Options),
+    Help,
+}
+
+impl Options {
+    /// Parses the arguments, without the program name
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+        let mut model = None;
+        let mut environments = Vec::new();
+        let mut size = (1280.0, 720.0);
+        let mut camera_position = None;
+        let mut camera_target = None;
+        let mut camera_preset = None;
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .ok_or_else(|| format!("missing value for {name}"))
+            };
+
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(Command::Help),
+                "-e" | "--env" => environments.push(parse_environment(&value(&arg)?)?),
+                "-s" | "--size" => size = parse_size(&value(&arg)?)?,
+                "-p" | "--camera-position" => camera_position = Some(parse_vec3(&value(&arg)?)?),
+                "-t" | "--camera-target" => camera_target = Some(parse_vec3(&value(&arg)?)?),
+                "-c" | "--camera" => camera_preset = Some(value(&arg)?),
+                _ if arg.starts_with('-') => return Err(format!("unknown option {arg}")),
+                _ if model.is_some() => return Err(format!("unexpected argument {arg}")),
+                _ => model = Some(parse_model(&arg)?),
+            }
+        }
+
+        let model = model.ok_or_else(|| "missing model path".to_string())?;
+
+        Ok(Command::Run(Options {
+            model,
+            environments,
+            width: size.0,
+            height: size.1,
+            camera_position,
+            camera_target,
+            camera_preset,
+        }))
+    }
+}
+
+fn parse_model(arg: &str) -> Result<PathBuf, String> {
+    let path = Path::new(arg);
+    if !is_model_file(path) {
+        return Err(format!("{arg} is not a .gltf or .glb file"));
+    }
+    if !path.is_file() {
+        return Err(format!("model {arg} does not exist"));
+    }
+
+    // Relative paths would be resolved against resources/gltf by the loader.
+    std::path::absolute(path).map_err(|err| format!("invalid model path {arg}: {err}"))
+}
+
+fn parse_environment(arg: &str) -> Result<String, String> {
+    if IBLResource::is_environment_file(arg) {
+        if !Path::new(arg).is_file() {
+            return Err(format!("environment map {arg} does not exist"));
+        }
+        return Ok(arg.to_string());
+    }
+
+    let path = Path::new("resources/tex/env").join(format!("{arg}.hdr"));
+    if !path.is_file() {
+        return Err(format!("unknown environment {arg}, {} does not exist", path.display()));
+    }
+    Ok(arg.to_string())
+}
+
+fn parse_size(arg: &str) -> Result<(f32, f32), String> {
+    let invalid = || format!("invalid window size {arg}, expected <WIDTH>x<HEIGHT>");
+
+    let (width, height) = arg.split_once(['x', 'X']).ok_or_else(invalid)?;
+    let width = width.trim().parse::<u32>().map_err(|_| invalid())?;
+    let height = height.trim().parse::<u32>().map_err(|_| invalid())?;
+    if width == 0 || height == 0 {
+        return Err(invalid());
+    }
+
+    Ok((width as f32, height as f32))
+}
+
+fn parse_vec3(arg: &str) -> Result<Vec3, String> {
+    let components = arg
+        .split(',')
+        .map(|component| component.trim().parse::<f32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("invalid vector {arg}: {err}"))?;
+
+    match components[..] {
+        [x, y, z] if x.is_finite() && y.is_finite() && z.is_finite() => Ok(Vec3::new(x, y, z)),
+        _ => Err(format!("invalid vector {arg}, expected <X,Y,Z>")),
+    }
+}